@@ -8,6 +8,10 @@ let timerId: number | null = null;
 let currentServiceIndex = 0;
 let serviceCount = 0;
 let busy = false;
+let ingestBuffer: any = null; // Reusable IngestBuffer owned by WASM
+let overlay: 'trace' | 'diff' | 'map' | null = null; // Waterfall, diff map or service map replacing the rotation
+let shownTrace: string | null = null; // Trace behind the 'trace' overlay
+let shownDiff: { before: DiffSide; after: DiffSide } | null = null; // Sides behind the 'diff' overlay
 
 const SERVICE_ROTATE_MS = 10000; // 10 seconds per service
 
@@ -20,6 +24,19 @@ interface InitMessage {
 interface AppendDataMessage {
   type: 'append';
   data: Uint8Array;
+  jobId?: string;
+  sourceKey?: string;
+  lastModified?: string;
+}
+
+interface RemoveSourceMessage {
+  type: 'remove_source';
+  sourceKey: string;
+}
+
+interface SetActiveJobMessage {
+  type: 'set_active_job';
+  jobId: string;
 }
 
 interface StartAnimationMessage {
@@ -30,7 +47,409 @@ interface StopAnimationMessage {
   type: 'stop_animation';
 }
 
-type WorkerMessage = InitMessage | AppendDataMessage | StartAnimationMessage | StopAnimationMessage;
+interface ExportSvgMessage {
+  type: 'export_svg';
+  requestId: number;
+}
+
+interface CapturePngMessage {
+  type: 'capture_png';
+  requestId: number;
+  width: number;
+  height: number;
+}
+
+interface RecordPlaybackMessage {
+  type: 'record_playback';
+  requestId: number;
+  options: RecordingOptions;
+}
+
+interface SetFilterMessage {
+  type: 'set_filter';
+  requestId: number;
+  expr: string;
+  mode?: FilterMode;
+}
+
+interface SearchMessage {
+  type: 'search';
+  requestId: number;
+  query: string;
+  limit?: number;
+}
+
+interface FocusServiceMessage {
+  type: 'focus_service';
+  requestId: number;
+  serviceId: string;
+  caption?: string;
+}
+
+interface FocusResultMessage {
+  type: 'focus_result';
+  requestId: number;
+  result: SearchResult;
+}
+
+interface ClearHighlightMessage {
+  type: 'clear_highlight';
+}
+
+interface ListTracesMessage {
+  type: 'list_traces';
+  requestId: number;
+  limit?: number;
+}
+
+interface ShowTraceMessage {
+  type: 'show_trace';
+  requestId: number;
+  traceId: string;
+}
+
+interface CloseTraceMessage {
+  type: 'close_trace';
+  clearPath?: boolean;
+}
+
+interface AddAnnotationMessage {
+  type: 'add_annotation';
+  requestId: number;
+  annotation: NewAnnotation;
+}
+
+interface RemoveAnnotationMessage {
+  type: 'remove_annotation';
+  requestId: number;
+  id: number;
+}
+
+interface JumpToAnnotationMessage {
+  type: 'jump_to_annotation';
+  requestId: number;
+  from?: number;
+  forward: boolean;
+}
+
+interface ExportAnnotationsMessage {
+  type: 'export_annotations';
+  requestId: number;
+}
+
+interface ImportAnnotationsMessage {
+  type: 'import_annotations';
+  requestId: number;
+  saved: Annotation[];
+}
+
+interface SetCameraMessage {
+  type: 'set_camera';
+  x: number;
+  y: number;
+  zoom: number;
+}
+
+interface SetThemeMessage {
+  type: 'set_theme';
+  requestId: number;
+  theme: ThemeName | Partial<Theme> | null;
+}
+
+export type ThemeName = 'dark' | 'light' | 'deuteranopia' | 'protanopia';
+
+export type Rgba = [number, number, number, number];
+
+export interface Theme {
+  background: Rgba;
+  text: Rgba;
+  highlight: Rgba;
+  node: { healthy: Rgba; warning: Rgba; error: Rgba };
+  edge: Rgba;
+  grid: Rgba;
+  pin: Rgba;
+  diff: { added: Rgba; removed: Rgba; unchanged: Rgba; worse: Rgba; better: Rgba };
+  trace: { bar: Rgba; error_bar: Rgba; critical: Rgba };
+  incident: Rgba;
+  note: Rgba;
+}
+
+interface SetLodOptionsMessage {
+  type: 'set_lod_options';
+  options: LodOptions | null;
+}
+
+export interface LodOptions {
+  enabled?: boolean;
+  min_label_px?: number;
+  point_radius_px?: number;
+  bundle_cell_px?: number;
+  hysteresis?: number;
+}
+
+interface SetPlaybackSpeedMessage {
+  type: 'set_playback_speed';
+  speed: number;
+}
+
+interface SaveSessionMessage {
+  type: 'save_session';
+  requestId: number;
+}
+
+interface LoadSessionMessage {
+  type: 'load_session';
+  requestId: number;
+  bytes: Uint8Array;
+}
+
+interface DiffReplaysMessage {
+  type: 'diff_replays';
+  requestId: number;
+  before: DiffSide;
+  after: DiffSide;
+}
+
+interface ShowDiffMessage {
+  type: 'show_diff';
+  requestId: number;
+  before: DiffSide;
+  after: DiffSide;
+}
+
+interface CloseDiffMessage {
+  type: 'close_diff';
+}
+
+interface ShowServiceMapMessage {
+  type: 'show_service_map';
+  requestId: number;
+}
+
+interface CloseMapMessage {
+  type: 'close_map';
+}
+
+interface PickNodeMessage {
+  type: 'pick_node';
+  requestId: number;
+  x: number;
+  y: number;
+}
+
+interface DragNodeMessage {
+  type: 'drag_node';
+  id: string;
+  x: number;
+  y: number;
+}
+
+interface PinNodeMessage {
+  type: 'pin_node';
+  requestId: number;
+  id: string;
+  x: number;
+  y: number;
+}
+
+interface UnpinNodeMessage {
+  type: 'unpin_node';
+  requestId: number;
+  id: string;
+}
+
+interface ExportLayoutMessage {
+  type: 'export_layout';
+  requestId: number;
+}
+
+interface ImportLayoutMessage {
+  type: 'import_layout';
+  requestId: number;
+  json: string;
+}
+
+interface SetEdgeBundlingMessage {
+  type: 'set_edge_bundling';
+  requestId: number;
+  strength: number;
+}
+
+interface SetGroupingMessage {
+  type: 'set_grouping';
+  requestId: number;
+  by: GroupBy | null;
+}
+
+interface CollapseGroupMessage {
+  type: 'collapse_group';
+  requestId: number;
+  name: string;
+  collapsed: boolean;
+}
+
+interface SetLabelRulesMessage {
+  type: 'set_label_rules';
+  requestId: number;
+  rules: LabelRules | null;
+}
+
+export interface LabelRules {
+  aliases?: Record<string, string>;
+  column?: string | null;
+  pattern?: string | null;
+  template?: string | null;
+  strip_prefixes?: string[];
+  strip_suffixes?: string[];
+}
+
+export type GroupBy = 'prefix' | { attribute: string };
+
+export interface ServiceGroup {
+  name: string;
+  members: string[];
+  collapsed: boolean;
+  status: string;
+  metrics: DiffMetrics;
+}
+
+export interface PickedNode {
+  kind: 'service' | 'group';
+  id: string;
+}
+
+export type AnnotationKind = 'deploy' | 'incident' | 'anomaly' | 'bookmark' | 'note';
+
+export interface NewAnnotation {
+  timestamp_ns: number;
+  end_ns?: number | null;
+  kind?: AnnotationKind;
+  label?: string;
+  service?: string | null;
+  severity?: 'warning' | 'error' | null;
+}
+
+export interface Annotation extends NewAnnotation {
+  id: number;
+  kind: AnnotationKind;
+  label: string;
+  source: 'user' | 'detected';
+}
+
+export interface AnnotationTimeline {
+  start_ns: number | null;
+  end_ns: number | null;
+  annotations: Annotation[];
+}
+
+export interface AnnotationJump {
+  annotation: Annotation;
+  service_index: number | null;
+}
+
+export interface TraceSummary {
+  trace_id: string;
+  span_count: number;
+  root_service: string;
+  start_ns: number;
+  duration_ns: number;
+}
+
+export interface PathSegment {
+  span_id: string;
+  service: string;
+  operation: string | null;
+  start_ns: number;
+  end_ns: number;
+}
+
+export interface CriticalPath {
+  trace_id: string;
+  root_span_id: string;
+  start_ns: number;
+  duration_ns: number;
+  segments: PathSegment[];
+  span_ids: string[];
+  services: Array<{ service: string; time_ns: number }>;
+}
+
+export interface DiffSide {
+  job: string;
+  start_ns?: number | null;
+  end_ns?: number | null;
+}
+
+export interface DiffMetrics {
+  spans: number;
+  errors: number;
+  rate_per_s: number | null;
+  error_rate: number;
+  mean_latency_ns: number | null;
+}
+
+export interface Delta {
+  change: 'added' | 'removed' | 'changed' | 'unchanged';
+  before: DiffMetrics | null;
+  after: DiffMetrics | null;
+  rate_delta: number | null;
+  error_rate_delta: number | null;
+  latency_delta_ns: number | null;
+  score: number;
+}
+
+export interface ReplayDiff {
+  before: DiffSide;
+  after: DiffSide;
+  services: Array<Delta & { service: string }>;
+  edges: Array<Delta & { from: string; to: string }>;
+}
+
+export interface SearchResult {
+  kind: 'service' | 'operation' | 'attribute' | 'span';
+  service: string;
+  operation?: string;
+  key?: string;
+  value?: string;
+  span_id?: string;
+  label: string;
+  score: number;
+  spans: number;
+}
+
+export type FilterMode = 'dim' | 'hide';
+
+// A part of a loaded session that could not be restored; the rest was
+export type SessionWarning =
+  | { kind: 'unknown_job'; job: string }
+  | { kind: 'filter_dropped'; expr: string; message: string }
+  | { kind: 'labels_dropped'; message: string };
+
+export interface FilterSummary {
+  expr: string;
+  mode: FilterMode;
+  matching_services: number;
+  matching_spans: number;
+  total_services: number;
+  total_spans: number;
+}
+
+export interface RecordingOptions {
+  start_ms?: number;
+  end_ms?: number;
+  fps?: number;
+  width?: number;
+  height?: number;
+  format?: 'apng' | 'gif' | 'rgba';
+}
+
+export interface RecordingProgress {
+  frame: number;
+  frames: number;
+  time_ms: number;
+  duration_ms: number;
+}
+
+type WorkerMessage = InitMessage | AppendDataMessage | RemoveSourceMessage | SetActiveJobMessage | StartAnimationMessage | StopAnimationMessage | ExportSvgMessage | CapturePngMessage | RecordPlaybackMessage | SetFilterMessage | SearchMessage | FocusServiceMessage | FocusResultMessage | ClearHighlightMessage | ListTracesMessage | ShowTraceMessage | CloseTraceMessage | AddAnnotationMessage | RemoveAnnotationMessage | JumpToAnnotationMessage | ExportAnnotationsMessage | ImportAnnotationsMessage | SetCameraMessage | SetThemeMessage | SetLodOptionsMessage | SetPlaybackSpeedMessage | SaveSessionMessage | LoadSessionMessage | DiffReplaysMessage | ShowDiffMessage | CloseDiffMessage | ShowServiceMapMessage | CloseMapMessage | PickNodeMessage | DragNodeMessage | PinNodeMessage | UnpinNodeMessage | ExportLayoutMessage | ImportLayoutMessage | SetGroupingMessage | SetEdgeBundlingMessage | CollapseGroupMessage | SetLabelRulesMessage;
 
 // Check if we have services available
 function hasServices(): boolean {
@@ -43,7 +462,8 @@ function animationStep() {
     return;
   }
   
-  if (hasServices()) {
+  // The rotation holds while a waterfall or map is on screen
+  if (hasServices() && overlay === null) {
     currentServiceIndex = (currentServiceIndex + 1) % serviceCount;
     
     console.log(`🎯 Worker: Rotating to service ${currentServiceIndex + 1}/${serviceCount}`);
@@ -70,7 +490,13 @@ function animationStep() {
   }
   
   // Schedule next tick using setTimeout (works in workers)
-  timerId = self.setTimeout(animationStep, SERVICE_ROTATE_MS) as unknown as number;
+  timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
+}
+
+// Time on each service at the current playback speed
+function rotateMs(): number {
+  const speed = wasmModule?.get_playback_speed ? wasmModule.get_playback_speed() : 1;
+  return SERVICE_ROTATE_MS / speed;
 }
 
 // Start animation once (idempotent)
@@ -84,7 +510,7 @@ function startAnimationOnce() {
   currentServiceIndex = 0;
   
   // Start the animation loop with setTimeout
-  timerId = self.setTimeout(animationStep, SERVICE_ROTATE_MS) as unknown as number;
+  timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
   
   self.postMessage({ type: 'animation_started', serviceCount });
 }
@@ -132,7 +558,7 @@ async function initWasmOnce(offscreenCanvas: OffscreenCanvas) {
 }
 
 // Append new data using proper memory management - NO STATE REBUILDING
-function appendData(data: Uint8Array) {
+function appendData(data: Uint8Array, jobId?: string, sourceKey?: string, lastModified?: string) {
   if (!wasmModule) {
     console.error('❌ Worker: WASM not initialized');
     return;
@@ -147,11 +573,13 @@ function appendData(data: Uint8Array) {
   busy = true;
   
   try {
-    // Check if we have proper memory access
-    if (!wasmModule.alloc || !wasmModule.append_chunk || !wasmModule.memory) {
-      console.log('🔄 Worker: Using fallback parse_arrow_replay (no memory access)');
-      // Fallback to parse_arrow_replay if memory management isn't available
-      const result = wasmModule.parse_arrow_replay(data);
+    // Check if we have the safe ingestion buffer
+    if (!wasmModule.IngestBuffer) {
+      console.log('🔄 Worker: Using fallback parse_arrow_replay (no ingest buffer)');
+      // Fallback to parse_arrow_replay if the ingest buffer isn't available
+      const result = wasmModule.ingest
+        ? wasmModule.ingest(data, { ...options, snapshot: true })
+        : wasmModule.parse_arrow_replay(data);
       
       // Try to extract service count from result
       if (result && result.services) {
@@ -161,38 +589,34 @@ function appendData(data: Uint8Array) {
       
       console.log('✅ Worker: Data processed via fallback method');
     } else {
-      // Use proper append pattern with direct memory writes
-      const ptr = wasmModule.alloc(data.length);
+      // Reuse one buffer, growing it when a larger chunk arrives
+      if (!ingestBuffer) {
+        ingestBuffer = new wasmModule.IngestBuffer(Math.max(data.length, 1));
+      } else if (ingestBuffer.capacity < data.length) {
+        ingestBuffer.reserve(data.length);
+      }
+      
+      // Fill a fresh view of WASM memory (reserve may have moved it), then commit the checked length
+      ingestBuffer.view().set(data);
+      ingestBuffer.commit(data.length, options);
       
+      console.log('✅ Worker: Appended', data.length, 'bytes via ingest buffer');
+      
+      // Get updated service count
       try {
-        // Write directly to WASM linear memory
-        const memoryView = new Uint8Array(wasmModule.memory.buffer, ptr, data.length);
-        memoryView.set(data);
-        
-        // Call append_chunk - no state rebuilding
-        wasmModule.append_chunk(ptr, data.length);
-        
-        console.log('✅ Worker: Appended', data.length, 'bytes via direct memory');
-        
-        // Get updated service count
-        try {
-          serviceCount = wasmModule.get_service_count();
-          console.log(`📊 Worker: Service count updated to ${serviceCount}`);
-        } catch (countError) {
-          console.log('🔄 Worker: Could not get service count from WASM');
-        }
-        
-      } finally {
-        // Always free allocated memory
-        wasmModule.free(ptr, data.length);
+        serviceCount = wasmModule.get_service_count();
+        console.log(`📊 Worker: Service count updated to ${serviceCount}`);
+      } catch (countError) {
+        console.log('🔄 Worker: Could not get service count from WASM');
       }
     }
     
     // Start animation automatically if we have services (idempotent)
     startAnimationOnce();
+    redrawMap();
     
     // Notify main thread
-    self.postMessage({ type: 'data_updated', serviceCount });
+    self.postMessage({ type: 'data_updated', serviceCount, jobs: listJobs(), annotations: annotationTimeline() });
     
   } catch (error) {
     console.error('❌ Worker: Failed to append data:', error);
@@ -205,37 +629,990 @@ function appendData(data: Uint8Array) {
   }
 }
 
-// Legacy startAnimation - redirect to new function
-function startAnimation() {
-  startAnimationOnce();
+// Jobs known to WASM, or an empty list on older builds
+function listJobs(): any[] {
+  try {
+    return wasmModule?.list_jobs ? wasmModule.list_jobs() : [];
+  } catch (error) {
+    console.log('🔄 Worker: Could not list jobs from WASM');
+    return [];
+  }
 }
 
-// Legacy stopAnimation - redirect to new function
-function stopAnimation() {
-  stopAnimationLoop();
+// User and detected annotations as timeline markers, or null on older builds
+function annotationTimeline(): AnnotationTimeline | null {
+  try {
+    return wasmModule?.annotations ? wasmModule.annotations() : null;
+  } catch (error) {
+    console.log('🔄 Worker: Could not read annotations from WASM');
+    return null;
+  }
 }
 
-// Handle messages from main thread
-self.onmessage = async (event: MessageEvent<WorkerMessage>) => {
-  const message = event.data;
+// Switch the job that drives rendering and restart the rotation on it
+function setActiveJob(jobId: string) {
+  if (!wasmModule) {
+    console.error('❌ Worker: WASM not initialized');
+    return;
+  }
   
-  console.log('📨 Worker: Received message:', message.type);
+  try {
+    wasmModule.set_active_job(jobId);
+    serviceCount = wasmModule.get_service_count();
+    currentServiceIndex = 0;
+    overlay = null;
+    self.postMessage({ type: 'data_updated', serviceCount, jobs: listJobs(), annotations: annotationTimeline() });
+  } catch (error) {
+    console.error('❌ Worker: Failed to set active job:', error);
+    self.postMessage({ 
+      type: 'data_error', 
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Withdraw everything a source file contributed, e.g. after the backend deleted it
+function removeSource(sourceKey: string) {
+  if (!wasmModule?.remove_source) {
+    return;
+  }
   
-  switch (message.type) {
-    case 'init':
-      await initWasmOnce(message.canvas);
-      break;
-      
-    case 'append':
-      appendData(message.data);
-      break;
-      
-    case 'start_animation':
-      startAnimation();
-      break;
-      
-    case 'stop_animation':
-      stopAnimation();
+  try {
+    const removed = wasmModule.remove_source(sourceKey);
+    console.log(`🗑️ Worker: Removed ${removed} spans from ${sourceKey}`);
+    serviceCount = wasmModule.get_service_count();
+    self.postMessage({ type: 'data_updated', serviceCount, jobs: listJobs(), annotations: annotationTimeline() });
+  } catch (error) {
+    console.log(`🔄 Worker: Source ${sourceKey} was not ingested`);
+  }
+}
+
+// Serialize the current view to SVG and reply to the matching request
+function exportSvg(requestId: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const svg: string = wasmModule.export_svg();
+    self.postMessage({ type: 'svg_exported', requestId, svg });
+  } catch (error) {
+    console.error('❌ Worker: SVG export failed:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Render the current view offscreen at any size and reply with PNG bytes
+async function capturePng(requestId: number, width: number, height: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const png: Uint8Array = await wasmModule.capture_png(width, height);
+    console.log(`📸 Worker: Captured ${width}x${height} PNG (${png.byteLength} bytes)`);
+    // Transfer the bytes - the worker has no further use for them
+    self.postMessage({ type: 'png_captured', requestId, png }, { transfer: [png.buffer] });
+  } catch (error) {
+    console.error('❌ Worker: PNG capture failed:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Record playback to an animation, streaming progress (and raw frames for
+// the 'rgba' format) back to the main thread as each frame is rendered
+async function recordPlayback(requestId: number, options: RecordingOptions) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const onProgress = (progress: RecordingProgress, rgba?: Uint8Array) => {
+      console.log(`🎬 Worker: Recorded frame ${progress.frame}/${progress.frames}`);
+      self.postMessage(
+        { type: 'recording_progress', requestId, progress, rgba },
+        { transfer: rgba ? [rgba.buffer] : [] }
+      );
+    };
+    const bytes: Uint8Array = await wasmModule.record_playback(options, onProgress);
+    self.postMessage({ type: 'recording_complete', requestId, bytes }, { transfer: [bytes.buffer] });
+  } catch (error) {
+    console.error('❌ Worker: Recording failed:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Apply a filter expression and redraw the current service with it. Hidden
+// services leave the rotation, so the service count is refreshed too
+function setFilter(requestId: number, expr: string, mode?: FilterMode) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const summary: FilterSummary | null = wasmModule.set_filter(expr, mode);
+    serviceCount = wasmModule.get_service_count();
+    currentServiceIndex = serviceCount > 0 ? currentServiceIndex % serviceCount : 0;
+    if (animationRunning && overlay === null) {
+      if (hasServices()) {
+        wasmModule.render_service_by_index(currentServiceIndex);
+      } else {
+        wasmModule.clear_canvas();
+      }
+    }
+    redrawMap();
+    self.postMessage({ type: 'filter_set', requestId, summary });
+    self.postMessage({ type: 'data_updated', serviceCount, jobs: listJobs(), annotations: annotationTimeline() });
+  } catch (error: any) {
+    // Parse errors arrive as { message, start, end } rather than an Error
+    const parseError = error && typeof error.start === 'number';
+    console.log('🔎 Worker: Filter rejected:', parseError ? error.message : error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error || parseError ? error.message : String(error),
+      details: parseError ? { start: error.start, end: error.end } : undefined
+    });
+  }
+}
+
+// Ranked matches from the incremental search index in WASM
+function search(requestId: number, query: string, limit?: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const results: SearchResult[] = wasmModule.search(query, limit);
+    self.postMessage({ type: 'search_results', requestId, results });
+  } catch (error) {
+    console.error('❌ Worker: Search failed:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Fly to a service: the service map zoomed in on its node, outlined. The
+// rotation holds until the map is closed, then continues from there after
+// a full dwell on the focused service
+function focusService(requestId: number, serviceId: string, caption?: string) {
+  focusWith(requestId, () => wasmModule.focus_service(serviceId, caption));
+}
+
+// Fly to a search result of any kind, captioned with its label
+function focusResult(requestId: number, result: SearchResult) {
+  focusWith(requestId, () => wasmModule.focus_result(result));
+}
+
+function focusWith(requestId: number, focus: () => number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    currentServiceIndex = focus();
+    overlay = 'map';
+    if (animationRunning) {
+      if (timerId !== null) {
+        self.clearTimeout(timerId);
+      }
+      timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
+    }
+    self.postMessage({ type: 'service_focused', requestId, serviceIndex: currentServiceIndex, serviceCount });
+  } catch (error) {
+    console.error('❌ Worker: Focus failed:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Remove the search highlight and redraw the current service without it
+function clearHighlight() {
+  if (!wasmModule?.clear_highlight) {
+    return;
+  }
+  
+  try {
+    // Zooms back out to the camera from before the flight
+    wasmModule.clear_highlight();
+    redrawView();
+  } catch (error) {
+    console.error('❌ Worker: Failed to clear highlight:', error);
+  }
+}
+
+// Traces of the active job, slowest first
+function listTraces(requestId: number, limit?: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const traces: TraceSummary[] = wasmModule.list_traces(limit);
+    self.postMessage({ type: 'traces', requestId, traces });
+  } catch (error) {
+    console.error('❌ Worker: Failed to list traces:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Draw a trace's waterfall with its critical path, holding the rotation
+// until the trace is closed
+function showTrace(requestId: number, traceId: string) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const path: CriticalPath = wasmModule.show_trace(traceId);
+    overlay = 'trace';
+    shownTrace = traceId;
+    self.postMessage({ type: 'trace_shown', requestId, path });
+  } catch (error) {
+    console.error('❌ Worker: Failed to show trace:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Leave the waterfall and resume the rotation. Services on the critical path
+// stay outlined unless clearPath is set
+function closeTrace(clearPath?: boolean) {
+  if (!wasmModule?.clear_trace) {
+    return;
+  }
+  
+  try {
+    if (clearPath) {
+      wasmModule.clear_trace();
+    }
+    overlay = null;
+    if (hasServices()) {
+      wasmModule.render_service_by_index(currentServiceIndex);
+    } else {
+      wasmModule.clear_canvas();
+    }
+  } catch (error) {
+    console.error('❌ Worker: Failed to close trace:', error);
+  }
+}
+
+// Annotate the active job and reply with the updated timeline
+function addAnnotation(requestId: number, annotation: NewAnnotation) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const id: number = wasmModule.add_annotation(annotation);
+    self.postMessage({ type: 'annotation_added', requestId, id, annotations: annotationTimeline() });
+  } catch (error) {
+    console.error('❌ Worker: Failed to add annotation:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+function removeAnnotation(requestId: number, id: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const removed: boolean = wasmModule.remove_annotation(id);
+    self.postMessage({ type: 'annotation_removed', requestId, removed, annotations: annotationTimeline() });
+  } catch (error) {
+    console.error('❌ Worker: Failed to remove annotation:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Step to the next or previous annotation. When WASM flew to its service,
+// the rotation continues from there
+function jumpToAnnotation(requestId: number, from: number | undefined, forward: boolean) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const jump: AnnotationJump | null = wasmModule.jump_to_annotation(from, forward);
+    if (jump?.service_index != null) {
+      currentServiceIndex = jump.service_index;
+      overlay = null;
+      if (animationRunning) {
+        if (timerId !== null) {
+          self.clearTimeout(timerId);
+        }
+        timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
+      }
+    }
+    self.postMessage({ type: 'annotation_jump', requestId, jump });
+  } catch (error) {
+    console.error('❌ Worker: Failed to jump to annotation:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// User annotations of the active job, for saving
+function exportAnnotations(requestId: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const saved: Annotation[] = wasmModule.export_annotations();
+    self.postMessage({ type: 'annotations_exported', requestId, saved });
+  } catch (error) {
+    console.error('❌ Worker: Failed to export annotations:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+function importAnnotations(requestId: number, saved: Annotation[]) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const count: number = wasmModule.import_annotations(saved);
+    self.postMessage({ type: 'annotations_imported', requestId, count, annotations: annotationTimeline() });
+  } catch (error) {
+    console.error('❌ Worker: Failed to import annotations:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Pan and zoom; WASM redraws the scene on screen
+function setCamera(x: number, y: number, zoom: number) {
+  try {
+    wasmModule?.set_camera(x, y, zoom);
+  } catch (error) {
+    console.error('❌ Worker: Failed to set camera:', error);
+  }
+}
+
+// Redraw whatever is on screen, e.g. after the theme changes
+function redrawView() {
+  if (overlay === 'trace' && shownTrace !== null) {
+    wasmModule.show_trace(shownTrace);
+  } else if (overlay === 'diff' && shownDiff !== null) {
+    wasmModule.show_diff(shownDiff.before, shownDiff.after);
+  } else if (overlay === 'map') {
+    wasmModule.show_service_map();
+  } else if (hasServices()) {
+    wasmModule.render_service_by_index(currentServiceIndex);
+  } else {
+    wasmModule.clear_canvas();
+  }
+}
+
+// Switch themes and redraw the current view in the new colors
+function setTheme(requestId: number, theme: ThemeName | Partial<Theme> | null) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.set_theme(theme);
+    redrawView();
+    const applied: Theme = wasmModule.get_theme();
+    self.postMessage({ type: 'theme_set', requestId, theme: applied });
+  } catch (error) {
+    console.error('❌ Worker: Failed to set theme:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Tune level of detail; WASM redraws the scene on screen
+function setLodOptions(options: LodOptions | null) {
+  try {
+    wasmModule?.set_lod_options(options);
+  } catch (error) {
+    console.error('❌ Worker: Failed to set LOD options:', error);
+  }
+}
+
+// Change the playback rate and restart the current dwell at the new rate
+function setPlaybackSpeed(speed: number) {
+  try {
+    if (!wasmModule) {
+      return;
+    }
+    wasmModule.set_playback_speed(speed);
+    if (animationRunning) {
+      if (timerId !== null) {
+        self.clearTimeout(timerId);
+      }
+      timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
+    }
+  } catch (error) {
+    console.error('❌ Worker: Failed to set playback speed:', error);
+  }
+}
+
+function saveSession(requestId: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const bytes: Uint8Array = wasmModule.save_session();
+    self.postMessage({ type: 'session_saved', requestId, bytes }, { transfer: [bytes.buffer] });
+  } catch (error) {
+    console.error('❌ Worker: Failed to save session:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Restore a saved session and resume the rotation at its playhead
+function loadSession(requestId: number, bytes: Uint8Array) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const restored: { service_index: number; warnings: SessionWarning[] } = wasmModule.load_session(bytes);
+    currentServiceIndex = restored.service_index;
+    serviceCount = wasmModule.get_service_count();
+    overlay = null;
+    if (animationRunning) {
+      if (timerId !== null) {
+        self.clearTimeout(timerId);
+      }
+      timerId = self.setTimeout(animationStep, rotateMs()) as unknown as number;
+    }
+    self.postMessage({ type: 'data_updated', serviceCount, jobs: listJobs(), annotations: annotationTimeline() });
+    const camera = wasmModule.get_camera();
+    const speed: number = wasmModule.get_playback_speed();
+    self.postMessage({
+      type: 'session_loaded',
+      requestId,
+      serviceIndex: currentServiceIndex,
+      serviceCount,
+      camera,
+      speed,
+      warnings: restored.warnings
+    });
+  } catch (error) {
+    console.error('❌ Worker: Failed to load session:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Compare two jobs or time windows
+function diffReplays(requestId: number, before: DiffSide, after: DiffSide) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const diff: ReplayDiff = wasmModule.diff_replays(before, after);
+    self.postMessage({ type: 'diff', requestId, diff });
+  } catch (error) {
+    console.error('❌ Worker: Failed to diff replays:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Draw the diff map, holding the rotation until the diff is closed
+function showDiff(requestId: number, before: DiffSide, after: DiffSide) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const diff: ReplayDiff = wasmModule.show_diff(before, after);
+    overlay = 'diff';
+    shownDiff = { before, after };
+    self.postMessage({ type: 'diff_shown', requestId, diff });
+  } catch (error) {
+    console.error('❌ Worker: Failed to show diff:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Leave the diff map and resume the rotation
+function closeDiff() {
+  if (!wasmModule || overlay !== 'diff') {
+    return;
+  }
+  
+  try {
+    overlay = null;
+    if (hasServices()) {
+      wasmModule.render_service_by_index(currentServiceIndex);
+    } else {
+      wasmModule.clear_canvas();
+    }
+  } catch (error) {
+    console.error('❌ Worker: Failed to close diff:', error);
+  }
+}
+
+// Draw the service map, holding the rotation until the map is closed
+function showServiceMap(requestId: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.show_service_map();
+    overlay = 'map';
+    self.postMessage({ type: 'service_map_shown', requestId });
+  } catch (error) {
+    console.error('❌ Worker: Failed to show service map:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Redraw the service map after its services or pins change, if it is up
+function redrawMap() {
+  if (overlay !== 'map') {
+    return;
+  }
+  
+  try {
+    wasmModule.show_service_map();
+  } catch (error) {
+    console.error('❌ Worker: Failed to redraw service map:', error);
+  }
+}
+
+// Leave the service map and resume the rotation
+function closeMap() {
+  if (!wasmModule || overlay !== 'map') {
+    return;
+  }
+  
+  try {
+    overlay = null;
+    if (hasServices()) {
+      wasmModule.render_service_by_index(currentServiceIndex);
+    } else {
+      wasmModule.clear_canvas();
+    }
+  } catch (error) {
+    console.error('❌ Worker: Failed to close service map:', error);
+  }
+}
+
+// The service or collapsed group under a clip-space point on the map, or null
+function pickNode(requestId: number, x: number, y: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const picked: PickedNode | null = wasmModule.pick_node(x, y);
+    self.postMessage({ type: 'node_picked', requestId, picked });
+  } catch (error) {
+    console.error('❌ Worker: Failed to pick node:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Move a service under the pointer while it is dragged. Sent on every
+// pointer move, so there is no reply
+function dragNode(id: string, x: number, y: number) {
+  try {
+    wasmModule?.drag_node(id, x, y);
+    redrawMap();
+  } catch (error) {
+    console.error('❌ Worker: Failed to drag node:', error);
+  }
+}
+
+function pinNode(requestId: number, id: string, x: number, y: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.pin_node(id, x, y);
+    redrawMap();
+    self.postMessage({ type: 'node_pinned', requestId });
+  } catch (error) {
+    console.error('❌ Worker: Failed to pin node:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+function unpinNode(requestId: number, id: string) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const unpinned: boolean = wasmModule.unpin(id);
+    redrawMap();
+    self.postMessage({ type: 'node_unpinned', requestId, unpinned });
+  } catch (error) {
+    console.error('❌ Worker: Failed to unpin node:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Pinned positions of the active job as JSON, for saving to a file
+function exportLayout(requestId: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const json: string = wasmModule.export_layout();
+    self.postMessage({ type: 'layout_exported', requestId, json });
+  } catch (error) {
+    console.error('❌ Worker: Failed to export layout:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+function importLayout(requestId: number, json: string) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    const count: number = wasmModule.import_layout(json);
+    redrawMap();
+    self.postMessage({ type: 'layout_imported', requestId, count });
+  } catch (error) {
+    console.error('❌ Worker: Failed to import layout:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Group services on the map and reply with the groups
+function setGrouping(requestId: number, by: GroupBy | null) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.set_grouping(by);
+    redrawMap();
+    const groups: ServiceGroup[] = wasmModule.groups();
+    self.postMessage({ type: 'grouping_set', requestId, groups });
+  } catch (error) {
+    console.error('❌ Worker: Failed to set grouping:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// 0 draws straight map edges, 1 bundles them fully through the groups
+function setEdgeBundling(requestId: number, strength: number) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.set_edge_bundling(strength);
+    redrawMap();
+    self.postMessage({ type: 'edge_bundling_set', requestId });
+  } catch (error) {
+    console.error('❌ Worker: Failed to set edge bundling:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+function collapseGroup(requestId: number, name: string, collapsed: boolean) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.collapse_group(name, collapsed);
+    redrawMap();
+    const groups: ServiceGroup[] = wasmModule.groups();
+    self.postMessage({ type: 'group_collapsed', requestId, groups });
+  } catch (error) {
+    console.error('❌ Worker: Failed to collapse group:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Change how service IDs are labelled and redraw the service or map on screen
+function setLabelRules(requestId: number, rules: LabelRules | null) {
+  try {
+    if (!wasmModule) {
+      throw new Error('WASM not initialized');
+    }
+    wasmModule.set_label_rules(rules);
+    if (overlay === null && hasServices()) {
+      wasmModule.render_service_by_index(currentServiceIndex);
+    }
+    redrawMap();
+    self.postMessage({ type: 'label_rules_set', requestId });
+  } catch (error) {
+    console.error('❌ Worker: Failed to set label rules:', error);
+    self.postMessage({ 
+      type: 'request_error', 
+      requestId,
+      error: error instanceof Error ? error.message : String(error) 
+    });
+  }
+}
+
+// Legacy startAnimation - redirect to new function
+function startAnimation() {
+  startAnimationOnce();
+}
+
+// Legacy stopAnimation - redirect to new function
+function stopAnimation() {
+  stopAnimationLoop();
+}
+
+// Handle messages from main thread
+self.onmessage = async (event: MessageEvent<WorkerMessage>) => {
+  const message = event.data;
+  
+  console.log('📨 Worker: Received message:', message.type);
+  
+  switch (message.type) {
+    case 'init':
+      await initWasmOnce(message.canvas);
+      break;
+      
+    case 'append':
+      appendData(message.data, message.jobId, message.sourceKey, message.lastModified);
+      break;
+      
+    case 'remove_source':
+      removeSource(message.sourceKey);
+      break;
+      
+    case 'set_active_job':
+      setActiveJob(message.jobId);
+      break;
+      
+    case 'start_animation':
+      startAnimation();
+      break;
+      
+    case 'stop_animation':
+      stopAnimation();
+      break;
+      
+    case 'export_svg':
+      exportSvg(message.requestId);
+      break;
+      
+    case 'capture_png':
+      await capturePng(message.requestId, message.width, message.height);
+      break;
+      
+    case 'record_playback':
+      await recordPlayback(message.requestId, message.options);
+      break;
+      
+    case 'set_filter':
+      setFilter(message.requestId, message.expr, message.mode);
+      break;
+      
+    case 'search':
+      search(message.requestId, message.query, message.limit);
+      break;
+      
+    case 'focus_service':
+      focusService(message.requestId, message.serviceId, message.caption);
+      break;
+      
+    case 'focus_result':
+      focusResult(message.requestId, message.result);
+      break;
+      
+    case 'clear_highlight':
+      clearHighlight();
+      break;
+      
+    case 'list_traces':
+      listTraces(message.requestId, message.limit);
+      break;
+      
+    case 'show_trace':
+      showTrace(message.requestId, message.traceId);
+      break;
+      
+    case 'close_trace':
+      closeTrace(message.clearPath);
+      break;
+      
+    case 'add_annotation':
+      addAnnotation(message.requestId, message.annotation);
+      break;
+      
+    case 'remove_annotation':
+      removeAnnotation(message.requestId, message.id);
+      break;
+      
+    case 'jump_to_annotation':
+      jumpToAnnotation(message.requestId, message.from, message.forward);
+      break;
+      
+    case 'export_annotations':
+      exportAnnotations(message.requestId);
+      break;
+      
+    case 'import_annotations':
+      importAnnotations(message.requestId, message.saved);
+      break;
+      
+    case 'set_camera':
+      setCamera(message.x, message.y, message.zoom);
+      break;
+      
+    case 'set_theme':
+      setTheme(message.requestId, message.theme);
+      break;
+      
+    case 'set_lod_options':
+      setLodOptions(message.options);
+      break;
+      
+    case 'set_playback_speed':
+      setPlaybackSpeed(message.speed);
+      break;
+      
+    case 'save_session':
+      saveSession(message.requestId);
+      break;
+      
+    case 'load_session':
+      loadSession(message.requestId, message.bytes);
+      break;
+      
+    case 'diff_replays':
+      diffReplays(message.requestId, message.before, message.after);
+      break;
+      
+    case 'show_diff':
+      showDiff(message.requestId, message.before, message.after);
+      break;
+      
+    case 'close_diff':
+      closeDiff();
+      break;
+      
+    case 'show_service_map':
+      showServiceMap(message.requestId);
+      break;
+      
+    case 'close_map':
+      closeMap();
+      break;
+      
+    case 'pick_node':
+      pickNode(message.requestId, message.x, message.y);
+      break;
+      
+    case 'drag_node':
+      dragNode(message.id, message.x, message.y);
+      break;
+      
+    case 'pin_node':
+      pinNode(message.requestId, message.id, message.x, message.y);
+      break;
+      
+    case 'unpin_node':
+      unpinNode(message.requestId, message.id);
+      break;
+      
+    case 'export_layout':
+      exportLayout(message.requestId);
+      break;
+      
+    case 'import_layout':
+      importLayout(message.requestId, message.json);
+      break;
+      
+    case 'set_grouping':
+      setGrouping(message.requestId, message.by);
+      break;
+      
+    case 'set_edge_bundling':
+      setEdgeBundling(message.requestId, message.strength);
+      break;
+      
+    case 'collapse_group':
+      collapseGroup(message.requestId, message.name, message.collapsed);
+      break;
+      
+    case 'set_label_rules':
+      setLabelRules(message.requestId, message.rules);
       break;
       
     default:
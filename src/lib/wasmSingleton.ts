@@ -1,18 +1,25 @@
 // WASM Singleton - Initialize once, keep alive, push data incrementally
 
+interface IngestBuffer {
+  readonly capacity: number;
+  reserve(capacity: number): void;
+  view(): Uint8Array;
+  write(chunk: Uint8Array, offset: number): void;
+  commit(len: number): void;
+}
+
 interface WasmExports {
-  alloc(size: number): number;
-  free(ptr: number, size: number): void;
-  append_chunk(ptr: number, len: number): void;
+  IngestBuffer: new (capacity: number) => IngestBuffer;
+  append_chunk(data: Uint8Array): void;
   render_replay(): void;
   stop_animation(): void;
   init_webgpu(canvas: HTMLCanvasElement): Promise<void>;
-  memory: WebAssembly.Memory;
 }
 
 class WasmSingleton {
   private wasmReady: Promise<WasmExports> | null = null;
   private exportsRef: WasmExports | null = null;
+  private ingestBuffer: IngestBuffer | null = null;
   private animationStarted = false;
 
   // Initialize WASM once and keep it alive
@@ -42,27 +49,21 @@ class WasmSingleton {
     console.log('✅ WebGPU initialized with WASM singleton');
   }
 
-  // Append new data using direct memory writes (zero-copy)
+  // Append new data through the reusable WASM ingest buffer
   async appendData(bytes: Uint8Array): Promise<void> {
     const wasm = await this.getWasm();
     
-    // Allocate memory in WASM
-    const ptr = wasm.alloc(bytes.length);
-    
-    try {
-      // Write bytes directly to WASM linear memory
-      const memoryView = new Uint8Array(wasm.memory.buffer, ptr, bytes.length);
-      memoryView.set(bytes);
-      
-      // Call append function with pointer and length
-      wasm.append_chunk(ptr, bytes.length);
-      
-      console.log('✅ Appended', bytes.length, 'bytes via direct memory write');
-      
-    } finally {
-      // Always free the allocated memory
-      wasm.free(ptr, bytes.length);
+    if (!this.ingestBuffer) {
+      this.ingestBuffer = new wasm.IngestBuffer(Math.max(bytes.length, 1));
+    } else if (this.ingestBuffer.capacity < bytes.length) {
+      this.ingestBuffer.reserve(bytes.length);
     }
+    
+    // Fill a fresh view of WASM memory (reserve may have moved it), then commit the checked length
+    this.ingestBuffer.view().set(bytes);
+    this.ingestBuffer.commit(bytes.length);
+    
+    console.log('✅ Appended', bytes.length, 'bytes via ingest buffer');
   }
 
   // Start animation (only once)
@@ -1,5 +1,7 @@
 // Service to manage WASM Web Worker communication
 
+import type { Annotation, AnnotationJump, AnnotationTimeline, CriticalPath, DiffSide, FilterMode, FilterSummary, GroupBy, LabelRules, LodOptions, NewAnnotation, PickedNode, RecordingOptions, RecordingProgress, ReplayDiff, SearchResult, ServiceGroup, SessionWarning, Theme, ThemeName, TraceSummary } from './wasm-worker';
+
 interface WorkerResponse {
   type: string;
   error?: string;
@@ -10,6 +12,11 @@ class WasmWorkerService {
   private worker: Worker | null = null;
   private initialized = false;
   private animationStarted = false;
+  private jobs: Array<{ job_id: string; service_count: number; span_count: number; active: boolean }> = [];
+  private annotations: AnnotationTimeline | null = null;
+  private nextRequestId = 1;
+  private pending = new Map<number, { resolve: (message: WorkerResponse) => void; reject: (error: Error) => void }>();
+  private progressHandlers = new Map<number, (message: WorkerResponse) => void>();
   
   // Initialize the worker with OffscreenCanvas
   async initialize(canvas: HTMLCanvasElement): Promise<void> {
@@ -73,12 +80,38 @@ class WasmWorkerService {
       
       console.log('📨 Main: Received from worker:', message.type);
       
+      // Intermediate updates for a long-running request
+      if (message.type === 'recording_progress') {
+        this.progressHandlers.get(message.requestId)?.(message);
+        return;
+      }
+      
+      // Replies to request() calls carry the id they were sent with
+      if (message.requestId !== undefined && this.pending.has(message.requestId)) {
+        const { resolve, reject } = this.pending.get(message.requestId)!;
+        this.pending.delete(message.requestId);
+        this.progressHandlers.delete(message.requestId);
+        if (message.type === 'request_error') {
+          // Extra fields such as a filter error's start/end ride along on the Error
+          reject(Object.assign(new Error(message.error), message.details));
+        } else {
+          resolve(message);
+        }
+        return;
+      }
+      
       switch (message.type) {
         case 'data_updated':
           console.log('✅ Main: Data updated in worker');
           if (message.serviceCount) {
             console.log(`📊 Main: Worker has ${message.serviceCount} services`);
           }
+          if (message.jobs) {
+            this.jobs = message.jobs;
+          }
+          if (message.annotations) {
+            this.annotations = message.annotations;
+          }
           break;
           
         case 'animation_started':
@@ -116,8 +149,8 @@ class WasmWorkerService {
     };
   }
   
-  // Send new data to worker (non-blocking)
-  appendData(data: Uint8Array): void {
+  // Send new data to worker (non-blocking), optionally attributed to a job and source file
+  appendData(data: Uint8Array, jobId?: string, sourceKey?: string, lastModified?: string): void {
     if (!this.worker || !this.initialized) {
       console.error('❌ Main: Worker not initialized, cannot append data');
       return;
@@ -126,10 +159,326 @@ class WasmWorkerService {
     // DON'T transfer ArrayBuffer - copy it instead to avoid detached buffer
     this.worker.postMessage({
       type: 'append',
-      data: data  // This will be copied, not transferred
+      data: data,  // This will be copied, not transferred
+      jobId,
+      sourceKey,
+      lastModified
+    });
+  }
+  
+  // Drop everything a source file contributed
+  removeSource(sourceKey: string): void {
+    if (!this.worker || !this.initialized) {
+      console.error('❌ Main: Worker not initialized, cannot remove source');
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'remove_source', sourceKey });
+  }
+  
+  // Select which job the worker renders
+  setActiveJob(jobId: string): void {
+    if (!this.worker || !this.initialized) {
+      console.error('❌ Main: Worker not initialized, cannot set active job');
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'set_active_job', jobId });
+  }
+  
+  // Post a message and wait for the worker's reply with the same requestId
+  private request(message: Record<string, any>, onProgress?: (message: WorkerResponse) => void): Promise<WorkerResponse> {
+    if (!this.worker || !this.initialized) {
+      return Promise.reject(new Error('Worker not initialized'));
+    }
+    
+    const requestId = this.nextRequestId++;
+    return new Promise((resolve, reject) => {
+      this.pending.set(requestId, { resolve, reject });
+      if (onProgress) {
+        this.progressHandlers.set(requestId, onProgress);
+      }
+      this.worker!.postMessage({ ...message, requestId });
     });
   }
   
+  // Current view as a standalone SVG document
+  async exportSvg(): Promise<string> {
+    const reply = await this.request({ type: 'export_svg' });
+    return reply.svg;
+  }
+  
+  // Current view rendered offscreen at the given size, as PNG bytes
+  async capturePng(width: number, height: number): Promise<Uint8Array> {
+    const reply = await this.request({ type: 'capture_png', width, height });
+    return reply.png;
+  }
+  
+  // Record playback offscreen. Resolves to the encoded APNG/GIF, or to empty
+  // bytes for the 'rgba' format, whose frames arrive through onProgress
+  async recordPlayback(
+    options: RecordingOptions = {},
+    onProgress?: (progress: RecordingProgress, rgba?: Uint8Array) => void
+  ): Promise<Uint8Array> {
+    const reply = await this.request(
+      { type: 'record_playback', options },
+      onProgress && ((message) => onProgress(message.progress, message.rgba))
+    );
+    return reply.bytes;
+  }
+  
+  // Filter every view by an expression such as `status = error AND duration > 200ms`.
+  // An empty expression clears the filter and resolves to null. Parse errors
+  // reject with an Error carrying the `start`/`end` of the offending input
+  async setFilter(expr: string, mode: FilterMode = 'dim'): Promise<FilterSummary | null> {
+    const reply = await this.request({ type: 'set_filter', expr, mode });
+    return reply.summary;
+  }
+  
+  // Ranked matches across services, operations, span IDs and attribute values
+  async search(query: string, limit?: number): Promise<SearchResult[]> {
+    const reply = await this.request({ type: 'search', query, limit });
+    return reply.results;
+  }
+  
+  // Fly to a search result (service, operation, attribute or span) at its
+  // service's node, outlined with the result's label
+  async focusResult(result: SearchResult): Promise<void> {
+    await this.request({ type: 'focus_result', result });
+  }
+  
+  // Fly the camera to a service on the service map and outline it, captioned
+  // with what led there. The rotation pauses until closeMap
+  async focusService(serviceId: string, caption?: string): Promise<void> {
+    await this.request({ type: 'focus_service', serviceId, caption });
+  }
+  
+  // Remove the outline left by focusResult and zoom back out
+  clearHighlight(): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'clear_highlight' });
+  }
+  
+  // Traces in the active job, slowest first
+  async listTraces(limit?: number): Promise<TraceSummary[]> {
+    const reply = await this.request({ type: 'list_traces', limit });
+    return reply.traces;
+  }
+  
+  // Show a trace's waterfall with its critical path; the service rotation
+  // pauses until closeTrace. Services on the path are outlined afterwards
+  async showTrace(traceId: string): Promise<CriticalPath> {
+    const reply = await this.request({ type: 'show_trace', traceId });
+    return reply.path;
+  }
+  
+  // Return from the waterfall to the rotation, optionally dropping the path outline
+  closeTrace(clearPath = false): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'close_trace', clearPath });
+  }
+  
+  // Annotate the active job, e.g. a deploy at a timestamp. Returns the new id
+  async addAnnotation(annotation: NewAnnotation): Promise<number> {
+    const reply = await this.request({ type: 'add_annotation', annotation });
+    this.annotations = reply.annotations ?? this.annotations;
+    return reply.id;
+  }
+  
+  async removeAnnotation(id: number): Promise<boolean> {
+    const reply = await this.request({ type: 'remove_annotation', id });
+    this.annotations = reply.annotations ?? this.annotations;
+    return reply.removed;
+  }
+  
+  // Step to the annotation after (or before) `from`, flying to its service
+  // if it has one. Null past either end
+  async jumpToAnnotation(from: number | undefined, forward = true): Promise<AnnotationJump | null> {
+    const reply = await this.request({ type: 'jump_to_annotation', from, forward });
+    return reply.jump;
+  }
+  
+  // User annotations of the active job, serializable with the session
+  async exportAnnotations(): Promise<Annotation[]> {
+    const reply = await this.request({ type: 'export_annotations' });
+    return reply.saved;
+  }
+  
+  // Replace the active job's user annotations with exported ones
+  async importAnnotations(saved: Annotation[]): Promise<number> {
+    const reply = await this.request({ type: 'import_annotations', saved });
+    this.annotations = reply.annotations ?? this.annotations;
+    return reply.count;
+  }
+  
+  // Pan and zoom the view; (x, y) is the clip-space point at the canvas centre
+  setCamera(x: number, y: number, zoom: number): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'set_camera', x, y, zoom });
+  }
+  
+  // A built-in theme by name, or custom colors over the dark theme; resolves
+  // with the colors now in use
+  async setTheme(theme: ThemeName | Partial<Theme> | null): Promise<Theme> {
+    const reply = await this.request({ type: 'set_theme', theme });
+    return reply.theme;
+  }
+  
+  // Level of detail for zoomed-out views; null restores the defaults
+  setLodOptions(options: LodOptions | null): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'set_lod_options', options });
+  }
+  
+  // Playback rate: 2 rotates through services twice as fast
+  setPlaybackSpeed(speed: number): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'set_playback_speed', speed });
+  }
+  
+  // The view as versioned session bytes
+  async saveSession(): Promise<Uint8Array> {
+    const reply = await this.request({ type: 'save_session' });
+    return reply.bytes;
+  }
+  
+  // Restore a session from saveSession. Resolves to the service index it
+  // resumed at, the restored camera and speed, and the parts that were
+  // dropped. A session with unusable values rejects without changing anything
+  async loadSession(bytes: Uint8Array): Promise<{ serviceIndex: number; camera: { center: [number, number]; zoom: number }; speed: number; warnings: SessionWarning[] }> {
+    const reply = await this.request({ type: 'load_session', bytes });
+    return { serviceIndex: reply.serviceIndex, camera: reply.camera, speed: reply.speed, warnings: reply.warnings };
+  }
+  
+  // A link that reopens this replay where it is now: the session rides in
+  // the URL fragment as base64url, so it never reaches the server
+  async shareLink(): Promise<string> {
+    const bytes = await this.saveSession();
+    const base64 = btoa(Array.from(bytes, (b) => String.fromCharCode(b)).join('')).replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '');
+    const url = new URL(location.href);
+    url.hash = `session=${base64}`;
+    return url.toString();
+  }
+  
+  // Session bytes from a link made by shareLink, if the URL has one
+  sessionFromLink(href: string = location.href): Uint8Array | null {
+    const match = new URL(href).hash.match(/session=([\w-]+)/);
+    if (!match) {
+      return null;
+    }
+    const base64 = match[1].replace(/-/g, '+').replace(/_/g, '/');
+    return Uint8Array.from(atob(base64), (c) => c.charCodeAt(0));
+  }
+  
+  // Per-service and per-edge deltas between two jobs or time windows
+  async diffReplays(before: DiffSide, after: DiffSide): Promise<ReplayDiff> {
+    const reply = await this.request({ type: 'diff_replays', before, after });
+    return reply.diff;
+  }
+  
+  // Show the diff as a service map colored by delta; the service rotation
+  // pauses until closeDiff
+  async showDiff(before: DiffSide, after: DiffSide): Promise<ReplayDiff> {
+    const reply = await this.request({ type: 'show_diff', before, after });
+    return reply.diff;
+  }
+  
+  // Return from the diff map to the rotation
+  closeDiff(): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'close_diff' });
+  }
+  
+  // Show every service at its layout position; the service rotation pauses
+  // until closeMap
+  async showServiceMap(): Promise<void> {
+    await this.request({ type: 'show_service_map' });
+  }
+  
+  closeMap(): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'close_map' });
+  }
+  
+  // The service or collapsed group under a clip-space point on the map, or null
+  async pickNode(x: number, y: number): Promise<PickedNode | null> {
+    const reply = await this.request({ type: 'pick_node', x, y });
+    return reply.picked;
+  }
+  
+  // Move a service to a clip-space point and pin it there, e.g. while dragging
+  dragNode(id: string, x: number, y: number): void {
+    if (!this.worker || !this.initialized) {
+      return;
+    }
+    
+    this.worker.postMessage({ type: 'drag_node', id, x, y });
+  }
+  
+  // Pin a service at a layout-space position
+  async pinNode(id: string, x: number, y: number): Promise<void> {
+    await this.request({ type: 'pin_node', id, x, y });
+  }
+  
+  async unpinNode(id: string): Promise<boolean> {
+    const reply = await this.request({ type: 'unpin_node', id });
+    return reply.unpinned;
+  }
+  
+  // Pinned positions of the active job as JSON
+  async exportLayout(): Promise<string> {
+    const reply = await this.request({ type: 'export_layout' });
+    return reply.json;
+  }
+  
+  async importLayout(json: string): Promise<number> {
+    const reply = await this.request({ type: 'import_layout', json });
+    return reply.count;
+  }
+  
+  // Group services on the map by name prefix or a span attribute; null ungroups
+  async setGrouping(by: GroupBy | null): Promise<ServiceGroup[]> {
+    const reply = await this.request({ type: 'set_grouping', by });
+    return reply.groups;
+  }
+  
+  // How strongly map edges bundle, from 0 (straight) to 1
+  async setEdgeBundling(strength: number): Promise<void> {
+    await this.request({ type: 'set_edge_bundling', strength });
+  }
+  
+  async collapseGroup(name: string, collapsed: boolean): Promise<ServiceGroup[]> {
+    const reply = await this.request({ type: 'collapse_group', name, collapsed });
+    return reply.groups;
+  }
+  
+  // How service IDs become labels in every view and export; null restores
+  // the default
+  async setLabelRules(rules: LabelRules | null): Promise<void> {
+    await this.request({ type: 'set_label_rules', rules });
+  }
+  
   // Start animation (only once)
   startAnimation(): void {
     if (!this.worker || !this.initialized) {
@@ -164,6 +513,9 @@ class WasmWorkerService {
       this.stopAnimation();
       this.worker.terminate();
       this.worker = null;
+      this.pending.forEach(({ reject }) => reject(new Error('Worker terminated')));
+      this.pending.clear();
+      this.progressHandlers.clear();
       this.initialized = false;
       this.animationStarted = false;
     }
@@ -177,6 +529,15 @@ class WasmWorkerService {
   get isAnimationStarted(): boolean {
     return this.animationStarted;
   }
+  
+  get knownJobs(): Array<{ job_id: string; service_count: number; span_count: number; active: boolean }> {
+    return this.jobs;
+  }
+  
+  // Annotations in the active job as of the last data update or annotation change
+  get annotationTimeline(): AnnotationTimeline | null {
+    return this.annotations;
+  }
 }
 
 // Singleton instance
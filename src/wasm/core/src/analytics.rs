@@ -0,0 +1,49 @@
+// Aggregate views computed over span stores.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::store::SpanStore;
+
+/// Service-level comparison between two jobs.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobComparison {
+    pub job_a: String,
+    pub job_b: String,
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub shared: Vec<SharedService>,
+    pub span_count_a: usize,
+    pub span_count_b: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedService {
+    pub id: String,
+    pub span_count_a: usize,
+    pub span_count_b: usize,
+}
+
+/// Compare the service sets and span counts of two stores.
+pub fn compare_stores(job_a: &str, a: &SpanStore, job_b: &str, b: &SpanStore) -> JobComparison {
+    let ids_a: BTreeSet<&str> = a.services().iter().map(|s| s.id.as_str()).collect();
+    let ids_b: BTreeSet<&str> = b.services().iter().map(|s| s.id.as_str()).collect();
+
+    JobComparison {
+        job_a: job_a.to_string(),
+        job_b: job_b.to_string(),
+        only_in_a: ids_a.difference(&ids_b).map(|id| id.to_string()).collect(),
+        only_in_b: ids_b.difference(&ids_a).map(|id| id.to_string()).collect(),
+        shared: ids_a
+            .intersection(&ids_b)
+            .map(|id| SharedService {
+                id: id.to_string(),
+                span_count_a: a.service_span_count(id),
+                span_count_b: b.service_span_count(id),
+            })
+            .collect(),
+        span_count_a: a.span_count(),
+        span_count_b: b.span_count(),
+    }
+}
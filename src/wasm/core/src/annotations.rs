@@ -0,0 +1,288 @@
+// Timeline annotations: deploys, incidents, bookmarks and notes added from
+// JavaScript, plus one entry per detected anomaly.
+//
+// Each job keeps its own list. User annotations persist until removed and
+// are what gets saved and loaded; detected ones are rebuilt whenever anomaly
+// detection reruns, so they never go stale. Navigation walks annotations in
+// time order, breaking ties by id.
+
+use serde::{Deserialize, Serialize};
+
+use crate::anomaly::{Anomaly, Severity};
+use crate::frame::Rgba;
+use crate::theme::Theme;
+
+pub const INCIDENT_COLOR: Rgba = [0.9, 0.2, 0.6, 1.0];
+pub const NOTE_COLOR: Rgba = [0.7, 0.7, 0.75, 1.0];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    Deploy,
+    Incident,
+    Anomaly,
+    #[default]
+    Bookmark,
+    Note,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationSource {
+    #[default]
+    User,
+    Detected,
+}
+
+/// A marker at a replay timestamp, optionally spanning a range and tied to a service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: u32,
+    pub timestamp_ns: i64,
+    #[serde(default)]
+    pub end_ns: Option<i64>,
+    #[serde(default)]
+    pub kind: AnnotationKind,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub service: Option<String>,
+    #[serde(default)]
+    pub severity: Option<Severity>,
+    #[serde(default)]
+    pub source: AnnotationSource,
+}
+
+impl Annotation {
+    /// Marker colour: anomalies by severity, everything else by kind.
+    pub fn color(&self, theme: &Theme) -> Rgba {
+        match (self.kind, self.severity) {
+            (_, Some(Severity::Error)) => theme.node.error,
+            (_, Some(Severity::Warning)) | (AnnotationKind::Anomaly, None) => theme.node.warning,
+            (AnnotationKind::Deploy, _) => theme.highlight,
+            (AnnotationKind::Incident, _) => theme.incident,
+            (AnnotationKind::Bookmark, _) => theme.text,
+            (AnnotationKind::Note, _) => theme.note,
+        }
+    }
+}
+
+/// What `add_annotation` takes: an annotation without its id and source.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct NewAnnotation {
+    pub timestamp_ns: i64,
+    pub end_ns: Option<i64>,
+    pub kind: AnnotationKind,
+    pub label: String,
+    pub service: Option<String>,
+    pub severity: Option<Severity>,
+}
+
+/// What `annotations` returns: the job's time range and its annotations.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotationTimeline {
+    pub start_ns: Option<i64>,
+    pub end_ns: Option<i64>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl AnnotationTimeline {
+    pub fn new(range: Option<(i64, i64)>, annotations: &[Annotation]) -> Self {
+        Self {
+            start_ns: range.map(|(start, _)| start),
+            end_ns: range.map(|(_, end)| end),
+            annotations: annotations.to_vec(),
+        }
+    }
+}
+
+/// What `jump_to_annotation` returns: the annotation and, when it flew to
+/// the annotation's service, that service's index in the rotation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotationJump {
+    pub annotation: Annotation,
+    pub service_index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    items: Vec<Annotation>,
+    next_id: u32,
+}
+
+impl Annotations {
+    /// Every annotation, in time order.
+    pub fn all(&self) -> &[Annotation] {
+        &self.items
+    }
+
+    /// Annotations added by the user, for saving.
+    pub fn user(&self) -> Vec<Annotation> {
+        self.items.iter().filter(|a| a.source == AnnotationSource::User).cloned().collect()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Annotation> {
+        self.items.iter().find(|a| a.id == id)
+    }
+
+    pub fn add(&mut self, annotation: NewAnnotation) -> u32 {
+        self.push(annotation, AnnotationSource::User)
+    }
+
+    /// Remove a user annotation. Detected ones go away only when their anomaly does.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let before = self.items.len();
+        self.items.retain(|a| a.id != id || a.source == AnnotationSource::Detected);
+        self.items.len() < before
+    }
+
+    /// Replace the user annotations with saved ones, keeping their ids.
+    pub fn load(&mut self, saved: Vec<Annotation>) {
+        self.items.retain(|a| a.source == AnnotationSource::Detected);
+        for mut annotation in saved {
+            annotation.source = AnnotationSource::User;
+            // Saved ids win; detected entries that collide are renumbered
+            if let Some(clash) = self.items.iter_mut().find(|a| a.id == annotation.id) {
+                clash.id = u32::MAX;
+            }
+            self.next_id = self.next_id.max(annotation.id.saturating_add(1));
+            self.items.push(annotation);
+        }
+        for index in 0..self.items.len() {
+            if self.items[index].id == u32::MAX {
+                self.items[index].id = self.next_id;
+                self.next_id += 1;
+            }
+        }
+        self.sort();
+    }
+
+    /// Rebuild the detected annotations from the job's anomalies.
+    pub fn set_detected(&mut self, anomalies: &[Anomaly]) {
+        self.items.retain(|a| a.source == AnnotationSource::User);
+        for anomaly in anomalies {
+            let annotation = NewAnnotation {
+                timestamp_ns: anomaly.start_ns,
+                end_ns: Some(anomaly.end_ns),
+                kind: AnnotationKind::Anomaly,
+                label: anomaly.label(),
+                service: Some(anomaly.service.clone()),
+                severity: Some(anomaly.severity),
+            };
+            self.push(annotation, AnnotationSource::Detected);
+        }
+    }
+
+    /// The annotation after `from` (or the first one), or before it when
+    /// `forward` is false. `None` at either end.
+    pub fn step(&self, from: Option<u32>, forward: bool) -> Option<&Annotation> {
+        let Some(current) = from.and_then(|id| self.items.iter().position(|a| a.id == id)) else {
+            return if forward { self.items.first() } else { self.items.last() };
+        };
+        if forward {
+            self.items.get(current + 1)
+        } else {
+            current.checked_sub(1).and_then(|index| self.items.get(index))
+        }
+    }
+
+    fn push(&mut self, annotation: NewAnnotation, source: AnnotationSource) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.items.push(Annotation {
+            id,
+            timestamp_ns: annotation.timestamp_ns,
+            end_ns: annotation.end_ns,
+            kind: annotation.kind,
+            label: annotation.label,
+            service: annotation.service,
+            severity: annotation.severity,
+            source,
+        });
+        self.sort();
+        id
+    }
+
+    fn sort(&mut self) {
+        self.items.sort_by_key(|a| (a.timestamp_ns, a.id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anomaly::{AnomalyKind, Metric};
+
+    fn note(timestamp_ns: i64, label: &str) -> NewAnnotation {
+        NewAnnotation { timestamp_ns, label: label.to_string(), ..Default::default() }
+    }
+
+    fn anomaly(service: &str, start_ns: i64) -> Anomaly {
+        Anomaly {
+            service: service.to_string(),
+            metric: Metric::Latency,
+            kind: AnomalyKind::Spike,
+            severity: Severity::Error,
+            start_ns,
+            end_ns: start_ns + 10,
+            baseline: 1.0,
+            observed: 5.0,
+            score: 8.0,
+        }
+    }
+
+    #[test]
+    fn steps_through_user_and_detected_in_time_order() {
+        let mut annotations = Annotations::default();
+        let deploy = annotations.add(NewAnnotation { kind: AnnotationKind::Deploy, ..note(200, "v2") });
+        let early = annotations.add(note(50, "start"));
+        annotations.set_detected(&[anomaly("cart", 100)]);
+
+        let labels: Vec<&str> = annotations.all().iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, ["start", "cart latency spike", "v2"]);
+
+        let second = annotations.step(Some(early), true).unwrap();
+        assert_eq!((second.source, second.severity), (AnnotationSource::Detected, Some(Severity::Error)));
+        assert_eq!(annotations.step(Some(second.id), true).unwrap().id, deploy);
+        assert!(annotations.step(Some(deploy), true).is_none());
+        assert_eq!(annotations.step(None, false).unwrap().id, deploy);
+        assert!(annotations.step(Some(early), false).is_none());
+
+        // Detection reruns replace detected entries; user ones stay
+        annotations.set_detected(&[]);
+        assert_eq!(annotations.all().len(), 2);
+        assert!(annotations.remove(early));
+        assert!(!annotations.remove(early));
+    }
+
+    #[test]
+    fn load_replaces_user_annotations_and_keeps_ids() {
+        let mut annotations = Annotations::default();
+        annotations.add(note(10, "old"));
+        annotations.set_detected(&[anomaly("auth", 30)]);
+
+        let saved = vec![Annotation {
+            id: 1,
+            timestamp_ns: 20,
+            end_ns: None,
+            kind: AnnotationKind::Incident,
+            label: "outage".to_string(),
+            service: None,
+            severity: None,
+            source: AnnotationSource::Detected,
+        }];
+        annotations.load(saved);
+
+        assert_eq!(annotations.user().len(), 1);
+        assert_eq!(annotations.get(1).unwrap().label, "outage");
+        assert_eq!(annotations.get(1).unwrap().source, AnnotationSource::User);
+        // The detected entry that had id 1 was renumbered past the saved ones
+        let detected: Vec<u32> = annotations.all().iter()
+            .filter(|a| a.source == AnnotationSource::Detected)
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(detected, [2]);
+        assert_eq!(annotations.add(note(0, "new")), 3);
+    }
+}
@@ -0,0 +1,606 @@
+// Anomaly detection over per-service latency and error-rate series.
+//
+// Spans are bucketed into fixed time windows per service. Each series runs
+// through a streaming detector: an EWMA baseline with its variance scores
+// every window as a z-score, flagging spikes above the threshold, while a
+// one-sided CUSUM over the same scores catches sustained shifts too small
+// to spike. The baseline is held while CUSUM is accumulating. A detected
+// shift re-baselines the detector at the new level, so one change is
+// reported once rather than as a run of spikes. Only increases count:
+// lower latency or fewer errors are never anomalous.
+//
+// A `Monitor` keeps that state between ingests. New spans only touch their
+// own windows, and a window is fed to the detectors once a newer one has
+// opened, so each ingest costs what it adds rather than what is stored.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnomalyOptions {
+    /// Width of the buckets each series is built from.
+    pub window_ns: i64,
+    /// EWMA smoothing factor; higher adapts faster.
+    pub alpha: f64,
+    /// z-score at which a window is flagged as a spike. Twice this is an error.
+    pub z_threshold: f64,
+    /// Windows used to seed the baseline before anything is flagged.
+    pub warmup: usize,
+    /// CUSUM slack, in standard deviations, ignored per window.
+    pub cusum_k: f64,
+    /// CUSUM total, in standard deviations, that signals a shift.
+    pub cusum_h: f64,
+}
+
+impl Default for AnomalyOptions {
+    fn default() -> Self {
+        Self {
+            window_ns: 10_000_000_000,
+            alpha: 0.3,
+            z_threshold: 3.0,
+            warmup: 5,
+            cusum_k: 0.5,
+            cusum_h: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Mean span duration in the window, in nanoseconds.
+    Latency,
+    /// Fraction of the window's spans with an error status.
+    ErrorRate,
+}
+
+impl Metric {
+    // Deviations below this are noise however flat the baseline is
+    fn min_deviation(self, baseline: f64) -> f64 {
+        match self {
+            Metric::Latency => (baseline.abs() * 0.05).max(1.0),
+            Metric::ErrorRate => 0.02,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Windows well above the baseline.
+    Spike,
+    /// A sustained move to a higher level, found by CUSUM.
+    Shift,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The `ServiceNode.status` this severity maps to.
+    pub fn status(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A time range where one service's metric deviated from its baseline.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Anomaly {
+    pub service: String,
+    pub metric: Metric,
+    pub kind: AnomalyKind,
+    pub severity: Severity,
+    pub start_ns: i64,
+    pub end_ns: i64,
+    /// Baseline level before the deviation.
+    pub baseline: f64,
+    /// Highest value seen in the range.
+    pub observed: f64,
+    /// Highest z-score in the range.
+    pub score: f64,
+}
+
+impl Anomaly {
+    /// Short text for timeline markers, e.g. "checkout latency shift".
+    pub fn label(&self) -> String {
+        let metric = match self.metric {
+            Metric::Latency => "latency",
+            Metric::ErrorRate => "errors",
+        };
+        let kind = match self.kind {
+            AnomalyKind::Spike => "spike",
+            AnomalyKind::Shift => "shift",
+        };
+        format!("{} {} {}", self.service, metric, kind)
+    }
+}
+
+/// An anomaly as a timeline marker, with its label.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnomalyMarker {
+    pub label: String,
+    #[serde(flatten)]
+    pub anomaly: Anomaly,
+}
+
+/// What `anomalies` returns: the job's time range and its anomalies in it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnomalyTimeline {
+    pub start_ns: Option<i64>,
+    pub end_ns: Option<i64>,
+    pub markers: Vec<AnomalyMarker>,
+}
+
+impl AnomalyTimeline {
+    pub fn new(range: Option<(i64, i64)>, anomalies: &[Anomaly]) -> Self {
+        Self {
+            start_ns: range.map(|(start, _)| start),
+            end_ns: range.map(|(_, end)| end),
+            markers: anomalies
+                .iter()
+                .map(|anomaly| AnomalyMarker { label: anomaly.label(), anomaly: anomaly.clone() })
+                .collect(),
+        }
+    }
+}
+
+/// Spans that count as failed for the error-rate series.
+pub fn is_error_status(status: &str) -> bool {
+    let status = status.to_ascii_lowercase();
+    status.contains("error") || status.contains("fail")
+}
+
+/// Per-window totals for one service.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Window {
+    pub start_ns: i64,
+    pub spans: usize,
+    pub errors: usize,
+    pub duration_sum_ns: f64,
+    pub timed_spans: usize,
+}
+
+/// Bucket `(service, timestamp_ns, duration_ns, status)` tuples into
+/// `window_ns` windows. Windows without spans are left out.
+pub fn service_windows<'a, I>(spans: I, window_ns: i64) -> BTreeMap<String, Vec<Window>>
+where
+    I: IntoIterator<Item = (&'a str, i64, Option<i64>, Option<&'a str>)>,
+{
+    let window_ns = window_ns.max(1);
+    let mut buckets: BTreeMap<&str, BTreeMap<i64, Window>> = BTreeMap::new();
+    for (service, timestamp_ns, duration_ns, status) in spans {
+        let start_ns = timestamp_ns.div_euclid(window_ns) * window_ns;
+        let window = buckets.entry(service).or_default().entry(start_ns).or_insert(Window {
+            start_ns,
+            ..Default::default()
+        });
+        window.spans += 1;
+        if status.is_some_and(is_error_status) {
+            window.errors += 1;
+        }
+        if let Some(duration_ns) = duration_ns {
+            window.duration_sum_ns += duration_ns as f64;
+            window.timed_spans += 1;
+        }
+    }
+    buckets
+        .into_iter()
+        .map(|(service, windows)| (service.to_string(), windows.into_values().collect()))
+        .collect()
+}
+
+// What the detector made of one window
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flag {
+    Spike { z: f64, baseline: f64 },
+    // The shift began in the window starting at `since_ns`
+    Shift { z: f64, baseline: f64, since_ns: i64 },
+}
+
+/// Streaming detector for one series: EWMA z-scores plus CUSUM.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    metric: Metric,
+    options: AnomalyOptions,
+    mean: f64,
+    variance: f64,
+    seen: usize,
+    cusum: f64,
+    cusum_start_ns: i64,
+}
+
+impl Detector {
+    pub fn new(metric: Metric, options: AnomalyOptions) -> Self {
+        Self { metric, options, mean: 0.0, variance: 0.0, seen: 0, cusum: 0.0, cusum_start_ns: 0 }
+    }
+
+    // Score the value of the window starting at `start_ns`
+    fn push(&mut self, start_ns: i64, value: f64) -> Option<Flag> {
+        self.seen += 1;
+        if self.seen == 1 {
+            self.mean = value;
+            return None;
+        }
+        if self.seen <= self.options.warmup {
+            self.update(value);
+            return None;
+        }
+
+        let deviation = self.variance.sqrt().max(self.metric.min_deviation(self.mean));
+        let z = (value - self.mean) / deviation;
+        let baseline = self.mean;
+
+        // Scores are capped so one huge spike cannot pass for a shift
+        if self.cusum == 0.0 {
+            self.cusum_start_ns = start_ns;
+        }
+        self.cusum = (self.cusum + z.min(self.options.z_threshold) - self.options.cusum_k).max(0.0);
+        if self.cusum > self.options.cusum_h {
+            let since_ns = self.cusum_start_ns;
+            // Re-baseline at the new level, keeping the spread
+            self.mean = value;
+            self.cusum = 0.0;
+            return Some(Flag::Shift { z, baseline, since_ns });
+        }
+
+        if z >= self.options.z_threshold {
+            // Spikes stay out of the baseline so a run of them keeps flagging
+            return Some(Flag::Spike { z, baseline });
+        }
+        // Hold the baseline while a possible shift builds up, or the EWMA
+        // would absorb it before CUSUM can tell
+        if self.cusum == 0.0 {
+            self.update(value);
+        }
+        None
+    }
+
+    fn update(&mut self, value: f64) {
+        let alpha = self.options.alpha;
+        let delta = value - self.mean;
+        self.mean += alpha * delta;
+        self.variance = (1.0 - alpha) * (self.variance + alpha * delta * delta);
+    }
+}
+
+// The value a window contributes to a metric's series, if any
+fn window_value(metric: Metric, window: &Window) -> Option<f64> {
+    match metric {
+        Metric::Latency if window.timed_spans > 0 => Some(window.duration_sum_ns / window.timed_spans as f64),
+        Metric::ErrorRate if window.spans > 0 => Some(window.errors as f64 / window.spans as f64),
+        _ => None,
+    }
+}
+
+// One service's metric: its detector and what it flagged so far
+#[derive(Debug, Clone)]
+struct Series {
+    metric: Metric,
+    detector: Detector,
+    found: Vec<Anomaly>,
+}
+
+impl Series {
+    fn new(metric: Metric, options: AnomalyOptions) -> Self {
+        Self { metric, detector: Detector::new(metric, options), found: Vec::new() }
+    }
+
+    // Score the next window, merging adjacent flags
+    fn push(&mut self, service: &str, window: &Window) {
+        let Some(value) = window_value(self.metric, window) else { return };
+        let options = self.detector.options;
+        let (start_ns, end_ns) = (window.start_ns, window.start_ns + options.window_ns);
+        let severity = |score: f64| {
+            if score >= 2.0 * options.z_threshold {
+                Severity::Error
+            } else {
+                Severity::Warning
+            }
+        };
+        let found = &mut self.found;
+
+        match self.detector.push(start_ns, value) {
+            Some(Flag::Spike { z, baseline }) => match found.last_mut() {
+                // Extend a spike that ended in the previous window
+                Some(last) if last.kind == AnomalyKind::Spike && last.end_ns == start_ns => {
+                    last.end_ns = end_ns;
+                    last.observed = last.observed.max(value);
+                    last.score = last.score.max(z);
+                    last.severity = severity(last.score);
+                }
+                _ => found.push(Anomaly {
+                    service: service.to_string(),
+                    metric: self.metric,
+                    kind: AnomalyKind::Spike,
+                    severity: severity(z),
+                    start_ns,
+                    end_ns,
+                    baseline,
+                    observed: value,
+                    score: z,
+                }),
+            },
+            Some(Flag::Shift { z, baseline, since_ns }) => {
+                // The shift absorbs the spikes flagged while it built up
+                let mut score = z;
+                let mut observed = value;
+                while let Some(last) = found.last() {
+                    if last.start_ns < since_ns {
+                        break;
+                    }
+                    score = score.max(last.score);
+                    observed = observed.max(last.observed);
+                    found.pop();
+                }
+                found.push(Anomaly {
+                    service: service.to_string(),
+                    metric: self.metric,
+                    kind: AnomalyKind::Shift,
+                    severity: severity(score),
+                    start_ns: since_ns,
+                    end_ns,
+                    baseline,
+                    observed,
+                    score,
+                });
+            }
+            None => {}
+        }
+    }
+}
+
+/// Run both metrics of every service through a detector in one pass.
+pub fn detect(windows: &BTreeMap<String, Vec<Window>>, options: &AnomalyOptions) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    for (service, windows) in windows {
+        for metric in [Metric::Latency, Metric::ErrorRate] {
+            let mut series = Series::new(metric, *options);
+            for window in windows {
+                series.push(service, window);
+            }
+            anomalies.extend(series.found);
+        }
+    }
+    sort(&mut anomalies);
+    anomalies
+}
+
+fn sort(anomalies: &mut [Anomaly]) {
+    anomalies.sort_by(|a, b| a.start_ns.cmp(&b.start_ns).then_with(|| a.service.cmp(&b.service)));
+}
+
+// Windows of one service that the detectors have not consumed yet, plus
+// the detectors
+#[derive(Debug, Clone)]
+struct ServiceSeries {
+    open: BTreeMap<i64, Window>,
+    // Windows starting before this were fed to the detectors
+    fed_until_ns: i64,
+    latency: Series,
+    errors: Series,
+}
+
+/// Streaming detection over one job's spans.
+///
+/// `add` buckets new spans into their services' windows. Once a service has
+/// a newer window, the older ones are fed to its detectors and dropped; the
+/// newest is still filling, so `anomalies` scores it on a copy. A span
+/// landing in a window already fed would need its series replayed, which
+/// `is_stale` reports so the caller can start over from the full store.
+#[derive(Debug, Clone, Default)]
+pub struct Monitor {
+    options: AnomalyOptions,
+    services: BTreeMap<String, ServiceSeries>,
+    stale: bool,
+}
+
+impl Monitor {
+    pub fn new(options: AnomalyOptions) -> Self {
+        Self { options, ..Default::default() }
+    }
+
+    /// Add `(service, timestamp_ns, duration_ns, status)` tuples, as
+    /// `service_windows` takes them.
+    pub fn add<'a, I>(&mut self, spans: I)
+    where
+        I: IntoIterator<Item = (&'a str, i64, Option<i64>, Option<&'a str>)>,
+    {
+        let options = self.options;
+        let window_ns = options.window_ns.max(1);
+        let mut touched = Vec::new();
+        for (service, timestamp_ns, duration_ns, status) in spans {
+            let start_ns = timestamp_ns.div_euclid(window_ns) * window_ns;
+            if !self.services.contains_key(service) {
+                let series = ServiceSeries {
+                    open: BTreeMap::new(),
+                    fed_until_ns: i64::MIN,
+                    latency: Series::new(Metric::Latency, options),
+                    errors: Series::new(Metric::ErrorRate, options),
+                };
+                self.services.insert(service.to_string(), series);
+            }
+            let series = self.services.get_mut(service).expect("service was just added");
+            if start_ns < series.fed_until_ns {
+                self.stale = true;
+                continue;
+            }
+            let window = series.open.entry(start_ns).or_insert(Window { start_ns, ..Default::default() });
+            window.spans += 1;
+            if status.is_some_and(is_error_status) {
+                window.errors += 1;
+            }
+            if let Some(duration_ns) = duration_ns {
+                window.duration_sum_ns += duration_ns as f64;
+                window.timed_spans += 1;
+            }
+            if touched.last().map(String::as_str) != Some(service) {
+                touched.push(service.to_string());
+            }
+        }
+
+        touched.sort();
+        touched.dedup();
+        for service in touched {
+            let Some(series) = self.services.get_mut(&service) else { continue };
+            while series.open.len() > 1 {
+                let (start_ns, window) = series.open.pop_first().expect("more than one window is open");
+                series.latency.push(&service, &window);
+                series.errors.push(&service, &window);
+                series.fed_until_ns = start_ns + window_ns;
+            }
+        }
+    }
+
+    /// True once a span arrived in a window the detectors already consumed.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Every anomaly so far, oldest first, including the still-open windows.
+    pub fn anomalies(&self) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+        for (service, series) in &self.services {
+            for metric in [&series.latency, &series.errors] {
+                match series.open.last_key_value() {
+                    Some((_, window)) => {
+                        let mut metric = metric.clone();
+                        metric.push(service, window);
+                        anomalies.extend(metric.found);
+                    }
+                    None => anomalies.extend(metric.found.iter().cloned()),
+                }
+            }
+        }
+        sort(&mut anomalies);
+        anomalies
+    }
+}
+
+/// The status each service gets from `anomalies`: its worst severity, or
+/// "healthy" when none of them concerns it.
+pub fn service_status<'a>(anomalies: &'a [Anomaly]) -> impl Fn(&str) -> &'static str + 'a {
+    move |service| {
+        anomalies
+            .iter()
+            .filter(|anomaly| anomaly.service == service)
+            .map(|anomaly| anomaly.severity)
+            .max()
+            .map_or("healthy", Severity::status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = 1_000_000_000;
+
+    fn options() -> AnomalyOptions {
+        AnomalyOptions { window_ns: SECOND, ..Default::default() }
+    }
+
+    // One latency window per second, with the given mean
+    fn latency(values: &[f64]) -> Vec<Anomaly> {
+        let windows: Vec<Window> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| Window {
+                start_ns: i as i64 * SECOND,
+                spans: 1,
+                duration_sum_ns: v,
+                timed_spans: 1,
+                ..Default::default()
+            })
+            .collect();
+        detect(&BTreeMap::from([("cart".to_string(), windows)]), &options())
+    }
+
+    fn noisy(len: usize, level: f64) -> Vec<f64> {
+        (0..len).map(|i| level + [0.0, 3.0, -2.0, 1.0, -1.0][i % 5]).collect()
+    }
+
+    #[test]
+    fn isolated_spike_is_flagged_once() {
+        let mut values = noisy(30, 100.0);
+        values[20] = 200.0;
+        values[21] = 190.0;
+        let found = latency(&values);
+
+        assert_eq!(found.len(), 1);
+        let spike = &found[0];
+        assert_eq!((spike.kind, spike.severity), (AnomalyKind::Spike, Severity::Error));
+        assert_eq!((spike.start_ns, spike.end_ns), (20 * SECOND, 22 * SECOND));
+        assert_eq!(spike.observed, 200.0);
+        assert!((spike.baseline - 100.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn sustained_small_shift_is_found_by_cusum() {
+        // +8 is well under the spike threshold but never goes away
+        let mut values = noisy(20, 100.0);
+        values.extend(noisy(20, 108.0));
+        let found = latency(&values);
+
+        assert_eq!(found.len(), 1, "{:?}", found);
+        let shift = &found[0];
+        assert_eq!(shift.kind, AnomalyKind::Shift);
+        assert!(shift.start_ns >= 20 * SECOND && shift.start_ns <= 22 * SECOND, "{:?}", shift);
+        assert!(shift.end_ns <= 30 * SECOND);
+    }
+
+    #[test]
+    fn errors_feed_service_status() {
+        let mut spans = Vec::new();
+        for second in 0..20 {
+            for i in 0..10 {
+                let status = if second >= 15 && i < 5 { "ERROR" } else { "ok" };
+                spans.push(("auth", second * SECOND + i, Some(1_000), Some(status)));
+            }
+            spans.push(("cart", second * SECOND, Some(1_000), None));
+        }
+        let windows = service_windows(spans.iter().copied(), SECOND);
+        assert_eq!(windows["auth"].len(), 20);
+        assert_eq!(windows["auth"][15].errors, 5);
+
+        let anomalies = detect(&windows, &options());
+        assert!(anomalies.iter().all(|a| a.service == "auth" && a.metric == Metric::ErrorRate));
+        assert_eq!(anomalies[0].start_ns, 15 * SECOND);
+        assert_eq!(anomalies[0].label(), "auth errors shift");
+
+        let status = service_status(&anomalies);
+        assert_eq!((status("auth"), status("cart")), ("error", "healthy"));
+        assert!(detect(&BTreeMap::new(), &options()).is_empty());
+    }
+
+    #[test]
+    fn monitor_matches_a_full_pass_and_flags_late_spans() {
+        let spans: Vec<_> = (0..40i64)
+            .map(|second| {
+                let duration = if second >= 20 { 1_800 } else { 1_000 + (second % 3) * 10 };
+                ("cart", second * SECOND, Some(duration), None)
+            })
+            .collect();
+        let mut monitor = Monitor::new(options());
+        for chunk in spans.chunks(7) {
+            monitor.add(chunk.iter().copied());
+        }
+        let full = detect(&service_windows(spans.iter().copied(), SECOND), &options());
+        assert!(!full.is_empty());
+        assert_eq!(monitor.anomalies(), full);
+        // Only the newest window is still held
+        assert_eq!(monitor.services["cart"].open.len(), 1);
+
+        assert!(!monitor.is_stale());
+        monitor.add([("cart", 3 * SECOND, Some(1_000), None)]);
+        assert!(monitor.is_stale());
+    }
+}
@@ -0,0 +1,84 @@
+// Hierarchical edge bundling (Holten 2006).
+//
+// An edge is routed through the control points of its path up the service
+// hierarchy and back down, and drawn as a cubic B-spline over them, so edges
+// that share part of the hierarchy share part of their route. The strength
+// straightens each route toward the direct line first: 0 draws straight
+// edges and 1 follows the hierarchy fully. The spline is returned as a chain
+// of cubic Béziers, which every backend can draw.
+
+use crate::scene::Primitive;
+
+/// How strongly map edges follow the hierarchy when nothing is set.
+pub const DEFAULT_BUNDLING: f32 = 0.85;
+
+/// `control` pulled toward the line between its ends by `1 - strength`.
+pub fn straighten(control: &[[f32; 2]], strength: f32) -> Vec<[f32; 2]> {
+    let (Some(&first), Some(&last)) = (control.first(), control.last()) else { return Vec::new() };
+    let strength = strength.clamp(0.0, 1.0);
+    let steps = control.len().saturating_sub(1).max(1) as f32;
+    control
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            let t = i as f32 / steps;
+            let line = [first[0] + t * (last[0] - first[0]), first[1] + t * (last[1] - first[1])];
+            [
+                strength * point[0] + (1.0 - strength) * line[0],
+                strength * point[1] + (1.0 - strength) * line[1],
+            ]
+        })
+        .collect()
+}
+
+/// A uniform cubic B-spline over `control`, clamped so it starts and ends at
+/// the first and last points, as a `Primitive::Curve`.
+pub fn spline(control: &[[f32; 2]]) -> Primitive {
+    let (Some(&first), Some(&last)) = (control.first(), control.last()) else {
+        return Primitive::Curve { start: [0.0, 0.0], segments: Vec::new() };
+    };
+    let mut points = vec![first, first];
+    points.extend_from_slice(control);
+    points.extend([last, last]);
+
+    let mix = |weights: [f32; 3], p: [[f32; 2]; 3]| {
+        [0, 1].map(|axis| (weights[0] * p[0][axis] + weights[1] * p[1][axis] + weights[2] * p[2][axis]) / 6.0)
+    };
+    let segments = points
+        .windows(4)
+        .map(|p| {
+            [
+                mix([0.0, 4.0, 2.0], [p[0], p[1], p[2]]),
+                mix([0.0, 2.0, 4.0], [p[0], p[1], p[2]]),
+                mix([1.0, 4.0, 1.0], [p[1], p[2], p[3]]),
+            ]
+        })
+        .collect();
+    Primitive::Curve { start: first, segments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strength_blends_between_the_route_and_a_straight_line() {
+        let route = [[0.0, 0.0], [0.5, 1.0], [1.0, 0.0]];
+        assert_eq!(straighten(&route, 1.0), route);
+        assert_eq!(straighten(&route, 0.0), [[0.0, 0.0], [0.5, 0.0], [1.0, 0.0]]);
+        assert_eq!(straighten(&route, 0.5)[1], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn splines_are_clamped_to_their_ends() {
+        let Primitive::Curve { start, segments } = spline(&[[0.0, 0.0], [0.5, 0.75], [1.0, 0.0]]) else {
+            panic!("not a curve")
+        };
+        assert_eq!(start, [0.0, 0.0]);
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[3][2], [1.0, 0.0]);
+        // The middle of the curve is pulled toward, but not onto, the control point
+        let middle = segments[1][2];
+        assert_eq!(middle, [0.5, 0.5]);
+    }
+}
@@ -0,0 +1,410 @@
+// Replay diffing: what changed between two jobs or two time windows.
+//
+// Each side is a job, optionally narrowed to spans starting in a time
+// window. Both sides are reduced to per-service and per-edge totals (an
+// edge is a call from a parent span's service to its child's), which are
+// compared for rate, error rate and mean latency. The diff map draws every
+// service and edge from either side, coloured by how much worse or better
+// it got. An active filter fades or drops services with no matching span
+// on either side, with their edges.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::anomaly::is_error_status;
+use crate::frame::Rgba;
+use crate::geometry::{extract_service_number, text_width, CHAR_HEIGHT};
+use crate::filter::FilterMatch;
+use crate::labels::Labels;
+use crate::layout;
+use crate::scene::{Node, Primitive, Scene, Style, DIMMED_OPACITY};
+use crate::theme::Theme;
+
+pub const ADDED_COLOR: Rgba = [0.25, 0.6, 1.0, 1.0];
+pub const REMOVED_COLOR: Rgba = [0.45, 0.45, 0.5, 1.0];
+pub const UNCHANGED_COLOR: Rgba = [0.55, 0.55, 0.6, 1.0];
+pub const WORSE_COLOR: Rgba = [1.0, 0.25, 0.25, 1.0];
+pub const BETTER_COLOR: Rgba = [0.2, 0.85, 0.4, 1.0];
+
+/// Scores below this magnitude count as unchanged.
+pub const CHANGE_THRESHOLD: f32 = 0.1;
+
+const NODE_RADIUS: f32 = 0.05;
+const RING_RADIUS: f32 = 0.7;
+
+/// One side of a diff: a job, optionally only spans starting in `start_ns..end_ns`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSide {
+    pub job: String,
+    #[serde(default)]
+    pub start_ns: Option<i64>,
+    #[serde(default)]
+    pub end_ns: Option<i64>,
+}
+
+impl DiffSide {
+    pub fn range(&self) -> Option<(i64, i64)> {
+        match (self.start_ns, self.end_ns) {
+            (None, None) => None,
+            (start, end) => Some((start.unwrap_or(i64::MIN), end.unwrap_or(i64::MAX))),
+        }
+    }
+}
+
+/// Running totals for one service or edge.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counts {
+    pub spans: usize,
+    pub errors: usize,
+    pub duration_sum_ns: f64,
+    pub timed_spans: usize,
+}
+
+impl Counts {
+    pub fn add(&mut self, duration_ns: Option<i64>, status: Option<&str>) {
+        self.spans += 1;
+        if status.is_some_and(is_error_status) {
+            self.errors += 1;
+        }
+        if let Some(duration_ns) = duration_ns {
+            self.duration_sum_ns += duration_ns as f64;
+            self.timed_spans += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Counts) {
+        self.spans += other.spans;
+        self.errors += other.errors;
+        self.duration_sum_ns += other.duration_sum_ns;
+        self.timed_spans += other.timed_spans;
+    }
+}
+
+/// Everything one side of a diff is reduced to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Totals {
+    pub services: BTreeMap<String, Counts>,
+    pub edges: BTreeMap<(String, String), Counts>,
+    /// Time the rate is measured over; `None` when no span has a timestamp.
+    pub duration_ns: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Metrics {
+    pub spans: usize,
+    pub errors: usize,
+    /// Spans per second over the side's time range.
+    pub rate_per_s: Option<f64>,
+    pub error_rate: f64,
+    pub mean_latency_ns: Option<f64>,
+}
+
+impl Metrics {
+    pub fn new(counts: &Counts, duration_ns: Option<i64>) -> Self {
+        Self {
+            spans: counts.spans,
+            errors: counts.errors,
+            rate_per_s: duration_ns
+                .filter(|&ns| ns > 0)
+                .map(|ns| counts.spans as f64 / (ns as f64 / 1_000_000_000.0)),
+            error_rate: counts.errors as f64 / counts.spans.max(1) as f64,
+            mean_latency_ns: (counts.timed_spans > 0)
+                .then(|| counts.duration_sum_ns / counts.timed_spans as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+/// How one service or edge differs between the sides.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Delta {
+    pub change: Change,
+    pub before: Option<Metrics>,
+    pub after: Option<Metrics>,
+    pub rate_delta: Option<f64>,
+    pub error_rate_delta: Option<f64>,
+    pub latency_delta_ns: Option<f64>,
+    /// How much worse (positive, up to 1) or better (negative) it got: the
+    /// relative latency change or ten times the error-rate change, whichever
+    /// is larger. Rate changes alone do not count.
+    pub score: f32,
+}
+
+impl Delta {
+    fn new(before: Option<Metrics>, after: Option<Metrics>) -> Self {
+        let (rate_delta, error_rate_delta, latency_delta_ns, score) = match (&before, &after) {
+            (Some(b), Some(a)) => {
+                let latency = match (b.mean_latency_ns, a.mean_latency_ns) {
+                    (Some(b), Some(a)) => Some(a - b),
+                    _ => None,
+                };
+                let relative = match (latency, b.mean_latency_ns) {
+                    (Some(delta), Some(b)) if b > 0.0 => delta / b,
+                    _ => 0.0,
+                };
+                let errors = a.error_rate - b.error_rate;
+                let score = if relative.abs() >= (errors * 10.0).abs() { relative } else { errors * 10.0 };
+                let rate = match (b.rate_per_s, a.rate_per_s) {
+                    (Some(b), Some(a)) => Some(a - b),
+                    _ => None,
+                };
+                (rate, Some(errors), latency, (score as f32).clamp(-1.0, 1.0))
+            }
+            _ => (None, None, None, 0.0),
+        };
+        let change = match (&before, &after) {
+            (None, _) => Change::Added,
+            (_, None) => Change::Removed,
+            _ if score.abs() >= CHANGE_THRESHOLD => Change::Changed,
+            _ => Change::Unchanged,
+        };
+        Self { change, before, after, rate_delta, error_rate_delta, latency_delta_ns, score }
+    }
+
+    /// Map colour: blue for added, grey for removed or unchanged, shading
+    /// to red as things get worse and green as they get better.
+    pub fn color(&self, theme: &Theme) -> Rgba {
+        let colors = &theme.diff;
+        match self.change {
+            Change::Added => colors.added,
+            Change::Removed => colors.removed,
+            Change::Unchanged => colors.unchanged,
+            Change::Changed => {
+                let target = if self.score > 0.0 { colors.worse } else { colors.better };
+                let t = self.score.abs();
+                std::array::from_fn(|i| colors.unchanged[i] + (target[i] - colors.unchanged[i]) * t)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceDelta {
+    pub service: String,
+    #[serde(flatten)]
+    pub delta: Delta,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeDelta {
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub delta: Delta,
+}
+
+/// Result of `diff_replays`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayDiff {
+    pub before: DiffSide,
+    pub after: DiffSide,
+    pub services: Vec<ServiceDelta>,
+    pub edges: Vec<EdgeDelta>,
+}
+
+impl ReplayDiff {
+    pub fn count(&self, change: Change) -> usize {
+        self.services.iter().filter(|s| s.delta.change == change).count()
+    }
+}
+
+/// Compare the totals of two sides.
+pub fn diff(before: DiffSide, a: &Totals, after: DiffSide, b: &Totals) -> ReplayDiff {
+    let metrics = |totals: &Totals, counts: Option<&Counts>| counts.map(|c| Metrics::new(c, totals.duration_ns));
+
+    let services: BTreeSet<&String> = a.services.keys().chain(b.services.keys()).collect();
+    let edges: BTreeSet<&(String, String)> = a.edges.keys().chain(b.edges.keys()).collect();
+    ReplayDiff {
+        services: services
+            .into_iter()
+            .map(|service| ServiceDelta {
+                service: service.clone(),
+                delta: Delta::new(metrics(a, a.services.get(service)), metrics(b, b.services.get(service))),
+            })
+            .collect(),
+        edges: edges
+            .into_iter()
+            .map(|edge| EdgeDelta {
+                from: edge.0.clone(),
+                to: edge.1.clone(),
+                delta: Delta::new(metrics(a, a.edges.get(edge)), metrics(b, b.edges.get(edge))),
+            })
+            .collect(),
+        before,
+        after,
+    }
+}
+
+/// The diff map: services on a ring, edges between them, all coloured by
+/// their delta. Removed services and edges are outlined only. With a
+/// `filter`, services it does not match are faded or left off the ring,
+/// and so are edges touching them.
+pub fn diff_scene(diff: &ReplayDiff, filter: Option<&FilterMatch>, labels: &Labels, theme: &Theme) -> Scene {
+    let mut scene = Scene::new(theme.background);
+    let header = format!("{} VS {}", side_label(&diff.before), side_label(&diff.after));
+    scene.add(
+        Primitive::Text { text: header, origin: [-0.95, 0.85], size: CHAR_HEIGHT * 0.6 },
+        Style::fill(theme.text),
+    );
+
+    let dims = |service: &str| filter.is_some_and(|f| f.dims(service));
+    let services: Vec<&ServiceDelta> =
+        diff.services.iter().filter(|s| filter.is_none_or(|f| f.shows(&s.service))).collect();
+    let positions = layout::ring(services.len(), RING_RADIUS);
+    let position: BTreeMap<&str, [f32; 2]> = services
+        .iter()
+        .zip(&positions)
+        .map(|(service, &at)| (service.service.as_str(), at))
+        .collect();
+
+    for edge in &diff.edges {
+        let (Some(&from), Some(&to)) = (position.get(edge.from.as_str()), position.get(edge.to.as_str())) else {
+            continue;
+        };
+        let mut style = Style::stroke(edge.delta.color(theme), 0.01);
+        if edge.delta.change == Change::Removed {
+            style = style.with_opacity(0.5);
+        }
+        if dims(&edge.from) || dims(&edge.to) {
+            style.opacity *= DIMMED_OPACITY;
+        }
+        scene.insert(Scene::ROOT, Node::shape(Primitive::Line { from, to }, style).z(-1));
+    }
+
+    for (service, &center) in services.iter().zip(&positions) {
+        let color = service.delta.color(theme);
+        let style = match service.delta.change {
+            Change::Removed => Style::fill(theme.background).with_stroke(color, 0.01),
+            _ => Style::fill(color),
+        };
+        let opacity = if dims(&service.service) { DIMMED_OPACITY } else { 1.0 };
+        scene.add(Primitive::Circle { center, radius: NODE_RADIUS }, style.with_opacity(opacity));
+
+        let label = labels.label(&service.service);
+        let size = CHAR_HEIGHT * 0.35;
+        let width = text_width(&label) * size / CHAR_HEIGHT;
+        scene.add(
+            Primitive::Text {
+                text: label,
+                origin: [center[0] - width / 2.0, center[1] - NODE_RADIUS - size - 0.02],
+                size,
+            },
+            Style::fill(theme.text).with_opacity(opacity),
+        );
+    }
+    scene
+}
+
+fn side_label(side: &DiffSide) -> String {
+    match side.range() {
+        None => extract_service_number(&side.job),
+        Some(_) => format!("{}:WINDOW", extract_service_number(&side.job)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterMode;
+
+    fn side(job: &str) -> DiffSide {
+        DiffSide { job: job.to_string(), start_ns: None, end_ns: None }
+    }
+
+    fn counts(spans: usize, errors: usize, latency_ns: f64) -> Counts {
+        Counts { spans, errors, duration_sum_ns: latency_ns * spans as f64, timed_spans: spans }
+    }
+
+    fn totals(services: &[(&str, Counts)], edges: &[(&str, &str, Counts)]) -> Totals {
+        Totals {
+            services: services.iter().map(|(s, c)| (s.to_string(), *c)).collect(),
+            edges: edges.iter().map(|(f, t, c)| ((f.to_string(), t.to_string()), *c)).collect(),
+            duration_ns: Some(10_000_000_000),
+        }
+    }
+
+    #[test]
+    fn deltas_classify_and_score_changes() {
+        let before = totals(
+            &[("gateway", counts(100, 0, 100.0)), ("cart", counts(50, 0, 100.0)), ("legacy", counts(5, 0, 10.0))],
+            &[("gateway", "cart", counts(50, 0, 100.0)), ("gateway", "legacy", counts(5, 0, 10.0))],
+        );
+        let after = totals(
+            &[("gateway", counts(200, 0, 105.0)), ("cart", counts(50, 10, 90.0)), ("search", counts(10, 0, 5.0))],
+            &[("gateway", "cart", counts(50, 10, 150.0)), ("gateway", "search", counts(10, 0, 5.0))],
+        );
+        let diff = diff(side("mon"), &before, side("tue"), &after);
+
+        let service = |id: &str| &diff.services.iter().find(|s| s.service == id).unwrap().delta;
+        // Twice the traffic at about the same latency is not a change
+        assert_eq!(service("gateway").change, Change::Unchanged);
+        assert_eq!(service("gateway").rate_delta, Some(10.0));
+        // 20% errors outweighs a 10% speedup
+        assert_eq!((service("cart").change, service("cart").score), (Change::Changed, 1.0));
+        assert_eq!(service("search").change, Change::Added);
+        assert_eq!(service("legacy").change, Change::Removed);
+        assert_eq!((diff.count(Change::Added), diff.count(Change::Removed)), (1, 1));
+
+        let edge = |to: &str| &diff.edges.iter().find(|e| e.to == to).unwrap().delta;
+        assert_eq!(edge("cart").latency_delta_ns, Some(50.0));
+        assert_eq!((edge("legacy").change, edge("search").change), (Change::Removed, Change::Added));
+    }
+
+    #[test]
+    fn colors_shade_with_the_score() {
+        let before = totals(&[("a", counts(10, 0, 100.0)), ("b", counts(10, 0, 100.0))], &[]);
+        let after = totals(&[("a", counts(10, 0, 150.0)), ("b", counts(10, 0, 40.0))], &[]);
+        let diff = diff(side("x"), &before, side("y"), &after);
+
+        let [worse, better] = [&diff.services[0].delta, &diff.services[1].delta];
+        let [worse, better] = [worse, better].map(|delta| delta.color(&Theme::default()));
+        assert!(worse[0] > UNCHANGED_COLOR[0] && worse[1] < UNCHANGED_COLOR[1]);
+        assert!(better[1] > UNCHANGED_COLOR[1] && better[0] < UNCHANGED_COLOR[0]);
+
+        // Header plus a node and a label per service
+        assert_eq!(diff_scene(&diff, None, &Labels::default(), &Theme::default()).len(), 1 + 2 * 2);
+    }
+
+    #[test]
+    fn filtered_services_fade_or_leave_the_ring() {
+        let side_totals = totals(
+            &[("a", counts(10, 0, 100.0)), ("b", counts(10, 0, 100.0))],
+            &[("a", "b", counts(10, 0, 100.0))],
+        );
+        let diff = diff(side("x"), &side_totals, side("y"), &side_totals);
+        let draw = |mode| {
+            let filter = FilterMatch { mode, services: [("a".to_string(), 1)].into(), spans: 1 };
+            diff_scene(&diff, Some(&filter), &Labels::default(), &Theme::default())
+        };
+        // Circles, or with `lines` edges, in draw order
+        let opacities = |scene: &Scene, lines: bool| -> Vec<f32> {
+            scene
+                .draw_list()
+                .iter()
+                .filter(|item| match item.primitive {
+                    Primitive::Line { .. } => lines,
+                    Primitive::Circle { .. } => !lines,
+                    _ => false,
+                })
+                .map(|item| item.style.opacity)
+                .collect()
+        };
+
+        let dimmed = draw(FilterMode::Dim);
+        assert_eq!(opacities(&dimmed, false), [1.0, DIMMED_OPACITY]);
+        assert_eq!(opacities(&dimmed, true), [DIMMED_OPACITY]);
+
+        let hidden = draw(FilterMode::Hide);
+        assert_eq!(opacities(&hidden, false), [1.0]);
+        assert!(opacities(&hidden, true).is_empty());
+    }
+}
@@ -0,0 +1,777 @@
+// Filter expressions over the span store.
+//
+//     service = "checkout" AND duration > 200ms AND status = error
+//     attr.http.status_code >= 500 OR NOT (job ~ nightly)
+//
+// Fields are `service`, `operation`, `status`, `job`, `id`, `duration` and
+// `attr.<name>`.
+// Text compares case-insensitively, `~` tests for a substring and durations
+// take a unit (ns, us, ms, s, m). A span with no value for a field matches
+// no comparison on it. Parse errors carry the offending range in UTF-16
+// code units so JavaScript can underline it in the input string. Filters
+// arrive in shared links, so nesting is capped at `MAX_DEPTH`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::AttrValue;
+
+/// How deep parentheses and NOT may nest.
+pub const MAX_DEPTH: usize = 64;
+
+/// What happens to services with no matching span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    /// Keep them in every view, drawn faded.
+    #[default]
+    Dim,
+    /// Drop them from every view.
+    Hide,
+}
+
+impl FromStr for FilterMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "dim" => Ok(FilterMode::Dim),
+            "hide" => Ok(FilterMode::Hide),
+            other => Err(format!("Unknown filter mode '{}', expected 'dim' or 'hide'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FilterError {
+    pub message: String,
+    /// Start of the offending input, in UTF-16 code units.
+    pub start: usize,
+    /// End of the offending input (exclusive), in UTF-16 code units.
+    pub end: usize,
+}
+
+impl FilterError {
+    fn new(message: impl Into<String>, start: usize, end: usize) -> Self {
+        Self { message: message.into(), start, end }
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.message, self.start, self.end)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// The fields of one span a filter can see.
+#[derive(Debug, Clone, Copy)]
+pub struct SpanFields<'a> {
+    pub id: &'a str,
+    pub service: &'a str,
+    pub job: &'a str,
+    pub duration_ns: Option<i64>,
+    pub operation: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub attributes: &'a [(Arc<str>, AttrValue)],
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.or()?;
+        let next = parser.peek();
+        if next.kind != TokenKind::End {
+            return Err(FilterError::new(
+                format!("Expected AND or OR, found {}", next.kind.describe()),
+                next.start,
+                next.end,
+            ));
+        }
+        Ok(Self { source: source.to_string(), expr })
+    }
+
+    /// The expression as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, span: &SpanFields) -> bool {
+        self.expr.eval(span)
+    }
+}
+
+/// Which services a filter keeps, as evaluated against one store.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterMatch {
+    pub mode: FilterMode,
+    /// Matching span count for every service with at least one match.
+    pub services: BTreeMap<String, usize>,
+    pub spans: usize,
+}
+
+impl FilterMatch {
+    pub fn matches_service(&self, service: &str) -> bool {
+        self.services.contains_key(service)
+    }
+
+    /// Whether `service` still appears in views.
+    pub fn shows(&self, service: &str) -> bool {
+        self.mode == FilterMode::Dim || self.matches_service(service)
+    }
+
+    /// Whether `service` appears but faded.
+    pub fn dims(&self, service: &str) -> bool {
+        self.mode == FilterMode::Dim && !self.matches_service(service)
+    }
+}
+
+/// Which spans of one trace a filter keeps, for views drawn per span.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpanMatch {
+    pub mode: FilterMode,
+    /// IDs of the matching spans.
+    pub spans: HashSet<String>,
+}
+
+impl SpanMatch {
+    /// Whether the span still appears in views.
+    pub fn shows(&self, span_id: &str) -> bool {
+        self.mode == FilterMode::Dim || self.spans.contains(span_id)
+    }
+
+    /// Whether the span appears but faded.
+    pub fn dims(&self, span_id: &str) -> bool {
+        self.mode == FilterMode::Dim && !self.spans.contains(span_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    // Chains are kept flat so a long one does not nest
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparison),
+}
+
+impl Expr {
+    fn eval(&self, span: &SpanFields) -> bool {
+        match self {
+            Expr::And(terms) => terms.iter().all(|term| term.eval(span)),
+            Expr::Or(terms) => terms.iter().any(|term| term.eval(span)),
+            Expr::Not(inner) => !inner.eval(span),
+            Expr::Compare(comparison) => comparison.eval(span),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Service,
+    Operation,
+    Status,
+    Job,
+    Id,
+    Duration,
+    Attr(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Contains => "~",
+        }
+    }
+
+    fn is_ordering(self) -> bool {
+        matches!(self, Op::Gt | Op::Ge | Op::Lt | Op::Le)
+    }
+
+    fn compare<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Contains => false,
+        }
+    }
+}
+
+// Right-hand side of a comparison. Text is kept lowercased alongside the
+// number it parses as, if any.
+#[derive(Debug, Clone, PartialEq)]
+struct Literal {
+    text: String,
+    number: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparison {
+    field: Field,
+    op: Op,
+    value: Literal,
+}
+
+impl Comparison {
+    fn eval(&self, span: &SpanFields) -> bool {
+        match &self.field {
+            Field::Service => self.text(Some(span.service)),
+            Field::Operation => self.text(span.operation),
+            Field::Status => self.text(span.status),
+            Field::Job => self.text(Some(span.job)),
+            Field::Id => self.text(Some(span.id)),
+            Field::Duration => match (span.duration_ns, self.value.number) {
+                (Some(duration), Some(limit)) => self.op.compare(duration as f64, limit),
+                _ => false,
+            },
+            Field::Attr(name) => {
+                let Some((_, value)) = span.attributes.iter().find(|(key, _)| **key == **name) else {
+                    return false;
+                };
+                let number = match value {
+                    AttrValue::Int(v) => Some(*v as f64),
+                    AttrValue::Float(v) => Some(*v),
+                    AttrValue::Str(v) => v.trim().parse().ok(),
+                    AttrValue::Bool(_) => None,
+                };
+                match (number, self.value.number) {
+                    (Some(a), Some(b)) if self.op != Op::Contains => self.op.compare(a, b),
+                    _ if self.op.is_ordering() => false,
+                    _ => self.text(Some(&value.to_string())),
+                }
+            }
+        }
+    }
+
+    fn text(&self, value: Option<&str>) -> bool {
+        let Some(value) = value else { return false };
+        let value = value.to_lowercase();
+        match self.op {
+            Op::Contains => value.contains(&self.value.text),
+            op => op.compare(value.as_str(), self.value.text.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    // Number, plus its duration in nanoseconds when it carries a unit
+    Number { value: f64, nanos: Option<f64> },
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    End,
+}
+
+impl TokenKind {
+    fn describe(&self) -> String {
+        match self {
+            TokenKind::Word(word) => format!("'{}'", word),
+            TokenKind::Str(text) => format!("\"{}\"", text),
+            TokenKind::Number { .. } => "a number".to_string(),
+            TokenKind::Op(op) => format!("'{}'", op.symbol()),
+            TokenKind::And => "AND".to_string(),
+            TokenKind::Or => "OR".to_string(),
+            TokenKind::Not => "NOT".to_string(),
+            TokenKind::Open => "'('".to_string(),
+            TokenKind::Close => "')'".to_string(),
+            TokenKind::End => "end of input".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+    // Source text, for literals compared as text
+    text: String,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, FilterError> {
+    let chars: Vec<char> = source.chars().collect();
+    // UTF-16 offset of every char index, plus the end
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut offset = 0;
+    for c in &chars {
+        offsets.push(offset);
+        offset += c.len_utf16();
+    }
+    offsets.push(offset);
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let kind = match c {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::Open
+            }
+            ')' => {
+                i += 1;
+                TokenKind::Close
+            }
+            '=' => {
+                i += 1;
+                TokenKind::Op(Op::Eq)
+            }
+            '~' => {
+                i += 1;
+                TokenKind::Op(Op::Contains)
+            }
+            '!' | '<' | '>' => {
+                let equals = chars.get(i + 1) == Some(&'=');
+                i += 1 + equals as usize;
+                TokenKind::Op(match (c, equals) {
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(FilterError::new("Expected '!='", offsets[start], offsets[i])),
+                })
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(FilterError::new("Unterminated string", offsets[start], offsets[i]))
+                        }
+                        Some('\\') if i + 1 < chars.len() => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                TokenKind::Str(text)
+            }
+            _ if is_word_char(c) => {
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                // Numbers may carry a unit; anything else starting with a
+                // digit (dates, hex ids, 5xx) stays a word
+                let (digits, unit) = split_number(&word);
+                let scale = match unit {
+                    "" => Some(None),
+                    "ns" => Some(Some(1.0)),
+                    "us" => Some(Some(1e3)),
+                    "ms" => Some(Some(1e6)),
+                    "s" => Some(Some(1e9)),
+                    "m" => Some(Some(60e9)),
+                    _ => None,
+                };
+                match (digits.parse::<f64>(), scale) {
+                    (Ok(value), Some(scale)) => {
+                        TokenKind::Number { value, nanos: scale.map(|scale| value * scale) }
+                    }
+                    _ => match word.to_ascii_uppercase().as_str() {
+                        "AND" => TokenKind::And,
+                        "OR" => TokenKind::Or,
+                        "NOT" => TokenKind::Not,
+                        _ => TokenKind::Word(word),
+                    },
+                }
+            }
+            _ => {
+                return Err(FilterError::new(
+                    format!("Unexpected character '{}'", c),
+                    offsets[start],
+                    offsets[start + 1],
+                ))
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start: offsets[start],
+            end: offsets[i],
+            text: chars[start..i].iter().collect(),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        start: offset,
+        end: offset,
+        text: String::new(),
+    });
+    Ok(tokens)
+}
+
+// A word's leading number and the rest, e.g. ("200", "ms")
+fn split_number(word: &str) -> (&str, &str) {
+    let numeric = word
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(word.len());
+    word.split_at(numeric)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '/' | ':')
+}
+
+// Recursive descent: OR binds loosest, then AND, then NOT
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // Open parentheses and NOTs around the current position
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut terms = vec![self.and()?];
+        while self.peek().kind == TokenKind::Or {
+            self.next();
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut terms = vec![self.unary()?];
+        while self.peek().kind == TokenKind::And {
+            self.next();
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        if matches!(self.peek().kind, TokenKind::Not | TokenKind::Open) {
+            let token = self.peek();
+            if self.depth == MAX_DEPTH {
+                return Err(FilterError::new(
+                    format!("Filter nests more than {} levels deep", MAX_DEPTH),
+                    token.start,
+                    token.end,
+                ));
+            }
+            self.depth += 1;
+            let expr = self.nested();
+            self.depth -= 1;
+            return expr;
+        }
+        self.comparison().map(Expr::Compare)
+    }
+
+    // A NOT or a parenthesized expression
+    fn nested(&mut self) -> Result<Expr, FilterError> {
+        match self.peek().kind {
+            TokenKind::Not => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            _ => {
+                let open = self.next();
+                let expr = self.or()?;
+                let close = self.next();
+                if close.kind != TokenKind::Close {
+                    return Err(FilterError::new(
+                        format!(
+                            "Expected ')' to close the '(' at {}, found {}",
+                            open.start,
+                            close.kind.describe()
+                        ),
+                        close.start,
+                        close.end,
+                    ));
+                }
+                Ok(expr)
+            }
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, FilterError> {
+        let name = self.next();
+        let TokenKind::Word(word) = &name.kind else {
+            return Err(FilterError::new(
+                format!("Expected a field name, found {}", name.kind.describe()),
+                name.start,
+                name.end,
+            ));
+        };
+        let field = match word.to_ascii_lowercase().as_str() {
+            "service" => Field::Service,
+            "operation" => Field::Operation,
+            "status" => Field::Status,
+            "job" => Field::Job,
+            "id" => Field::Id,
+            "duration" => Field::Duration,
+            lower => match lower.split_once('.') {
+                Some(("attr", key)) if !key.is_empty() => Field::Attr(word[5..].to_string()),
+                _ => {
+                    return Err(FilterError::new(
+                        format!(
+                            "Unknown field '{}', expected service, operation, status, job, id, duration or attr.<name>",
+                            word
+                        ),
+                        name.start,
+                        name.end,
+                    ))
+                }
+            },
+        };
+
+        let op_token = self.next();
+        let TokenKind::Op(op) = op_token.kind else {
+            return Err(FilterError::new(
+                format!("Expected a comparison after '{}', found {}", word, op_token.kind.describe()),
+                op_token.start,
+                op_token.end,
+            ));
+        };
+
+        let value = self.next();
+        let error = |message: String| Err(FilterError::new(message, value.start, value.end));
+        let literal = match (&field, &value.kind) {
+            (Field::Duration, TokenKind::Number { nanos: Some(nanos), .. }) if op != Op::Contains => Literal {
+                text: value.text.clone(),
+                number: Some(*nanos),
+            },
+            (Field::Duration, TokenKind::Number { nanos: None, .. }) => {
+                return error(format!("Duration needs a unit, e.g. {}ms", value.text))
+            }
+            (Field::Duration, TokenKind::Number { .. }) => {
+                return error("'~' compares text; duration is a number".to_string())
+            }
+            (Field::Duration, TokenKind::Word(word)) if split_number(word).0.parse::<f64>().is_ok() => {
+                let (digits, unit) = split_number(word);
+                // The number is ASCII, so its length is its UTF-16 length
+                return Err(FilterError::new(
+                    format!("Unknown unit '{}', expected ns, us, ms, s or m", unit),
+                    value.start + digits.len(),
+                    value.end,
+                ));
+            }
+            (Field::Duration, _) => {
+                return error(format!("Expected a duration like 200ms, found {}", value.kind.describe()))
+            }
+            (Field::Attr(_), TokenKind::Number { value: number, nanos: None }) => Literal {
+                text: value.text.clone(),
+                number: Some(*number),
+            },
+            (Field::Service | Field::Operation | Field::Status | Field::Job | Field::Id, TokenKind::Word(text) | TokenKind::Str(text))
+                if op.is_ordering() =>
+            {
+                return error(format!("'{}' compares numbers; '{}' is text", op.symbol(), text))
+            }
+            (_, TokenKind::Number { .. }) if op.is_ordering() => {
+                return error(format!("'{}' compares numbers; '{}' is text", op.symbol(), word))
+            }
+            (_, TokenKind::Word(text) | TokenKind::Str(text)) => Literal {
+                text: text.to_lowercase(),
+                number: None,
+            },
+            // Other fields, and attributes given a unit, compare the text
+            (_, TokenKind::Number { .. }) => Literal {
+                text: value.text.to_lowercase(),
+                number: None,
+            },
+            _ => {
+                return error(format!(
+                    "Expected a value after '{}', found {}",
+                    op.symbol(),
+                    value.kind.describe()
+                ))
+            }
+        };
+
+        Ok(Comparison { field, op, value: literal })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span<'a>(
+        service: &'a str,
+        duration_ns: Option<i64>,
+        status: Option<&'a str>,
+        attributes: &'a [(Arc<str>, AttrValue)],
+    ) -> SpanFields<'a> {
+        SpanFields {
+            id: "span-1",
+            service,
+            job: "nightly",
+            duration_ns,
+            operation: Some("GET /cart"),
+            status,
+            attributes,
+        }
+    }
+
+    fn matches(expr: &str, span: &SpanFields) -> bool {
+        Filter::parse(expr).unwrap().matches(span)
+    }
+
+    #[test]
+    fn combines_service_duration_and_status() {
+        let expr = r#"service = "checkout" AND duration > 200ms AND status = error"#;
+        assert!(matches(expr, &span("Checkout", Some(250_000_000), Some("ERROR"), &[])));
+        assert!(!matches(expr, &span("checkout", Some(150_000_000), Some("error"), &[])));
+        assert!(!matches(expr, &span("checkout", None, Some("error"), &[])));
+        assert!(!matches(expr, &span("cart", Some(250_000_000), Some("error"), &[])));
+    }
+
+    #[test]
+    fn precedence_not_and_substring() {
+        let attrs = [];
+        let cart = span("cart-api", Some(1_000), None, &attrs);
+        assert!(matches("service = x OR service ~ cart AND duration < 1.5us", &cart));
+        assert!(!matches("(service = x OR service ~ cart) AND NOT duration < 1.5us", &cart));
+        assert!(matches("not status = error", &cart));
+        assert!(!matches("status != error", &cart));
+        assert!(matches("job = NIGHTLY and id = 'span-1'", &cart));
+        assert!(matches("operation ~ /cart", &cart));
+    }
+
+    #[test]
+    fn attributes_compare_numerically_or_as_text() {
+        let attrs = [
+            (Arc::from("http.status_code"), AttrValue::Int(503)),
+            (Arc::from("http.route"), AttrValue::Str("/api/Cart".to_string())),
+            (Arc::from("retry"), AttrValue::Str("2".to_string())),
+            (Arc::from("cached"), AttrValue::Bool(true)),
+        ];
+        let s = span("cart", None, None, &attrs);
+        assert!(matches("attr.http.status_code >= 500", &s));
+        assert!(!matches("attr.http.status_code < 500", &s));
+        assert!(matches("attr.retry = 2.0", &s));
+        assert!(matches(r#"attr.http.route ~ "/api/""#, &s));
+        assert!(matches("attr.cached = true", &s));
+        assert!(!matches("attr.http.route > 5", &s));
+        assert!(!matches("attr.missing != 1", &s));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let error = |expr: &str| {
+            let e = Filter::parse(expr).unwrap_err();
+            (e.start, e.end)
+        };
+        assert_eq!(error(""), (0, 0));
+        assert_eq!(error("servce = a"), (0, 6));
+        assert_eq!(error("duration > 200"), (11, 14));
+        assert_eq!(error("duration > 200xs"), (14, 16));
+        assert_eq!(error("service = 'abc"), (10, 14));
+        assert_eq!(error("service > a"), (10, 11));
+        assert_eq!(error("(service = a"), (12, 12));
+        assert_eq!(error("service = a status = b"), (12, 18));
+        assert_eq!(error("service = a AND"), (15, 15));
+        assert_eq!(error("service = a & b"), (12, 13));
+        // Offsets count UTF-16 units, like JavaScript string indices
+        assert_eq!(error("service = \"🚀\" AND x"), (19, 20));
+    }
+
+    #[test]
+    fn words_starting_with_digits_compare_as_text() {
+        let attrs = [(Arc::from("timeout"), AttrValue::Str("5s".to_string()))];
+        let s = SpanFields { id: "7abc", status: Some("5xx"), ..span("1password", None, None, &attrs) };
+        assert!(matches("id = 7abc", &s));
+        assert!(matches("status = 5xx AND service = 1password", &s));
+        assert!(matches("attr.timeout = 5s", &s));
+        assert!(matches("id ~ 7A", &s));
+        assert!(Filter::parse("duration > 5xx").is_err());
+    }
+
+    #[test]
+    fn nesting_is_capped() {
+        let nested = |depth: usize| format!("{}service = a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Filter::parse(&nested(MAX_DEPTH)).is_ok());
+        let error = Filter::parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!((error.start, error.end), (MAX_DEPTH, MAX_DEPTH + 1));
+        assert!(Filter::parse(&"(".repeat(10_000)).is_err());
+        assert!(Filter::parse(&"NOT ".repeat(10_000)).is_err());
+
+        // Long chains stay flat
+        let chain = vec!["service = a"; 10_000].join(" AND ");
+        assert!(!matches(&chain, &span("b", None, None, &[])));
+    }
+
+    #[test]
+    fn match_dims_or_hides_unmatched_services() {
+        let mut matched = FilterMatch {
+            services: BTreeMap::from([("cart".to_string(), 2)]),
+            spans: 2,
+            ..Default::default()
+        };
+        assert!(matched.shows("auth") && matched.dims("auth"));
+        assert!(matched.shows("cart") && !matched.dims("cart"));
+
+        matched.mode = FilterMode::Hide;
+        assert!(!matched.shows("auth") && !matched.dims("auth"));
+        assert_eq!("hide".parse(), Ok(FilterMode::Hide));
+        assert!("fade".parse::<FilterMode>().is_err());
+    }
+}
@@ -0,0 +1,63 @@
+// Backend-neutral description of one rendered frame, as tessellated from a
+// `Scene`. The wgpu renderer uploads it as a vertex buffer; the CPU
+// rasterizer in `raster` draws the exact same data so golden-image tests can
+// run without a GPU.
+
+pub type Rgba = [f32; 4];
+
+/// Floats per vertex in `Frame::vertex_data`: x, y, r, g, b, a.
+pub const VERTEX_FLOATS: usize = 6;
+
+/// A triangle-list vertex in clip space with a linear RGBA color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub color: Rgba,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub clear: Rgba,
+    pub vertices: Vec<Vertex>,
+}
+
+impl Frame {
+    pub fn new(clear: Rgba) -> Self {
+        Self { clear, vertices: Vec::new() }
+    }
+
+    /// Append flat `[x, y, x, y, ...]` triangle positions in one color.
+    pub fn push_triangles(&mut self, positions: &[f32], color: Rgba) {
+        self.vertices.extend(positions.chunks_exact(2).map(|p| Vertex {
+            position: [p[0], p[1]],
+            color,
+        }));
+    }
+
+    pub fn vertex_count(&self) -> u32 {
+        self.vertices.len() as u32
+    }
+
+    /// Interleaved vertex buffer contents, `VERTEX_FLOATS` per vertex.
+    pub fn vertex_data(&self) -> Vec<f32> {
+        self.vertices
+            .iter()
+            .flat_map(|v| v.position.into_iter().chain(v.color))
+            .collect()
+    }
+}
+
+pub fn to_unorm(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Encode a linear channel to 8-bit sRGB, as an sRGB render target does.
+pub fn encode_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let encoded = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    to_unorm(encoded)
+}
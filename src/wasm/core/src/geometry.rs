@@ -0,0 +1,344 @@
+// Target-independent geometry: the 5x7 bitmap font and service labels.
+//
+// Everything here produces flat triangle lists in clip space, two f32s per
+// vertex. `tessellate` places them for `Primitive::Text` scene nodes.
+
+pub const CHAR_WIDTH: f32 = 0.08;
+pub const CHAR_HEIGHT: f32 = 0.14;
+pub const CHAR_SPACING: f32 = 0.01;
+
+pub fn create_char_geometry(c: char, x: f32, y: f32, char_width: f32, char_height: f32) -> Vec<f32> {
+    let mut vertices = Vec::new();
+    let segment_width = char_width / 5.0;
+    let segment_height = char_height / 7.0;
+    
+    // Simple 5x7 bitmap font patterns
+    let pattern = match c.to_ascii_uppercase() {
+        'H' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (2,3),                                            // Middle horizontal
+            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
+        ],
+        'E' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
+            (1,3), (2,3), (3,3),                              // Middle horizontal  
+            (1,6), (2,6), (3,6), (4,6),                       // Top horizontal
+        ],
+        'L' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
+        ],
+        'O' => vec![
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
+            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
+            (1,6), (2,6), (3,6),                              // Top horizontal
+        ],
+        'A' => vec![
+            (1,0), (2,0), (3,0),                              // Bottom of A
+            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
+            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
+            (1,3), (2,3), (3,3),                              // Middle horizontal
+            (2,6),                                            // Top
+        ],
+        'W' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,0), (1,1),                                      // Left bottom
+            (2,0), (2,1), (2,2),                              // Middle
+            (3,0), (3,1),                                      // Right bottom
+            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
+        ],
+        'S' => vec![
+            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
+            (0,1), (0,2),                                      // Left bottom
+            (1,3), (2,3), (3,3),                              // Middle horizontal
+            (4,4), (4,5),                                      // Right top
+            (0,6), (1,6), (2,6), (3,6),                       // Top horizontal
+        ],
+        'M' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,5), (1,6),                                      // Left diagonal
+            (2,4), (2,5), (2,6),                              // Middle
+            (3,5), (3,6),                                      // Right diagonal
+            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
+        ],
+        'V' => vec![
+            (0,3), (0,4), (0,5), (0,6),                       // Left top
+            (1,1), (1,2),                                      // Left middle
+            (2,0),                                             // Bottom center
+            (3,1), (3,2),                                      // Right middle  
+            (4,3), (4,4), (4,5), (4,6),                       // Right top
+        ],
+        'T' => vec![
+            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
+            (2,0), (2,1), (2,2), (2,3), (2,4), (2,5),         // Center vertical
+        ],
+        'K' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,3),                                             // Middle connection
+            (2,2), (2,4),                                      // Diagonals
+            (3,1), (3,5),                                      // Diagonals  
+            (4,0), (4,6),                                      // Right ends
+        ],
+        'I' => vec![
+            (0,0), (1,0), (2,0), (3,0), (4,0),                // Bottom horizontal
+            (2,1), (2,2), (2,3), (2,4), (2,5),                // Center vertical
+            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
+        ],
+        'R' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,3), (1,6), (2,3), (2,6), (3,3), (3,6),         // Horizontals
+            (4,4), (4,5), (4,6),                               // Right top
+            (3,1), (4,0),                                      // Right diagonal
+        ],
+        'C' => vec![
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
+            (1,6), (2,6), (3,6),                              // Top horizontal
+            (4,1), (4,5),                                      // Right ends only
+        ],
+        'N' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,1), (2,2), (3,3),                              // Diagonal
+            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
+        ],
+        'U' => vec![
+            (0,1), (0,2), (0,3), (0,4), (0,5), (0,6),         // Left vertical
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (4,1), (4,2), (4,3), (4,4), (4,5), (4,6),         // Right vertical
+        ],
+        'D' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (1,6), (2,6), (3,6),                              // Top horizontal
+            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
+        ],
+        'P' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,3), (1,6), (2,3), (2,6), (3,3), (3,6),         // Horizontals
+            (4,4), (4,5), (4,6),                               // Right top only
+        ],
+        'F' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,3), (2,3), (3,3),                              // Middle horizontal
+            (1,6), (2,6), (3,6), (4,6),                       // Top horizontal
+        ],
+        'G' => vec![
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
+            (1,6), (2,6), (3,6),                              // Top horizontal
+            (4,1), (4,2), (2,3), (3,3), (4,3),                // Right with middle bar
+        ],
+        'B' => vec![
+            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (1,3), (2,3), (3,3),                              // Middle horizontal
+            (1,6), (2,6), (3,6),                              // Top horizontal
+            (4,1), (4,2), (4,4), (4,5),                       // Right segments
+        ],
+        'Y' => vec![
+            (0,5), (0,6),                                      // Left top
+            (1,4), (2,3),                                      // Left diagonal
+            (2,0), (2,1), (2,2),                              // Center bottom
+            (3,4), (4,5), (4,6),                              // Right diagonal and top
+        ],
+        'J' => vec![
+            (0,1),                                             // Left bottom hook
+            (1,0), (2,0), (3,0),                              // Bottom horizontal
+            (4,1), (4,2), (4,3), (4,4), (4,5), (4,6),         // Right vertical
+        ],
+        'Q' => vec![
+            (1,0), (2,0), (3,0),                              // Bottom horizontal  
+            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
+            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
+            (1,6), (2,6), (3,6),                              // Top horizontal
+            (3,1), (4,0),                                      // Tail
+        ],
+        'X' => vec![
+            (0,0), (0,6),                                      // Left corners
+            (1,1), (1,5),                                      // Inner diagonals
+            (2,2), (2,3), (2,4),                              // Center
+            (3,1), (3,5),                                      // Inner diagonals
+            (4,0), (4,6),                                      // Right corners
+        ],
+        'Z' => vec![
+            (0,0), (1,0), (2,0), (3,0), (4,0),                // Bottom horizontal
+            (4,1), (3,2), (2,3), (1,4), (0,5),                // Diagonal
+            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
+        ],
+        '+' => vec![
+            (2,1), (2,2), (2,4), (2,5),                       // Vertical line
+            (1,3), (3,3),                                      // Horizontal line
+        ],
+        '=' => vec![
+            (1,2), (2,2), (3,2),                              // Top line
+            (1,4), (2,4), (3,4),                              // Bottom line
+        ],
+        '!' => vec![
+            (2,2), (2,3), (2,4), (2,5), (2,6),                // Vertical line
+            (2,0),                                             // Bottom dot
+        ],
+        ',' => vec![
+            (2,0), (1,1),                                      // Comma shape
+        ],
+        '.' => vec![
+            (2,0),                                             // Dot
+        ],
+        ':' => vec![
+            (2,1), (2,5),                                      // Two dots
+        ],
+        ';' => vec![
+            (2,5), (2,1), (1,0),                              // Semicolon
+        ],
+        '?' => vec![
+            (1,6), (2,6), (3,6),                              // Top
+            (4,5), (4,4),                                      // Right top
+            (2,3), (3,3),                                      // Middle
+            (2,0),                                             // Bottom dot
+        ],
+        '0'..='9' => {
+            let digit = c.to_digit(10).unwrap_or(0);
+            match digit {
+                0 => vec![
+                    (1,0), (2,0), (3,0),                       // Bottom
+                    (0,1), (0,2), (0,3), (0,4), (0,5),         // Left
+                    (4,1), (4,2), (4,3), (4,4), (4,5),         // Right
+                    (1,6), (2,6), (3,6),                       // Top
+                ],
+                1 => vec![
+                    (2,0), (2,1), (2,2), (2,3), (2,4), (2,5), (2,6), // Vertical line
+                    (1,5),                                      // Top left
+                ],
+                2 => vec![
+                    (0,0), (1,0), (2,0), (3,0), (4,0),         // Bottom
+                    (0,1), (0,2),                               // Left bottom
+                    (1,3), (2,3), (3,3),                       // Middle
+                    (4,4), (4,5),                               // Right top
+                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
+                ],
+                3 => vec![
+                    (0,0), (1,0), (2,0), (3,0),                // Bottom
+                    (4,1), (4,2),                               // Right bottom
+                    (1,3), (2,3), (3,3),                       // Middle
+                    (4,4), (4,5),                               // Right top
+                    (0,6), (1,6), (2,6), (3,6),                // Top
+                ],
+                4 => vec![
+                    (3,0), (3,1), (3,2), (3,3), (3,4), (3,5), (3,6), // Right vertical
+                    (0,3), (0,4), (0,5),                       // Left vertical
+                    (1,3), (2,3),                               // Middle horizontal
+                ],
+                5 => vec![
+                    (1,0), (2,0), (3,0), (4,0),                // Bottom
+                    (4,1), (4,2),                               // Right bottom
+                    (1,3), (2,3), (3,3),                       // Middle
+                    (0,4), (0,5),                               // Left top
+                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
+                ],
+                6 => vec![
+                    (1,0), (2,0), (3,0),                       // Bottom
+                    (0,1), (0,2), (0,3), (0,4), (0,5),         // Left
+                    (1,3), (2,3), (3,3),                       // Middle
+                    (4,1), (4,2),                               // Right bottom
+                    (1,6), (2,6), (3,6),                       // Top
+                ],
+                7 => vec![
+                    (3,0), (3,1), (3,2), (3,3),                // Right diagonal
+                    (2,4), (2,5),                               // Middle
+                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
+                ],
+                8 => vec![
+                    (1,0), (2,0), (3,0),                       // Bottom
+                    (0,1), (0,2), (0,4), (0,5),                // Left
+                    (4,1), (4,2), (4,4), (4,5),                // Right
+                    (1,3), (2,3), (3,3),                       // Middle
+                    (1,6), (2,6), (3,6),                       // Top
+                ],
+                _ => vec![(2,3)], // Default dot
+            }
+        },
+        ' ' => vec![], // Space
+        _ => vec![(1,1), (2,1), (3,1), (1,2), (2,2), (3,2), (1,3), (2,3), (3,3)], // Default block
+    };
+    
+    // Convert pattern to triangles
+    for (px, py) in pattern {
+        let seg_x = x + px as f32 * segment_width;
+        let seg_y = y + py as f32 * segment_height;
+        
+        // Two triangles per segment
+        vertices.extend_from_slice(&[
+            seg_x, seg_y,                                    // Bottom left
+            seg_x + segment_width, seg_y,                    // Bottom right
+            seg_x, seg_y + segment_height,                   // Top left
+        ]);
+        vertices.extend_from_slice(&[
+            seg_x, seg_y + segment_height,                   // Top left
+            seg_x + segment_width, seg_y,                    // Bottom right
+            seg_x + segment_width, seg_y + segment_height,   // Top right
+        ]);
+    }
+    
+    vertices
+}
+
+pub fn create_text_geometry(text: &str, x: f32, y: f32) -> Vec<f32> {
+    let mut vertices = Vec::new();
+    
+    for (i, c) in text.chars().enumerate() {
+        let char_x = x + i as f32 * (CHAR_WIDTH + CHAR_SPACING);
+        let char_vertices = create_char_geometry(c, char_x, y, CHAR_WIDTH, CHAR_HEIGHT);
+        vertices.extend(char_vertices);
+    }
+    
+    vertices
+}
+
+// Width of `text` at the default cell size, without trailing spacing
+pub fn text_width(text: &str) -> f32 {
+    let chars = text.chars().count() as f32;
+    (chars * (CHAR_WIDTH + CHAR_SPACING) - CHAR_SPACING).max(0.0)
+}
+
+pub fn extract_service_number(service_id: &str) -> String {
+    // Extract just the number/identifier part for cleaner display
+    if service_id.starts_with("job-") {
+        // For "job-abc123" -> "abc123" 
+        service_id.strip_prefix("job-").unwrap_or(service_id).to_string()
+    } else if service_id.len() > 8 {
+        // For very long IDs, take last 8 characters
+        service_id.chars().rev().take(8).collect::<String>().chars().rev().collect()
+    } else {
+        // Use the ID as-is if it's short
+        service_id.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_lit_segment_is_two_triangles() {
+        // 'I' lights 15 segments; a space lights none
+        assert_eq!(create_char_geometry('I', 0.0, 0.0, CHAR_WIDTH, CHAR_HEIGHT).len(), 15 * 12);
+        assert!(create_char_geometry(' ', 0.0, 0.0, CHAR_WIDTH, CHAR_HEIGHT).is_empty());
+    }
+
+    #[test]
+    fn text_advances_one_cell_per_character() {
+        let one = create_text_geometry(".", 0.0, 0.0);
+        let two = create_text_geometry("..", 0.0, 0.0);
+        assert_eq!(two.len(), one.len() * 2);
+        assert!((two[12] - one[0] - 0.09).abs() < 1e-6);
+        assert!((text_width("..") - 0.17).abs() < 1e-6);
+    }
+
+    #[test]
+    fn service_numbers_drop_job_prefix_or_keep_last_eight() {
+        assert_eq!(extract_service_number("job-abc123"), "abc123");
+        assert_eq!(extract_service_number("checkout-service-7f9a"), "ice-7f9a");
+        assert_eq!(extract_service_number("cart"), "cart");
+    }
+}
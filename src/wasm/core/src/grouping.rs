@@ -0,0 +1,131 @@
+// Service groups for the service map: services sharing a name prefix, or a
+// span attribute such as a Kubernetes namespace.
+//
+// Grouping is a view setting, not part of the data, so the same choice
+// applies to whichever job is active. A group needs at least two members;
+// services that would be alone, or that lack the attribute, stay ungrouped.
+// Collapsed groups are remembered by name and stay collapsed as services
+// join or leave them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{Counts, Metrics, Totals};
+use crate::ServiceNode;
+
+/// How services are grouped: `"prefix"` or `{ "attribute": "k8s.namespace" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    /// The part of the service ID before the first `-` or `.`.
+    Prefix,
+    /// A span attribute; a service takes the value from its newest span that has it.
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Grouping {
+    pub by: Option<GroupBy>,
+    pub collapsed: BTreeSet<String>,
+}
+
+/// One group with its members' metrics rolled up.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<String>,
+    pub collapsed: bool,
+    /// The worst member status.
+    pub status: String,
+    pub metrics: Metrics,
+}
+
+/// The part of `service` before the first `-` or `.`, or the whole ID when it
+/// has neither. `None` when the ID starts with a separator.
+pub fn prefix(service: &str) -> Option<&str> {
+    service.split(['-', '.']).next().filter(|prefix| !prefix.is_empty())
+}
+
+/// Group `services` by `key`, in name order.
+pub fn groups(
+    services: &[ServiceNode],
+    key: impl Fn(&str) -> Option<String>,
+    totals: &Totals,
+    collapsed: &BTreeSet<String>,
+) -> Vec<Group> {
+    let mut members: BTreeMap<String, Vec<&ServiceNode>> = BTreeMap::new();
+    for node in services {
+        if let Some(name) = key(&node.id) {
+            members.entry(name).or_default().push(node);
+        }
+    }
+
+    members
+        .into_iter()
+        .filter(|(_, nodes)| nodes.len() > 1)
+        .map(|(name, nodes)| {
+            let mut counts = Counts::default();
+            for node in &nodes {
+                if let Some(service) = totals.services.get(&node.id) {
+                    counts.merge(service);
+                }
+            }
+            let status = ["error", "warning"]
+                .into_iter()
+                .find(|status| nodes.iter().any(|node| node.status == *status))
+                .unwrap_or("healthy");
+            Group {
+                collapsed: collapsed.contains(&name),
+                members: nodes.iter().map(|node| node.id.clone()).collect(),
+                status: status.to_string(),
+                metrics: Metrics::new(&counts, totals.duration_ns),
+                name,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, status: &str) -> ServiceNode {
+        ServiceNode { id: id.to_string(), x: 0.0, y: 0.0, status: status.to_string() }
+    }
+
+    #[test]
+    fn prefixes_split_on_dash_or_dot() {
+        assert_eq!(prefix("checkout-api"), Some("checkout"));
+        assert_eq!(prefix("billing.worker-2"), Some("billing"));
+        assert_eq!(prefix("cart"), Some("cart"));
+        assert_eq!(prefix("-odd"), None);
+    }
+
+    #[test]
+    fn groups_need_two_members_and_roll_up_metrics() {
+        let services = [
+            node("checkout-api", "healthy"),
+            node("checkout-db", "error"),
+            node("cart", "warning"),
+            node("auth-api", "healthy"),
+        ];
+        let mut totals = Totals { duration_ns: Some(2_000_000_000), ..Default::default() };
+        for (service, errors) in [("checkout-api", 0), ("checkout-db", 2)] {
+            let counts = Counts { spans: 4, errors, duration_sum_ns: 400.0, timed_spans: 4 };
+            totals.services.insert(service.to_string(), counts);
+        }
+        let collapsed = BTreeSet::from(["checkout".to_string()]);
+
+        let groups = groups(&services, |id| prefix(id).map(str::to_string), &totals, &collapsed);
+        assert_eq!(groups.len(), 1);
+        let checkout = &groups[0];
+        assert_eq!((checkout.name.as_str(), checkout.collapsed), ("checkout", true));
+        assert_eq!(checkout.status, "error");
+        assert_eq!(checkout.members, ["checkout-api", "checkout-db"]);
+        assert_eq!((checkout.metrics.spans, checkout.metrics.errors), (8, 2));
+        assert_eq!(checkout.metrics.rate_per_s, Some(4.0));
+        assert_eq!(checkout.metrics.mean_latency_ns, Some(100.0));
+    }
+}
@@ -0,0 +1,780 @@
+// Ingestion pipeline shared by every entry point.
+//
+// Bytes go through a `ChunkDecoder` into span records, which are merged into
+// the replay state according to `IngestOptions`. `append_chunk`,
+// `parse_arrow_replay` and `IngestBuffer::commit` differ only in the options
+// they pass, so they update state identically.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, BooleanArray, Float32Array, Float64Array, Int32Array, Int64Array, StringArray,
+};
+use arrow::array::{
+    DurationMicrosecondArray, DurationMillisecondArray, DurationNanosecondArray,
+    DurationSecondArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+    TimestampNanosecondArray, TimestampSecondArray,
+};
+use arrow::datatypes::{DataType, TimeUnit};
+use arrow::ipc::reader::FileReader;
+use arrow::record_batch::RecordBatch;
+use serde::Deserialize;
+
+use crate::jobs::{ReplayState, DEFAULT_JOB};
+use crate::provenance::BatchProvenance;
+use crate::store::{AttrValue, IngestStats, SpanRecord};
+use crate::ReplayData;
+
+/// Errors surfaced by the ingestion API, including the bounds checks of
+/// the JavaScript ingest buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestError {
+    /// A buffer was requested with zero capacity.
+    ZeroCapacity,
+    /// A chunk or commit carried no bytes.
+    EmptyChunk,
+    /// A commit claimed more bytes than the buffer holds.
+    OutOfBounds { len: usize, capacity: usize },
+    /// A write would run past the end of the buffer.
+    WriteOutOfBounds { end: usize, capacity: usize },
+    /// The decoder rejected the chunk.
+    Decode(String),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::ZeroCapacity => write!(f, "Ingest buffer capacity must be greater than zero"),
+            IngestError::EmptyChunk => write!(f, "Cannot ingest an empty chunk"),
+            IngestError::OutOfBounds { len, capacity } => write!(
+                f,
+                "Commit length {} exceeds ingest buffer capacity {}",
+                len, capacity
+            ),
+            IngestError::WriteOutOfBounds { end, capacity } => write!(
+                f,
+                "Write up to byte {} exceeds ingest buffer capacity {}",
+                end, capacity
+            ),
+            IngestError::Decode(message) => write!(f, "Failed to decode chunk: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+/// Whether a chunk is merged into or replaces the current state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeMode {
+    #[default]
+    Append,
+    Replace,
+}
+
+/// Per-call ingestion settings. Deserialized from JS as
+/// `{ snapshot, mode: "append" | "replace", job, source, last_modified }`;
+/// every field is optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct IngestOptions {
+    /// Return a `ReplayData` snapshot of the state after merging.
+    pub snapshot: bool,
+    pub mode: MergeMode,
+    /// Job to attribute every row to, overriding any job the data names.
+    pub job: Option<String>,
+    /// Key of the file the chunk came from, e.g. its S3 object key.
+    pub source: Option<String>,
+    /// Last-modified value of `source`; an unchanged source is skipped.
+    pub last_modified: Option<String>,
+}
+
+/// Records decoded from one chunk, independent of the wire format.
+#[derive(Debug, Default)]
+pub struct DecodedChunk {
+    pub records: Vec<SpanRecord>,
+    /// Job the whole chunk belongs to, if the format carries one.
+    pub job_hint: Option<String>,
+    /// Row count of each record batch, in order.
+    pub batch_rows: Vec<usize>,
+}
+
+/// Turns raw bytes into span records. New wire formats plug in here.
+pub trait ChunkDecoder {
+    fn decode(&self, data: &[u8]) -> Result<DecodedChunk, IngestError>;
+}
+
+/// Schema metadata key naming the job an Arrow file belongs to.
+pub const JOB_METADATA_KEY: &str = "job_id";
+
+/// Decoder for Arrow IPC files produced by the replay compiler.
+pub struct ArrowIpcDecoder;
+
+impl ChunkDecoder for ArrowIpcDecoder {
+    fn decode(&self, data: &[u8]) -> Result<DecodedChunk, IngestError> {
+        let reader = FileReader::try_new(Cursor::new(data), None)
+            .map_err(|e| IngestError::Decode(format!("Failed to create Arrow reader: {:?}", e)))?;
+
+        let mut chunk = DecodedChunk {
+            job_hint: reader.schema().metadata().get(JOB_METADATA_KEY).cloned(),
+            ..Default::default()
+        };
+        for maybe_batch in reader {
+            let batch = maybe_batch
+                .map_err(|e| IngestError::Decode(format!("Failed to read batch: {:?}", e)))?;
+            chunk.records.extend(batch_records(&batch, chunk.batch_rows.len()));
+            chunk.batch_rows.push(batch.num_rows());
+        }
+
+        Ok(chunk)
+    }
+}
+
+/// What one pass through the pipeline did.
+#[derive(Debug)]
+pub struct IngestOutcome {
+    pub stats: IngestStats,
+    pub batches: usize,
+    /// The source was already ingested at this version and nothing changed.
+    pub skipped: bool,
+    pub snapshot: Option<ReplayData>,
+}
+
+/// Decode `data` and merge it into `state` according to `options`.
+pub fn ingest(
+    state: &mut ReplayState,
+    decoder: &dyn ChunkDecoder,
+    data: &[u8],
+    options: &IngestOptions,
+) -> Result<IngestOutcome, IngestError> {
+    if data.is_empty() {
+        return Err(IngestError::EmptyChunk);
+    }
+
+    if let Some(key) = &options.source {
+        if state.sources().is_current(key, options.last_modified.as_deref()) {
+            return Ok(IngestOutcome {
+                stats: IngestStats::default(),
+                batches: 0,
+                skipped: true,
+                snapshot: options.snapshot.then(|| state.snapshot()),
+            });
+        }
+    }
+
+    // Decode fully before touching state so a bad chunk leaves it intact
+    let chunk = decoder.decode(data)?;
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let replace = options.mode == MergeMode::Replace;
+
+    let mut groups = partition_by_job(
+        chunk.records,
+        options.job.as_deref(),
+        chunk.job_hint.as_deref(),
+    );
+    // Replacing a named job with an empty chunk still clears it
+    if groups.is_empty() && replace {
+        if let Some(job) = &options.job {
+            groups.push((job.clone(), Vec::new()));
+        }
+    }
+
+    // Clear replaced jobs before registering the source so that clearing
+    // forgets the jobs' old sources but not this one
+    if replace {
+        for (job, _) in &groups {
+            state.clear_job(job);
+        }
+    }
+
+    let mut stats = IngestStats::default();
+    let source = match &options.source {
+        Some(key) => {
+            let batches = batch_provenance(&chunk.batch_rows, &groups);
+            let (id, removed) =
+                state.register_source(key, options.last_modified.clone(), &timestamp, batches);
+            stats += removed;
+            Some(id)
+        }
+        None => None,
+    };
+
+    for (job, records) in groups {
+        stats += state.ingest_job(&job, records, &timestamp, source);
+    }
+
+    Ok(IngestOutcome {
+        stats,
+        batches: chunk.batch_rows.len(),
+        skipped: false,
+        snapshot: options.snapshot.then(|| state.snapshot()),
+    })
+}
+
+// Group records by job in first-seen order. An explicit job wins over the
+// row's job column, which wins over the chunk-level job.
+fn partition_by_job(
+    records: Vec<SpanRecord>,
+    explicit: Option<&str>,
+    chunk_job: Option<&str>,
+) -> Vec<(String, Vec<SpanRecord>)> {
+    let mut groups: Vec<(String, Vec<SpanRecord>)> = Vec::new();
+    // Position of each job's group in `groups`
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        let job = explicit
+            .or(record.job.as_deref())
+            .or(chunk_job)
+            .unwrap_or(DEFAULT_JOB);
+        match positions.get(job) {
+            Some(&position) => groups[position].1.push(record),
+            None => {
+                positions.insert(job.to_string(), groups.len());
+                groups.push((job.to_string(), vec![record]));
+            }
+        }
+    }
+    groups
+}
+
+// Which jobs each record batch of a source fed
+fn batch_provenance(
+    batch_rows: &[usize],
+    groups: &[(String, Vec<SpanRecord>)],
+) -> Vec<BatchProvenance> {
+    let mut batches: Vec<BatchProvenance> = batch_rows
+        .iter()
+        .enumerate()
+        .map(|(index, &rows)| BatchProvenance {
+            index,
+            rows,
+            jobs: Vec::new(),
+        })
+        .collect();
+
+    for (job, records) in groups {
+        for record in records {
+            if let Some(batch) = batches.get_mut(record.batch) {
+                if !batch.jobs.contains(job) {
+                    batch.jobs.push(job.clone());
+                }
+            }
+        }
+    }
+    batches
+}
+
+// Columns with a fixed meaning; every other column becomes an attribute
+const KNOWN_COLUMNS: &[&str] = &[
+    "id", "span_id", "service_name", "service", "timestamp", "start_time", "end_time",
+    "duration_ns", "duration", "duration_ms", "name", "operation", "operation_name", "status",
+    "status_code", "job_id", "trace_id", "parent_span_id", "parent_id",
+];
+
+/// Decode the rows of one record batch.
+///
+/// The span key comes from `span_id`, falling back to `id`; the service from
+/// `service_name`/`service`, falling back to `id`; the timestamp from
+/// `timestamp`/`start_time` and the job from `job_id` when present. The
+/// duration comes from `duration_ns`/`duration`, `duration_ms` or
+/// `end_time` minus the start, the operation from `name`/`operation`/
+/// `operation_name` and the status from `status`/`status_code`. The trace
+/// comes from `trace_id` and the parent span from `parent_span_id`/`parent_id`.
+/// Any other string, integer, float or boolean column becomes an attribute
+/// named after the column, minus an `attr.` or `attributes.` prefix.
+pub fn batch_records(batch: &RecordBatch, batch_index: usize) -> Vec<SpanRecord> {
+    let ids = string_column(batch, &["id"]);
+    let keys = string_column(batch, &["span_id"]).or(ids);
+    let services = string_column(batch, &["service_name", "service"]).or(ids);
+
+    let (Some(keys), Some(services)) = (keys, services) else {
+        return Vec::new();
+    };
+
+    let timestamps = nanos_column(batch, &["timestamp", "start_time"]);
+    let ends = nanos_column(batch, &["end_time"]);
+    let durations = nanos_column(batch, &["duration_ns", "duration"])
+        .or_else(|| millis_column(batch, &["duration_ms"]));
+    let operations = string_column(batch, &["name", "operation", "operation_name"]);
+    let statuses = string_column(batch, &["status", "status_code"]);
+    let jobs = string_column(batch, &["job_id"]);
+    let traces = string_column(batch, &["trace_id"]);
+    let parents = string_column(batch, &["parent_span_id", "parent_id"]);
+    let attributes = attribute_columns(batch);
+
+    (0..batch.num_rows())
+        .filter(|&row| keys.is_valid(row) && services.is_valid(row))
+        .map(|row| {
+            let timestamp_ns = timestamps.as_ref().and_then(|ts| ts(row));
+            let duration_ns = match (&durations, &ends, timestamp_ns) {
+                (Some(duration), _, _) => duration(row),
+                (None, Some(end), Some(start)) => end(row).map(|end| end.saturating_sub(start)),
+                _ => None,
+            };
+            SpanRecord {
+                key: keys.value(row).to_string(),
+                service: services.value(row).to_string(),
+                timestamp_ns,
+                duration_ns,
+                operation: operations
+                    .filter(|o| o.is_valid(row))
+                    .map(|o| o.value(row).to_string()),
+                status: statuses
+                    .filter(|s| s.is_valid(row))
+                    .map(|s| s.value(row).to_string()),
+                trace_id: traces
+                    .filter(|t| t.is_valid(row))
+                    .map(|t| t.value(row).to_string()),
+                // Root spans often carry an empty parent rather than a null
+                parent_id: parents
+                    .filter(|p| p.is_valid(row) && !p.value(row).is_empty())
+                    .map(|p| p.value(row).to_string()),
+                attributes: attributes
+                    .iter()
+                    .filter_map(|(name, read)| Some((name.clone(), read(row)?)))
+                    .collect(),
+                job: jobs
+                    .filter(|j| j.is_valid(row))
+                    .map(|j| j.value(row).to_string()),
+                batch: batch_index,
+            }
+        })
+        .collect()
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, names: &[&str]) -> Option<&'a StringArray> {
+    names.iter().find_map(|name| {
+        batch
+            .column_by_name(name)
+            .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+    })
+}
+
+type NanosReader<'a> = Box<dyn Fn(usize) -> Option<i64> + 'a>;
+
+fn read<T: Array>(array: &T, row: usize, value: i64) -> Option<i64> {
+    array.is_valid(row).then_some(value)
+}
+
+// Normalize any integer, timestamp or duration column to nanoseconds.
+// Plain Int64 columns are assumed to already be in nanoseconds.
+fn nanos_column<'a>(batch: &'a RecordBatch, names: &[&str]) -> Option<NanosReader<'a>> {
+    let column = names.iter().find_map(|name| batch.column_by_name(name))?;
+    let any = column.as_any();
+
+    let reader: NanosReader<'a> = match column.data_type() {
+        DataType::Int64 => {
+            let a = any.downcast_ref::<Int64Array>()?;
+            Box::new(move |row| read(a, row, a.value(row)))
+        }
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            let a = any.downcast_ref::<TimestampSecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000_000_000)))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            let a = any.downcast_ref::<TimestampMillisecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000_000)))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            let a = any.downcast_ref::<TimestampMicrosecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000)))
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            let a = any.downcast_ref::<TimestampNanosecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row)))
+        }
+        DataType::Duration(TimeUnit::Second) => {
+            let a = any.downcast_ref::<DurationSecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000_000_000)))
+        }
+        DataType::Duration(TimeUnit::Millisecond) => {
+            let a = any.downcast_ref::<DurationMillisecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000_000)))
+        }
+        DataType::Duration(TimeUnit::Microsecond) => {
+            let a = any.downcast_ref::<DurationMicrosecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000)))
+        }
+        DataType::Duration(TimeUnit::Nanosecond) => {
+            let a = any.downcast_ref::<DurationNanosecondArray>()?;
+            Box::new(move |row| read(a, row, a.value(row)))
+        }
+        _ => return None,
+    };
+
+    Some(reader)
+}
+
+// A column of plain milliseconds, integer or fractional, as nanoseconds
+fn millis_column<'a>(batch: &'a RecordBatch, names: &[&str]) -> Option<NanosReader<'a>> {
+    let column = names.iter().find_map(|name| batch.column_by_name(name))?;
+    let any = column.as_any();
+
+    let reader: NanosReader<'a> = match column.data_type() {
+        DataType::Int64 => {
+            let a = any.downcast_ref::<Int64Array>()?;
+            Box::new(move |row| read(a, row, a.value(row).saturating_mul(1_000_000)))
+        }
+        DataType::Float64 => {
+            let a = any.downcast_ref::<Float64Array>()?;
+            Box::new(move |row| read(a, row, (a.value(row) * 1e6) as i64))
+        }
+        _ => return None,
+    };
+
+    Some(reader)
+}
+
+type AttrReader<'a> = Box<dyn Fn(usize) -> Option<AttrValue> + 'a>;
+
+// Readers for every column without a fixed meaning, keyed by attribute name
+fn attribute_columns(batch: &RecordBatch) -> Vec<(Arc<str>, AttrReader<'_>)> {
+    let schema = batch.schema();
+    let mut columns = Vec::new();
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if KNOWN_COLUMNS.contains(&field.name().as_str()) {
+            continue;
+        }
+        let any = column.as_any();
+        let reader: AttrReader<'_> = match column.data_type() {
+            DataType::Utf8 => {
+                let Some(a) = any.downcast_ref::<StringArray>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Str(a.value(row).to_string())))
+            }
+            DataType::Int64 => {
+                let Some(a) = any.downcast_ref::<Int64Array>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Int(a.value(row))))
+            }
+            DataType::Int32 => {
+                let Some(a) = any.downcast_ref::<Int32Array>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Int(a.value(row).into())))
+            }
+            DataType::Float64 => {
+                let Some(a) = any.downcast_ref::<Float64Array>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Float(a.value(row))))
+            }
+            DataType::Float32 => {
+                let Some(a) = any.downcast_ref::<Float32Array>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Float(a.value(row).into())))
+            }
+            DataType::Boolean => {
+                let Some(a) = any.downcast_ref::<BooleanArray>() else { continue };
+                Box::new(move |row| a.is_valid(row).then(|| AttrValue::Bool(a.value(row))))
+            }
+            _ => continue,
+        };
+        let name = field.name();
+        let name = name
+            .strip_prefix("attr.")
+            .or_else(|| name.strip_prefix("attributes."))
+            .unwrap_or(name);
+        columns.push((Arc::from(name), reader));
+    }
+    columns
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray, TimestampMillisecondArray};
+    use arrow::datatypes::{Field, Schema};
+    use arrow::ipc::writer::FileWriter;
+
+    use super::*;
+
+    /// Build an Arrow IPC file with one string column per `(name, values)`
+    /// pair, split into one record batch per entry of `batches`.
+    pub(crate) fn arrow_file(
+        metadata: &[(&str, &str)],
+        batches: &[Vec<(&str, Vec<&str>)>],
+    ) -> Vec<u8> {
+        let fields: Vec<Field> = batches[0]
+            .iter()
+            .map(|(name, _)| Field::new(*name, DataType::Utf8, true))
+            .collect();
+        let metadata: HashMap<String, String> = metadata
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let schema = Arc::new(Schema::new(fields).with_metadata(metadata));
+
+        let mut bytes = Vec::new();
+        {
+            let mut writer = FileWriter::try_new(&mut bytes, &schema).unwrap();
+            for columns in batches {
+                let arrays: Vec<ArrayRef> = columns
+                    .iter()
+                    .map(|(_, values)| Arc::new(StringArray::from(values.clone())) as ArrayRef)
+                    .collect();
+                writer
+                    .write(&RecordBatch::try_new(schema.clone(), arrays).unwrap())
+                    .unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        bytes
+    }
+
+    fn ids(values: Vec<&str>) -> Vec<(&str, Vec<&str>)> {
+        vec![("id", values)]
+    }
+
+    fn active_ids(state: &ReplayState) -> Vec<String> {
+        state.active_services().iter().map(|s| s.id.clone()).collect()
+    }
+
+    fn sourced(key: &str, last_modified: &str) -> IngestOptions {
+        IngestOptions {
+            source: Some(key.to_string()),
+            last_modified: Some(last_modified.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn legacy_id_schema_lands_in_default_job() {
+        let mut state = ReplayState::default();
+        let data = arrow_file(&[], &[ids(vec!["job-a", "job-b", "job-a"])]);
+
+        let outcome = ingest(
+            &mut state,
+            &ArrowIpcDecoder,
+            &data,
+            &IngestOptions {
+                snapshot: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let snapshot = outcome.snapshot.unwrap();
+        assert_eq!(snapshot.job_id, DEFAULT_JOB);
+        assert_eq!(snapshot.all_service_ids, ["job-a", "job-b"]);
+        assert_eq!(outcome.stats.duplicate_spans, 1);
+    }
+
+    #[test]
+    fn rows_are_partitioned_by_job() {
+        let mut state = ReplayState::default();
+        let data = arrow_file(
+            &[(JOB_METADATA_KEY, "nightly")],
+            &[vec![
+                ("id", vec!["a", "b", "c"]),
+                ("job_id", vec!["run-1", "run-2", "run-1"]),
+            ]],
+        );
+        ingest(&mut state, &ArrowIpcDecoder, &data, &IngestOptions::default()).unwrap();
+        let data = arrow_file(&[(JOB_METADATA_KEY, "nightly")], &[ids(vec!["d"])]);
+        ingest(&mut state, &ArrowIpcDecoder, &data, &IngestOptions::default()).unwrap();
+
+        let jobs: Vec<_> = state.jobs().into_iter().map(|j| (j.job_id, j.span_count)).collect();
+        assert_eq!(
+            jobs,
+            [("nightly".to_string(), 1), ("run-1".to_string(), 2), ("run-2".to_string(), 1)]
+        );
+        assert_eq!(state.active_job(), Some("run-1"));
+        assert_eq!(active_ids(&state), ["a", "c"]);
+    }
+
+    #[test]
+    fn explicit_job_overrides_the_data() {
+        let mut state = ReplayState::default();
+        let data = arrow_file(&[(JOB_METADATA_KEY, "nightly")], &[ids(vec!["a"])]);
+        let options = IngestOptions {
+            job: Some("manual".to_string()),
+            ..Default::default()
+        };
+        ingest(&mut state, &ArrowIpcDecoder, &data, &options).unwrap();
+
+        assert_eq!(state.active_job(), Some("manual"));
+    }
+
+    #[test]
+    fn replace_mode_clears_the_job_first() {
+        let mut state = ReplayState::default();
+        let first = arrow_file(&[], &[ids(vec!["a", "b"])]);
+        let second = arrow_file(&[], &[ids(vec!["c"])]);
+        ingest(&mut state, &ArrowIpcDecoder, &first, &IngestOptions::default()).unwrap();
+        let options = IngestOptions {
+            mode: MergeMode::Replace,
+            ..Default::default()
+        };
+        ingest(&mut state, &ArrowIpcDecoder, &second, &options).unwrap();
+
+        assert_eq!(active_ids(&state), ["c"]);
+    }
+
+    #[test]
+    fn unchanged_source_is_skipped_and_changed_source_replaced() {
+        let mut state = ReplayState::default();
+        let v1 = arrow_file(&[], &[ids(vec!["a", "b"]), ids(vec!["c"])]);
+        let v2 = arrow_file(&[], &[ids(vec!["a", "d"])]);
+
+        ingest(&mut state, &ArrowIpcDecoder, &v1, &sourced("replays/1.arrow", "t1")).unwrap();
+        let again = ingest(&mut state, &ArrowIpcDecoder, &v1, &sourced("replays/1.arrow", "t1")).unwrap();
+        assert!(again.skipped);
+
+        let summaries = state.source_summaries();
+        assert_eq!(summaries[0].batches.len(), 2);
+        assert_eq!(summaries[0].batches[0].rows, 2);
+        assert_eq!(summaries[0].span_count, 3);
+
+        let replaced = ingest(&mut state, &ArrowIpcDecoder, &v2, &sourced("replays/1.arrow", "t2")).unwrap();
+        assert!(!replaced.skipped);
+        assert_eq!(replaced.stats.removed_spans, 3);
+        assert_eq!(active_ids(&state), ["a", "d"]);
+
+        state.remove_source("replays/1.arrow").unwrap();
+        assert!(active_ids(&state).is_empty());
+        assert!(state.remove_source("replays/1.arrow").is_none());
+    }
+
+    #[test]
+    fn replacing_with_a_source_keeps_it_registered() {
+        let mut state = ReplayState::default();
+        let data = arrow_file(&[], &[ids(vec!["a", "b"])]);
+        let options = IngestOptions {
+            mode: MergeMode::Replace,
+            ..sourced("replays/1.arrow", "t1")
+        };
+        ingest(&mut state, &ArrowIpcDecoder, &data, &options).unwrap();
+
+        let summaries = state.source_summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!((summaries[0].key.as_str(), summaries[0].span_count), ("replays/1.arrow", 2));
+        assert!(ingest(&mut state, &ArrowIpcDecoder, &data, &options).unwrap().skipped);
+
+        let removed = state.remove_source("replays/1.arrow").unwrap();
+        assert_eq!(removed.removed_spans, 2);
+        assert!(active_ids(&state).is_empty());
+    }
+
+    #[test]
+    fn replacing_a_job_forgets_its_other_sources() {
+        let mut state = ReplayState::default();
+        let old = arrow_file(&[], &[ids(vec!["a", "b"])]);
+        ingest(&mut state, &ArrowIpcDecoder, &old, &sourced("replays/1.arrow", "t1")).unwrap();
+
+        let new = arrow_file(&[], &[ids(vec!["c"])]);
+        let options = IngestOptions {
+            mode: MergeMode::Replace,
+            ..sourced("replays/2.arrow", "t1")
+        };
+        ingest(&mut state, &ArrowIpcDecoder, &new, &options).unwrap();
+
+        let summaries = state.source_summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!((summaries[0].key.as_str(), summaries[0].span_count), ("replays/2.arrow", 1));
+        assert_eq!(active_ids(&state), ["c"]);
+
+        // Replacing again from the same source keeps it and its spans
+        ingest(&mut state, &ArrowIpcDecoder, &new, &IngestOptions { last_modified: None, ..options }).unwrap();
+        assert_eq!(state.source_summaries()[0].span_count, 1);
+        assert_eq!(active_ids(&state), ["c"]);
+    }
+
+    #[test]
+    fn timestamps_are_normalized_to_nanoseconds() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(TimestampMillisecondArray::from(vec![Some(5), None])),
+            ],
+        )
+        .unwrap();
+
+        let records = batch_records(&batch, 3);
+        assert_eq!(records[0].timestamp_ns, Some(5_000_000));
+        assert_eq!(records[1].timestamp_ns, None);
+        assert_eq!(records[1].batch, 3);
+    }
+
+    #[test]
+    fn durations_status_and_attributes_are_read() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("duration_ms", DataType::Float64, true),
+            Field::new("status", DataType::Utf8, true),
+            Field::new("attr.http.status_code", DataType::Int64, true),
+            Field::new("region", DataType::Utf8, true),
+            Field::new("trace_id", DataType::Utf8, true),
+            Field::new("parent_span_id", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Float64Array::from(vec![Some(1.5), None])),
+                Arc::new(StringArray::from(vec![Some("error"), None])),
+                Arc::new(Int64Array::from(vec![Some(503), None])),
+                Arc::new(StringArray::from(vec!["eu", "us"])),
+                Arc::new(StringArray::from(vec!["t1", "t1"])),
+                Arc::new(StringArray::from(vec!["", "a"])),
+            ],
+        )
+        .unwrap();
+
+        let records = batch_records(&batch, 0);
+        assert_eq!(records[0].duration_ns, Some(1_500_000));
+        assert_eq!(records[0].status.as_deref(), Some("error"));
+        assert_eq!(
+            records[0].attributes,
+            [
+                (Arc::from("http.status_code"), AttrValue::Int(503)),
+                (Arc::from("region"), AttrValue::Str("eu".to_string())),
+            ]
+        );
+        assert_eq!(records[0].trace_id.as_deref(), Some("t1"));
+        assert_eq!(records[0].parent_id, None);
+        assert_eq!(records[1].parent_id.as_deref(), Some("a"));
+        assert_eq!(records[1].duration_ns, None);
+        assert_eq!(records[1].attributes, [(Arc::from("region"), AttrValue::Str("us".to_string()))]);
+    }
+
+    #[test]
+    fn duration_falls_back_to_end_minus_start() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("start_time", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+            Field::new("end_time", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(TimestampMillisecondArray::from(vec![Some(100), Some(100)])),
+                Arc::new(TimestampMillisecondArray::from(vec![Some(350), None])),
+            ],
+        )
+        .unwrap();
+
+        let records = batch_records(&batch, 0);
+        assert_eq!(records[0].duration_ns, Some(250_000_000));
+        assert_eq!(records[1].duration_ns, None);
+        assert!(records[0].attributes.is_empty());
+    }
+
+    #[test]
+    fn bad_chunks_leave_state_untouched() {
+        let mut state = ReplayState::default();
+        let data = arrow_file(&[], &[ids(vec!["a"])]);
+        ingest(&mut state, &ArrowIpcDecoder, &data, &IngestOptions::default()).unwrap();
+
+        let empty = ingest(&mut state, &ArrowIpcDecoder, &[], &IngestOptions::default());
+        assert_eq!(empty.unwrap_err(), IngestError::EmptyChunk);
+        let garbage = ingest(&mut state, &ArrowIpcDecoder, b"not arrow", &IngestOptions::default());
+        assert!(matches!(garbage.unwrap_err(), IngestError::Decode(_)));
+
+        assert_eq!(active_ids(&state), ["a"]);
+    }
+}
@@ -0,0 +1,1049 @@
+// Replay state partitioned by job.
+//
+// The replay list mixes files from many jobs. Each job gets its own span
+// store so services from different runs are never blended together; one job
+// at a time is active and drives rendering.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::analytics::{self, JobComparison};
+use crate::annotations::{Annotation, Annotations, NewAnnotation};
+use crate::anomaly::{self, Anomaly, AnomalyOptions, Monitor};
+use crate::diff::{self, DiffSide, ReplayDiff};
+use crate::filter::{Filter, FilterError, FilterMatch, FilterMode, SpanMatch};
+use crate::grouping::{self, Group, GroupBy, Grouping};
+use crate::labels::{LabelRules, Labels};
+use crate::layout::Pins;
+use crate::provenance::{BatchProvenance, SourceId, SourceRegistry, SourceSummary};
+use crate::search::SearchResult;
+use crate::session::{SavedFilter, Session, SessionWarning};
+use crate::store::{IngestStats, RetentionPolicy, SpanRecord, SpanStore};
+use crate::trace::{self, CriticalPath, TraceError, TraceSpan, TraceSummary};
+use crate::{ReplayData, ServiceNode};
+
+/// Job used when neither the caller nor the data names one.
+pub const DEFAULT_JOB: &str = "default";
+
+#[derive(Debug, Default)]
+pub struct JobData {
+    pub store: SpanStore,
+    pub timestamp: String,
+    /// Anomalies found in the store, oldest first. Their severities set
+    /// the services' statuses.
+    pub anomalies: Vec<Anomaly>,
+    /// User annotations plus one per anomaly.
+    pub annotations: Annotations,
+    // Detector state carried between ingests
+    monitor: Monitor,
+    // When the job was last ingested into or selected, for job eviction
+    last_used: u64,
+}
+
+impl JobData {
+    // Rerun anomaly detection over the whole store
+    fn detect(&mut self, options: &AnomalyOptions) {
+        self.monitor = Monitor::new(*options);
+        self.monitor.add(self.store.timed_spans());
+        self.publish();
+    }
+
+    // Feed the spans stored from `seq` on to the detectors. Evictions and
+    // spans arriving in windows already scored need the full pass instead.
+    fn detect_since(&mut self, seq: u64, stats: &IngestStats, options: &AnomalyOptions) {
+        if stats.evicted_spans > 0 || stats.removed_spans > 0 {
+            return self.detect(options);
+        }
+        self.monitor.add(self.store.timed_spans_since(seq));
+        if self.monitor.is_stale() {
+            return self.detect(options);
+        }
+        self.publish();
+    }
+
+    fn publish(&mut self) {
+        self.anomalies = self.monitor.anomalies();
+        self.store.set_statuses(anomaly::service_status(&self.anomalies));
+        self.annotations.set_detected(&self.anomalies);
+    }
+}
+
+/// Summary row returned by `list_jobs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub job_id: String,
+    pub service_count: usize,
+    pub span_count: usize,
+    pub timestamp: String,
+    pub active: bool,
+}
+
+/// Result of `set_filter`, as returned to JavaScript.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterSummary {
+    pub expr: String,
+    pub mode: FilterMode,
+    pub matching_services: usize,
+    pub matching_spans: usize,
+    pub total_services: usize,
+    pub total_spans: usize,
+}
+
+// The filter set by `set_filter` and what it matched in the active job
+#[derive(Debug)]
+struct ActiveFilter {
+    filter: Filter,
+    matched: FilterMatch,
+}
+
+/// Every ingested job plus the one currently selected for rendering.
+///
+/// The span and service limits of the retention policy apply to each job's
+/// store, and `max_jobs` bounds how many stores there are, so memory stays
+/// bounded however many jobs arrive. An active filter and the display
+/// names follow the active job's new and dropped spans, and are rebuilt
+/// when the selection changes; each job's anomaly detection likewise picks
+/// up its new spans as they arrive.
+#[derive(Debug, Default)]
+pub struct ReplayState {
+    jobs: BTreeMap<String, JobData>,
+    active_job: Option<String>,
+    policy: RetentionPolicy,
+    sources: SourceRegistry,
+    filter: Option<ActiveFilter>,
+    anomaly_options: AnomalyOptions,
+    grouping: Grouping,
+    labels: Labels,
+    // Ticks on every ingest and selection; orders jobs for eviction
+    clock: u64,
+}
+
+impl ReplayState {
+    /// Get a job's data, creating it on first use. The first job created becomes active.
+    pub fn job_mut(&mut self, job_id: &str) -> &mut JobData {
+        if self.active_job.is_none() {
+            self.active_job = Some(job_id.to_string());
+        }
+        self.clock += 1;
+        let (policy, options, clock) = (self.policy, self.anomaly_options, self.clock);
+        let job = self.jobs.entry(job_id.to_string()).or_insert_with(|| JobData {
+            store: SpanStore::new(policy),
+            timestamp: String::new(),
+            anomalies: Vec::new(),
+            annotations: Annotations::default(),
+            monitor: Monitor::new(options),
+            last_used: 0,
+        });
+        job.last_used = clock;
+        job
+    }
+
+    pub fn active_job(&self) -> Option<&str> {
+        self.active_job.as_deref()
+    }
+
+    pub fn active(&self) -> Option<&JobData> {
+        self.active_job.as_deref().and_then(|id| self.jobs.get(id))
+    }
+
+    /// Select the job that drives rendering. Returns false for unknown jobs.
+    pub fn set_active_job(&mut self, job_id: &str) -> bool {
+        if !self.jobs.contains_key(job_id) {
+            return false;
+        }
+        self.active_job = Some(job_id.to_string());
+        // Marks the job as recently used
+        self.job_mut(job_id);
+        self.refresh_active();
+        true
+    }
+
+    pub fn active_services(&self) -> &[ServiceNode] {
+        self.active().map(|job| job.store.services()).unwrap_or_default()
+    }
+
+    /// Active services that views should draw: all of them, minus those an
+    /// active filter hides.
+    pub fn playback_services(&self) -> Vec<ServiceNode> {
+        let services = self.active_services();
+        match self.filter_match() {
+            Some(matched) => services.iter().filter(|s| matched.shows(&s.id)).cloned().collect(),
+            None => services.to_vec(),
+        }
+    }
+
+    /// Parse `expr` and make it the active filter. On a parse error the
+    /// previous filter stays in place.
+    pub fn set_filter(&mut self, expr: &str, mode: FilterMode) -> Result<FilterSummary, FilterError> {
+        let filter = Filter::parse(expr)?;
+        self.filter = Some(ActiveFilter {
+            filter,
+            matched: FilterMatch { mode, ..Default::default() },
+        });
+        self.refresh_active();
+        Ok(self.filter_summary().expect("filter was just set"))
+    }
+
+    /// Remove the active filter. Returns false if none was set.
+    pub fn clear_filter(&mut self) -> bool {
+        self.filter.take().is_some()
+    }
+
+    pub fn filter_match(&self) -> Option<&FilterMatch> {
+        self.filter.as_ref().map(|active| &active.matched)
+    }
+
+    pub fn filter_summary(&self) -> Option<FilterSummary> {
+        let active = self.filter.as_ref()?;
+        let store = self.active().map(|job| &job.store);
+        Some(FilterSummary {
+            expr: active.filter.source().to_string(),
+            mode: active.matched.mode,
+            matching_services: active.matched.services.len(),
+            matching_spans: active.matched.spans,
+            total_services: store.map_or(0, |s| s.services().len()),
+            total_spans: store.map_or(0, SpanStore::span_count),
+        })
+    }
+
+    /// Search the active job. See `SpanStore::search`.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.active().map(|job| job.store.search(query, limit)).unwrap_or_default()
+    }
+
+    /// The active job's traces, slowest first, at most `limit` of them.
+    pub fn traces(&self, limit: usize) -> Vec<TraceSummary> {
+        let Some(job) = self.active() else { return Vec::new() };
+        let mut traces: Vec<TraceSummary> = job
+            .store
+            .trace_ids()
+            .filter_map(|trace_id| trace::summarize(trace_id, &job.store.trace(trace_id)))
+            .collect();
+        traces.sort_by(|a, b| b.duration_ns.cmp(&a.duration_ns).then_with(|| a.trace_id.cmp(&b.trace_id)));
+        traces.truncate(limit);
+        traces
+    }
+
+    /// The spans of one trace in the active job.
+    pub fn trace(&self, trace_id: &str) -> Vec<TraceSpan> {
+        self.active().map(|job| job.store.trace(trace_id)).unwrap_or_default()
+    }
+
+    /// Which spans of one trace in the active job the active filter keeps.
+    /// `None` without a filter.
+    pub fn trace_match(&self, trace_id: &str) -> Option<SpanMatch> {
+        let active = self.filter.as_ref()?;
+        let (job_id, job) = self.jobs.get_key_value(self.active_job.as_deref()?)?;
+        Some(job.store.filter_trace(job_id, &active.filter, trace_id, active.matched.mode))
+    }
+
+    /// Critical path of one trace in the active job.
+    pub fn critical_path(&self, trace_id: &str) -> Result<CriticalPath, TraceError> {
+        trace::critical_path(trace_id, &self.trace(trace_id))
+    }
+
+    // Bring what depends on the active job's spans up to date after an
+    // operation on its store that added the spans from `seq` on: only the
+    // new and dropped spans are filtered, and only their services relabelled
+    fn update_active(&mut self, seq: u64) {
+        let Some(active_job) = self.active_job.as_deref() else { return };
+        let Some((job_id, job)) = self.jobs.get_key_value(active_job) else { return };
+        if let Some(column) = self.labels.rules().column.clone() {
+            for service in job.store.touched_services(seq) {
+                self.labels.set_name(service, job.store.service_attribute(service, &column));
+            }
+        }
+        if let Some(active) = self.filter.as_mut() {
+            job.store.refilter(job_id, &active.filter, seq, &mut active.matched);
+        }
+    }
+
+    // Re-derive what depends on the active job's spans: the filter match and
+    // the display names read from the label column
+    fn refresh_active(&mut self) {
+        self.relabel();
+        let Some(active) = self.filter.as_mut() else { return };
+        let mode = active.matched.mode;
+        let job = self.active_job.as_deref().and_then(|id| self.jobs.get_key_value(id));
+        active.matched = match job {
+            Some((job_id, job)) => job.store.filter(job_id, &active.filter, mode),
+            None => FilterMatch { mode, ..Default::default() },
+        };
+    }
+
+    pub fn set_policy(&mut self, policy: RetentionPolicy) -> IngestStats {
+        self.policy = policy;
+        let mut stats = IngestStats::default();
+        for job in self.jobs.values_mut() {
+            let job_stats = job.store.set_policy(policy);
+            if job_stats.evicted_spans > 0 {
+                job.detect(&self.anomaly_options);
+            }
+            stats += job_stats;
+        }
+        stats += self.evict_jobs(None);
+        let seq = self.active().map_or(0, |job| job.store.next_seq());
+        self.update_active(seq);
+        stats
+    }
+
+    // Drop the least recently used jobs beyond `max_jobs`. The active job
+    // and `keep`, the job just ingested into, always stay.
+    fn evict_jobs(&mut self, keep: Option<&str>) -> IngestStats {
+        let mut stats = IngestStats::default();
+        let Some(max_jobs) = self.policy.max_jobs else { return stats };
+        while self.jobs.len() > max_jobs {
+            let oldest = self
+                .jobs
+                .iter()
+                .filter(|(id, _)| Some(id.as_str()) != self.active_job.as_deref() && Some(id.as_str()) != keep)
+                .min_by_key(|(_, job)| job.last_used)
+                .map(|(id, _)| id.clone());
+            let Some(job_id) = oldest else { break };
+            let job = self.jobs.remove(&job_id).expect("job was just found");
+            self.sources.forget_job(&job_id);
+            stats.evicted_spans += job.store.span_count();
+            stats.evicted_services += job.store.services().len();
+            stats.evicted_jobs += 1;
+        }
+        stats
+    }
+
+    pub fn anomaly_options(&self) -> &AnomalyOptions {
+        &self.anomaly_options
+    }
+
+    /// Replace the detector settings and rerun detection on every job.
+    pub fn set_anomaly_options(&mut self, options: AnomalyOptions) {
+        self.anomaly_options = options;
+        for job in self.jobs.values_mut() {
+            job.detect(&options);
+        }
+    }
+
+    /// Anomalies in the active job, oldest first.
+    pub fn anomalies(&self) -> &[Anomaly] {
+        self.active().map(|job| job.anomalies.as_slice()).unwrap_or_default()
+    }
+
+    /// The active job's annotations, in time order.
+    pub fn annotations(&self) -> &[Annotation] {
+        self.active().map(|job| job.annotations.all()).unwrap_or_default()
+    }
+
+    /// Annotate the active job. Returns `None` when no job is active.
+    pub fn add_annotation(&mut self, annotation: NewAnnotation) -> Option<u32> {
+        let job_id = self.active_job.clone()?;
+        Some(self.jobs.get_mut(&job_id)?.annotations.add(annotation))
+    }
+
+    pub fn remove_annotation(&mut self, id: u32) -> bool {
+        let Some(job_id) = self.active_job.clone() else { return false };
+        self.jobs.get_mut(&job_id).is_some_and(|job| job.annotations.remove(id))
+    }
+
+    /// Replace the active job's user annotations with saved ones.
+    pub fn load_annotations(&mut self, saved: Vec<Annotation>) -> bool {
+        let Some(job_id) = self.active_job.clone() else { return false };
+        let Some(job) = self.jobs.get_mut(&job_id) else { return false };
+        job.annotations.load(saved);
+        true
+    }
+
+    /// The annotation after (or before) `from` in the active job.
+    pub fn step_annotation(&self, from: Option<u32>, forward: bool) -> Option<&Annotation> {
+        self.active()?.annotations.step(from, forward)
+    }
+
+    /// Pin a service of the active job at a layout position. The pin holds
+    /// through re-layouts and later appends, and applies again if the
+    /// service is evicted and returns. False when no job is active.
+    pub fn pin_node(&mut self, service: &str, position: [f32; 2]) -> bool {
+        let Some(job_id) = self.active_job.clone() else { return false };
+        let Some(job) = self.jobs.get_mut(&job_id) else { return false };
+        job.store.pin(service, position);
+        true
+    }
+
+    /// Return a service to the automatic layout. False if it was not pinned.
+    pub fn unpin(&mut self, service: &str) -> bool {
+        let Some(job_id) = self.active_job.clone() else { return false };
+        self.jobs.get_mut(&job_id).is_some_and(|job| job.store.unpin(service))
+    }
+
+    pub fn pins(&self) -> Pins {
+        self.active().map(|job| job.store.pins().clone()).unwrap_or_default()
+    }
+
+    /// Replace the active job's pins, e.g. with an imported layout.
+    pub fn set_pins(&mut self, pins: Pins) -> bool {
+        let Some(job_id) = self.active_job.clone() else { return false };
+        let Some(job) = self.jobs.get_mut(&job_id) else { return false };
+        job.store.set_pins(pins);
+        true
+    }
+
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    /// Replace the display-name rules. Fails, keeping the old rules, when
+    /// the pattern is not a valid regex.
+    pub fn set_label_rules(&mut self, rules: LabelRules) -> Result<(), regex::Error> {
+        self.labels = Labels::new(rules)?;
+        self.relabel();
+        Ok(())
+    }
+
+    fn relabel(&mut self) {
+        let names = match (&self.labels.rules().column, self.active()) {
+            (Some(column), Some(job)) => job
+                .store
+                .services()
+                .iter()
+                .filter_map(|node| Some((node.id.clone(), job.store.service_attribute(&node.id, column)?)))
+                .collect(),
+            _ => Default::default(),
+        };
+        self.labels.set_names(names);
+    }
+
+    pub fn grouping(&self) -> &Grouping {
+        &self.grouping
+    }
+
+    /// Group services on the map, or stop grouping with `None`. Every group
+    /// starts expanded.
+    pub fn set_grouping(&mut self, by: Option<GroupBy>) {
+        self.grouping = Grouping { by, ..Default::default() };
+    }
+
+    /// Groups among the services views draw, with their metrics rolled up
+    /// over the active job.
+    pub fn groups(&self) -> Vec<Group> {
+        let (Some(by), Some(job)) = (&self.grouping.by, self.active()) else { return Vec::new() };
+        let key = |service: &str| match by {
+            GroupBy::Prefix => grouping::prefix(service).map(str::to_string),
+            GroupBy::Attribute(key) => job.store.service_attribute(service, key),
+        };
+        grouping::groups(&self.playback_services(), key, &job.store.totals(None), &self.grouping.collapsed)
+    }
+
+    /// Collapse a group into one node, or expand it. False for unknown groups.
+    pub fn collapse_group(&mut self, name: &str, collapsed: bool) -> bool {
+        if !self.groups().iter().any(|group| group.name == name) {
+            return false;
+        }
+        if collapsed {
+            self.grouping.collapsed.insert(name.to_string());
+        } else {
+            self.grouping.collapsed.remove(name);
+        }
+        true
+    }
+
+    /// Calls between services in the active job, as (caller, callee).
+    pub fn service_edges(&self) -> Vec<(String, String)> {
+        self.active().map(|job| job.store.totals(None).edges.into_keys().collect()).unwrap_or_default()
+    }
+
+    /// Oldest and newest span timestamps in the active job.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        self.active()?.store.time_range()
+    }
+
+    /// Total retained spans across every job.
+    pub fn span_count(&self) -> usize {
+        self.jobs.values().map(|job| job.store.span_count()).sum()
+    }
+
+    /// Drop every span of `job_id` and forget the sources that fed it, so
+    /// the next ingest refills it from scratch. Pins and annotations stay.
+    pub fn clear_job(&mut self, job_id: &str) {
+        self.sources.forget_job(job_id);
+        let options = self.anomaly_options;
+        let job = self.job_mut(job_id);
+        job.store.clear();
+        job.detect(&options);
+        if self.active_job.as_deref() == Some(job_id) {
+            self.refresh_active();
+        }
+    }
+
+    /// Merge records into `job_id`. The least recently used jobs beyond
+    /// `max_jobs` are evicted afterwards.
+    pub fn ingest_job(
+        &mut self,
+        job_id: &str,
+        records: Vec<SpanRecord>,
+        timestamp: &str,
+        source: Option<SourceId>,
+    ) -> IngestStats {
+        let options = self.anomaly_options;
+        let was_active = self.active_job.as_deref() == Some(job_id);
+        let job = self.job_mut(job_id);
+        job.timestamp = timestamp.to_string();
+        let seq = job.store.next_seq();
+        let mut stats = job.store.ingest(records, source);
+        job.detect_since(seq, &stats, &options);
+        stats += self.evict_jobs(Some(job_id));
+        if was_active {
+            self.update_active(seq);
+        } else if self.active_job.as_deref() == Some(job_id) {
+            self.refresh_active();
+        }
+        stats
+    }
+
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    /// Register a source version, withdrawing whatever an older version of
+    /// the same key contributed.
+    pub fn register_source(
+        &mut self,
+        key: &str,
+        last_modified: Option<String>,
+        timestamp: &str,
+        batches: Vec<BatchProvenance>,
+    ) -> (SourceId, IngestStats) {
+        let stats = self.remove_source(key).unwrap_or_default();
+        let id = self.sources.register(key, last_modified, timestamp, batches);
+        (id, stats)
+    }
+
+    /// Remove every span contributed by `key`. Returns `None` for unknown sources.
+    pub fn remove_source(&mut self, key: &str) -> Option<IngestStats> {
+        let entry = self.sources.remove(key)?;
+        let mut stats = IngestStats::default();
+        for job_id in &entry.jobs {
+            if let Some(job) = self.jobs.get_mut(job_id) {
+                let job_stats = job.store.remove_source(entry.id);
+                if job_stats.removed_spans > 0 {
+                    job.detect(&self.anomaly_options);
+                }
+                stats += job_stats;
+            }
+        }
+        if self.active_job.as_ref().is_some_and(|id| entry.jobs.contains(id)) {
+            let seq = self.active().map_or(0, |job| job.store.next_seq());
+            self.update_active(seq);
+        }
+        Some(stats)
+    }
+
+    pub fn source_summaries(&self) -> Vec<SourceSummary> {
+        self.sources
+            .iter()
+            .map(|(key, entry)| SourceSummary {
+                key: key.clone(),
+                last_modified: entry.last_modified.clone(),
+                ingested_at: entry.ingested_at.clone(),
+                jobs: entry.jobs.iter().cloned().collect(),
+                batches: entry.batches.clone(),
+                span_count: entry
+                    .jobs
+                    .iter()
+                    .filter_map(|job_id| self.jobs.get(job_id))
+                    .map(|job| job.store.source_span_count(entry.id))
+                    .sum(),
+            })
+            .collect()
+    }
+
+    pub fn jobs(&self) -> Vec<JobSummary> {
+        self.jobs
+            .iter()
+            .map(|(job_id, job)| JobSummary {
+                job_id: job_id.clone(),
+                service_count: job.store.services().len(),
+                span_count: job.store.span_count(),
+                timestamp: job.timestamp.clone(),
+                active: self.active_job.as_deref() == Some(job_id.as_str()),
+            })
+            .collect()
+    }
+
+    /// Snapshot of the active job for JavaScript.
+    pub fn snapshot(&self) -> ReplayData {
+        match (self.active_job.as_deref(), self.active()) {
+            (Some(job_id), Some(job)) => job.store.snapshot(job_id, &job.timestamp),
+            _ => ReplayData {
+                services: Vec::new(),
+                all_service_ids: Vec::new(),
+                timestamp: String::new(),
+                job_id: String::new(),
+            },
+        }
+    }
+
+    /// Per-service and per-edge deltas from `before` to `after`. Returns
+    /// `None` if either side names an unknown job.
+    pub fn diff(&self, before: DiffSide, after: DiffSide) -> Option<ReplayDiff> {
+        let a = self.jobs.get(&before.job)?.store.totals(before.range());
+        let b = self.jobs.get(&after.job)?.store.totals(after.range());
+        Some(diff::diff(before, &a, after, &b))
+    }
+
+    /// The active filter evaluated against both jobs of a diff, so a
+    /// service matching on either side stays. `None` without a filter.
+    pub fn diff_match(&self, diff: &ReplayDiff) -> Option<FilterMatch> {
+        let active = self.filter.as_ref()?;
+        let mut matched = FilterMatch { mode: active.matched.mode, ..Default::default() };
+        let jobs: BTreeSet<&str> = [diff.before.job.as_str(), diff.after.job.as_str()].into();
+        for job_id in jobs {
+            let Some(job) = self.jobs.get(job_id) else { continue };
+            let side = job.store.filter(job_id, &active.filter, active.matched.mode);
+            for (service, count) in side.services {
+                *matched.services.entry(service).or_default() += count;
+            }
+            matched.spans += side.spans;
+        }
+        Some(matched)
+    }
+
+    /// The job selection, filter, user annotations and hand-placed positions
+    /// as a session. The caller fills in the view state it owns.
+    pub fn session(&self) -> Session {
+        let filter = self.filter_summary().map(|summary| SavedFilter { expr: summary.expr, mode: summary.mode });
+        let mut session = Session {
+            job: self.active_job.clone(),
+            filter,
+            grouping: self.grouping.clone(),
+            labels: self.labels.rules().clone(),
+            ..Default::default()
+        };
+        for (job_id, job) in &self.jobs {
+            let annotations = job.annotations.user();
+            if !annotations.is_empty() {
+                session.annotations.insert(job_id.clone(), annotations);
+            }
+            if !job.store.pins().is_empty() {
+                session.positions.insert(job_id.clone(), job.store.pins().clone());
+            }
+        }
+        session
+    }
+
+    /// Restore what `session` saved. Parts that cannot be restored are
+    /// checked before anything changes and reported instead: a job that is
+    /// not loaded leaves the current one active, a filter that no longer
+    /// parses leaves no filter active and a label pattern that no longer
+    /// compiles keeps the current rules.
+    pub fn restore_session(&mut self, session: &Session) -> Vec<SessionWarning> {
+        let mut warnings = Vec::new();
+        let filter = session.filter.as_ref().and_then(|saved| match Filter::parse(&saved.expr) {
+            Ok(filter) => Some((filter, saved.mode)),
+            Err(e) => {
+                warnings.push(SessionWarning::FilterDropped { expr: saved.expr.clone(), message: e.message });
+                None
+            }
+        });
+        // E.g. a pattern in a newer regex syntax
+        let labels = Labels::new(session.labels.clone())
+            .map_err(|e| warnings.push(SessionWarning::LabelsDropped { message: e.to_string() }))
+            .ok();
+
+        if let Some(job_id) = &session.job {
+            if !self.set_active_job(job_id) {
+                warnings.push(SessionWarning::UnknownJob { job: job_id.clone() });
+            }
+        }
+        for (job_id, job) in &mut self.jobs {
+            job.annotations.load(session.annotations.get(job_id).cloned().unwrap_or_default());
+            job.store.set_pins(session.positions.get(job_id).cloned().unwrap_or_default());
+        }
+        self.grouping = session.grouping.clone();
+        if let Some(labels) = labels {
+            self.labels = labels;
+        }
+        self.filter = filter.map(|(filter, mode)| ActiveFilter {
+            filter,
+            matched: FilterMatch { mode, ..Default::default() },
+        });
+        self.refresh_active();
+        warnings
+    }
+
+    /// Compare the service sets of two jobs. Returns `None` if either is unknown.
+    pub fn compare_jobs(&self, job_a: &str, job_b: &str) -> Option<JobComparison> {
+        let a = &self.jobs.get(job_a)?.store;
+        let b = &self.jobs.get(job_b)?.store;
+        Some(analytics::compare_stores(job_a, a, job_b, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::diff::Change;
+    use crate::store::AttrValue;
+
+    fn records(services: &[&str]) -> Vec<SpanRecord> {
+        services
+            .iter()
+            .enumerate()
+            .map(|(i, service)| SpanRecord {
+                key: format!("{}-{}", service, i),
+                service: service.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_job_is_active_until_another_is_selected() {
+        let mut state = ReplayState::default();
+        state.ingest_job("a", records(&["cart"]), "t", None);
+        state.ingest_job("b", records(&["auth", "auth"]), "t", None);
+
+        assert_eq!(state.snapshot().job_id, "a");
+        assert!(!state.set_active_job("missing"));
+        assert!(state.set_active_job("b"));
+        assert_eq!(state.snapshot().all_service_ids, ["auth"]);
+        assert_eq!(state.span_count(), 3);
+    }
+
+    #[test]
+    fn least_recently_used_jobs_are_evicted_beyond_max_jobs() {
+        let mut state = ReplayState::default();
+        state.set_policy(RetentionPolicy { max_jobs: Some(2), ..Default::default() });
+        state.ingest_job("a", records(&["cart"]), "t", None);
+        state.ingest_job("b", records(&["auth"]), "t", None);
+        let stats = state.ingest_job("c", records(&["db", "db"]), "t", None);
+
+        // "a" is active, so the older of the rest goes
+        let jobs: Vec<_> = state.jobs().into_iter().map(|job| job.job_id).collect();
+        assert_eq!(jobs, ["a", "c"]);
+        assert_eq!((stats.evicted_jobs, stats.evicted_spans), (1, 1));
+
+        // Selecting a job marks it as used
+        state.set_active_job("c");
+        state.ingest_job("d", records(&["search"]), "t", None);
+        let jobs: Vec<_> = state.jobs().into_iter().map(|job| job.job_id).collect();
+        assert_eq!(jobs, ["c", "d"]);
+        assert_eq!(state.span_count(), 3);
+    }
+
+    fn with_status(service: &str, key: &str, status: &str) -> SpanRecord {
+        SpanRecord {
+            key: key.to_string(),
+            service: service.to_string(),
+            status: Some(status.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(services: &[ServiceNode]) -> Vec<&str> {
+        services.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn filter_follows_ingest_and_active_job() {
+        let mut state = ReplayState::default();
+        let records = vec![with_status("cart", "1", "ok"), with_status("auth", "2", "error")];
+        state.ingest_job("a", records, "t", None);
+        state.ingest_job("b", vec![with_status("search", "3", "error")], "t", None);
+
+        let summary = state.set_filter("status = error", FilterMode::Hide).unwrap();
+        assert_eq!((summary.matching_services, summary.total_services), (1, 2));
+        assert_eq!(ids(&state.playback_services()), ["auth"]);
+
+        state.ingest_job("a", vec![with_status("cart", "4", "error")], "t", None);
+        assert_eq!(ids(&state.playback_services()), ["cart", "auth"]);
+
+        state.set_active_job("b");
+        assert_eq!(state.filter_summary().unwrap().matching_spans, 1);
+        assert_eq!(ids(&state.playback_services()), ["search"]);
+
+        let error = state.set_filter("status = ", FilterMode::Dim).unwrap_err();
+        assert_eq!((error.start, error.end), (9, 9));
+        assert_eq!(state.filter_match().unwrap().mode, FilterMode::Hide);
+
+        assert!(state.clear_filter());
+        assert!(state.filter_summary().is_none());
+    }
+
+    #[test]
+    fn filter_and_names_follow_new_and_evicted_spans() {
+        let mut state = ReplayState::default();
+        state.set_policy(RetentionPolicy { max_spans: Some(3), ..Default::default() });
+        let rules = LabelRules { column: Some("display_name".to_string()), ..Default::default() };
+        state.set_label_rules(rules).unwrap();
+        state.set_filter("status = error", FilterMode::Dim).unwrap();
+        let span = |service: &str, key: &str, status: &str, name: &str| SpanRecord {
+            attributes: vec![(Arc::from("display_name"), AttrValue::Str(name.to_string()))],
+            ..with_status(service, key, status)
+        };
+        let full = |state: &ReplayState| {
+            let filter = Filter::parse("status = error").unwrap();
+            state.active().unwrap().store.filter("a", &filter, FilterMode::Dim)
+        };
+
+        let batch = vec![span("cart", "1", "error", "Cart"), span("auth", "2", "ok", "Auth")];
+        state.ingest_job("a", batch, "t", None);
+        assert_eq!(state.filter_match(), Some(&full(&state)));
+        assert_eq!(state.filter_match().unwrap().services.len(), 1);
+
+        // Evicts span 1, so cart's only match goes and cart is renamed
+        let batch = vec![span("auth", "3", "error", "Login"), span("cart", "4", "ok", "Basket")];
+        state.ingest_job("a", batch, "t", None);
+        assert_eq!(state.filter_match(), Some(&full(&state)));
+        assert_eq!(state.filter_match().unwrap().services.keys().collect::<Vec<_>>(), ["auth"]);
+        assert_eq!(state.labels().label("cart"), "Basket");
+
+        // Evicts auth entirely
+        let batch = vec![span("db", "5", "error", "Postgres"), span("db", "6", "ok", "Postgres")];
+        state.ingest_job("a", batch, "t", None);
+        assert_eq!(state.filter_match(), Some(&full(&state)));
+        assert_eq!(state.filter_match().unwrap().services.keys().collect::<Vec<_>>(), ["db"]);
+        assert_eq!(state.labels().label("auth"), "auth");
+        assert_eq!(state.labels().label("db"), "Postgres");
+    }
+
+    fn in_trace(trace: &str, key: &str, parent: Option<&str>, start_ns: i64, duration_ns: i64) -> SpanRecord {
+        SpanRecord {
+            key: key.to_string(),
+            service: format!("svc-{}", key),
+            timestamp_ns: Some(start_ns),
+            duration_ns: Some(duration_ns),
+            trace_id: Some(trace.to_string()),
+            parent_id: parent.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn traces_are_listed_slowest_first_and_follow_eviction() {
+        let mut state = ReplayState::default();
+        let records = vec![
+            in_trace("fast", "f", None, 0, 10),
+            in_trace("slow", "root", None, 0, 100),
+            in_trace("slow", "child", Some("root"), 20, 50),
+        ];
+        state.ingest_job("a", records, "t", None);
+
+        let traces = state.traces(10);
+        assert_eq!(traces.iter().map(|t| t.trace_id.as_str()).collect::<Vec<_>>(), ["slow", "fast"]);
+        assert_eq!((traces[0].span_count, traces[0].root_service.as_str()), (2, "svc-root"));
+        assert_eq!(state.traces(1).len(), 1);
+        assert_eq!(state.critical_path("slow").unwrap().span_ids, ["root", "child"]);
+
+        // Evicting the parent leaves the child as the trace's root
+        state.set_policy(RetentionPolicy { max_spans: Some(1), ..Default::default() });
+        assert_eq!(state.traces(10).len(), 1);
+        assert_eq!(state.critical_path("slow").unwrap().root_span_id, "child");
+        assert!(state.critical_path("fast").is_err());
+    }
+
+    #[test]
+    fn anomalies_set_service_status() {
+        let mut state = ReplayState::default();
+        state.set_anomaly_options(AnomalyOptions { window_ns: 1_000, ..Default::default() });
+        let span = |i: i64, service: &str, duration_ns: i64| SpanRecord {
+            key: format!("{}-{}", service, i),
+            service: service.to_string(),
+            timestamp_ns: Some(i * 1_000),
+            duration_ns: Some(duration_ns),
+            ..Default::default()
+        };
+        let steady = || -> Vec<SpanRecord> {
+            (0..20).flat_map(|i| [span(i, "cart", 100), span(i, "auth", 100)]).collect()
+        };
+        state.ingest_job("a", steady(), "t", None);
+        assert!(state.anomalies().is_empty());
+        assert!(state.active_services().iter().all(|s| s.status == "healthy"));
+
+        state.ingest_job("a", vec![span(20, "auth", 900)], "t", None);
+        assert_eq!(state.anomalies().len(), 1);
+        assert_eq!(state.anomalies()[0].start_ns, 20_000);
+        let status = |state: &ReplayState, id: &str| {
+            state.active_services().iter().find(|s| s.id == id).unwrap().status.clone()
+        };
+        assert_eq!((status(&state, "auth").as_str(), status(&state, "cart").as_str()), ("error", "healthy"));
+        assert_eq!(state.time_range(), Some((0, 20_000)));
+        assert_eq!(state.annotations()[0].label, "auth latency spike");
+        let deploy = state.add_annotation(NewAnnotation { timestamp_ns: 5_000, ..Default::default() }).unwrap();
+        assert_eq!(state.step_annotation(None, true).unwrap().id, deploy);
+
+        // Replacing the job's data without the slow span clears the status
+        // and its annotation again
+        state.clear_job("a");
+        state.ingest_job("a", steady(), "t", None);
+        assert_eq!(status(&state, "auth"), "healthy");
+        let ids: Vec<u32> = state.annotations().iter().map(|a| a.id).collect();
+        assert_eq!(ids, [deploy]);
+    }
+
+    #[test]
+    fn sessions_restore_job_filter_annotations_and_positions() {
+        let mut state = ReplayState::default();
+        state.ingest_job("a", records(&["cart", "auth"]), "t", None);
+        state.ingest_job("b", records(&["cart"]), "t", None);
+        state.set_active_job("b");
+        state.set_filter("service = cart", FilterMode::Hide).unwrap();
+        state.add_annotation(NewAnnotation { timestamp_ns: 5, label: "deploy".to_string(), ..Default::default() });
+        state.job_mut("a").store.pin("auth", [0.5, 0.5]);
+        let saved = state.session();
+
+        let mut restored = ReplayState::default();
+        restored.ingest_job("a", records(&["cart", "auth"]), "t", None);
+        restored.ingest_job("b", records(&["cart"]), "t", None);
+        assert!(restored.restore_session(&saved).is_empty());
+
+        assert_eq!(restored.active_job(), Some("b"));
+        assert_eq!(restored.filter_summary().unwrap().expr, "service = cart");
+        assert_eq!(restored.annotations()[0].label, "deploy");
+        restored.set_active_job("a");
+        let auth = restored.active_services().iter().find(|s| s.id == "auth").unwrap();
+        assert_eq!((auth.x, auth.y), (0.5, 0.5));
+        assert_eq!(restored.session(), Session { job: Some("a".to_string()), ..saved });
+    }
+
+    #[test]
+    fn unrestorable_session_parts_are_reported_not_kept() {
+        let mut state = ReplayState::default();
+        state.ingest_job("a", records(&["cart"]), "t", None);
+        state.set_filter("service = cart", FilterMode::Hide).unwrap();
+        let session = Session {
+            job: Some("gone".to_string()),
+            filter: Some(SavedFilter { expr: "status = ".to_string(), mode: FilterMode::Dim }),
+            labels: LabelRules { pattern: Some("[".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+
+        let warnings: Vec<_> = state.restore_session(&session).into_iter().map(|w| w.to_string()).collect();
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("Saved filter 'status = ' dropped"));
+        assert!(warnings[1].starts_with("Saved label rules dropped"));
+        assert_eq!(warnings[2], "Saved job 'gone' is not loaded");
+        // The filter from before the load does not linger
+        assert!(state.filter_match().is_none());
+        assert_eq!(state.active_job(), Some("a"));
+    }
+
+    #[test]
+    fn pins_survive_appends_replaces_and_eviction() {
+        let mut state = ReplayState::default();
+        state.set_policy(RetentionPolicy { max_services: Some(2), ..Default::default() });
+        state.ingest_job("a", records(&["auth", "cart"]), "t", None);
+        assert!(state.pin_node("auth", [0.25, 0.5]));
+        let position = |state: &ReplayState, id: &str| {
+            state.active_services().iter().find(|s| s.id == id).map(|s| [s.x, s.y])
+        };
+        assert_eq!(position(&state, "auth"), Some([0.25, 0.5]));
+
+        // A third service evicts auth, the least recently seen; its pin waits for it to come back
+        state.ingest_job("a", records(&["db"]), "t", None);
+        assert_eq!(position(&state, "auth"), None);
+        state.ingest_job("a", vec![with_status("auth", "auth-again", "ok")], "t", None);
+        assert_eq!(position(&state, "auth"), Some([0.25, 0.5]));
+
+        // Replacing the job's data keeps the pin too
+        state.clear_job("a");
+        state.ingest_job("a", records(&["auth", "db"]), "t", None);
+        assert_eq!(position(&state, "auth"), Some([0.25, 0.5]));
+
+        assert!(state.unpin("auth"));
+        assert!(!state.unpin("auth"));
+        assert_ne!(position(&state, "auth"), Some([0.25, 0.5]));
+    }
+
+    #[test]
+    fn groups_by_attribute_and_remember_collapsed_ones() {
+        let mut state = ReplayState::default();
+        let in_namespace = |service: &str, namespace: &str| SpanRecord {
+            key: service.to_string(),
+            service: service.to_string(),
+            attributes: vec![(Arc::from("k8s.namespace"), AttrValue::Str(namespace.to_string()))],
+            ..Default::default()
+        };
+        let records = [("cart", "shop"), ("checkout", "shop"), ("auth", "iam")];
+        let records = records.iter().map(|(service, namespace)| in_namespace(service, namespace)).collect();
+        state.ingest_job("a", records, "t", None);
+        assert!(state.groups().is_empty());
+
+        state.set_grouping(Some(GroupBy::Attribute("k8s.namespace".to_string())));
+        let groups = state.groups();
+        assert_eq!(groups.len(), 1);
+        assert_eq!((groups[0].name.as_str(), groups[0].members.len()), ("shop", 2));
+
+        assert!(state.collapse_group("shop", true));
+        assert!(!state.collapse_group("iam", true));
+        state.ingest_job("a", vec![in_namespace("payments", "shop")], "t", None);
+        let shop = &state.groups()[0];
+        assert_eq!((shop.collapsed, shop.members.len()), (true, 3));
+        assert!(state.session().grouping.collapsed.contains("shop"));
+    }
+
+    #[test]
+    fn display_names_follow_the_label_column_of_the_active_job() {
+        let mut state = ReplayState::default();
+        let named = |service: &str, name: &str| SpanRecord {
+            key: format!("{}-{}", service, name),
+            service: service.to_string(),
+            attributes: vec![(Arc::from("display_name"), AttrValue::Str(name.to_string()))],
+            ..Default::default()
+        };
+        state.ingest_job("a", vec![named("svc-1", "Cart")], "t", None);
+        state.ingest_job("b", vec![named("svc-1", "Basket")], "t", None);
+        let rules = LabelRules { column: Some("display_name".to_string()), ..Default::default() };
+        state.set_label_rules(rules).unwrap();
+        assert_eq!(state.labels().label("svc-1"), "Cart");
+
+        state.set_active_job("b");
+        assert_eq!(state.labels().label("svc-1"), "Basket");
+        assert!(state.set_label_rules(LabelRules { pattern: Some("[".to_string()), ..Default::default() }).is_err());
+        assert_eq!(state.labels().label("svc-1"), "Basket");
+    }
+
+    #[test]
+    fn diff_compares_time_windows_and_edges() {
+        let mut state = ReplayState::default();
+        let call = |i: i64, service: &str, parent: Option<String>, status: &str| SpanRecord {
+            key: format!("{}-{}", service, i),
+            service: service.to_string(),
+            timestamp_ns: Some(i * 1_000),
+            duration_ns: Some(100),
+            status: Some(status.to_string()),
+            parent_id: parent,
+            ..Default::default()
+        };
+        let records: Vec<SpanRecord> = (0..20)
+            .flat_map(|i| {
+                let status = if i >= 10 { "error" } else { "ok" };
+                [call(i, "gateway", None, "ok"), call(i, "cart", Some(format!("gateway-{}", i)), status)]
+            })
+            .collect();
+        state.ingest_job("a", records, "t", None);
+
+        let window = |start_ns, end_ns| DiffSide { job: "a".to_string(), start_ns: Some(start_ns), end_ns: Some(end_ns) };
+        let diff = state.diff(window(0, 10_000), window(10_000, 20_000)).unwrap();
+        let cart = diff.services.iter().find(|s| s.service == "cart").unwrap();
+        assert_eq!((cart.delta.change, cart.delta.error_rate_delta), (Change::Changed, Some(1.0)));
+        assert!((cart.delta.before.unwrap().rate_per_s.unwrap() - 1_000_000.0).abs() < 1e-3);
+        assert_eq!(diff.edges.len(), 1);
+        assert_eq!((diff.edges[0].from.as_str(), diff.edges[0].delta.score), ("gateway", 1.0));
+
+        let missing = DiffSide { job: "missing".to_string(), start_ns: None, end_ns: None };
+        assert!(state.diff(window(0, 1), missing).is_none());
+    }
+
+    #[test]
+    fn compare_reports_added_removed_and_shared_services() {
+        let mut state = ReplayState::default();
+        state.ingest_job("a", records(&["cart", "auth"]), "t", None);
+        state.ingest_job("b", records(&["auth", "auth", "search"]), "t", None);
+
+        let diff = state.compare_jobs("a", "b").unwrap();
+        assert_eq!(diff.only_in_a, ["cart"]);
+        assert_eq!(diff.only_in_b, ["search"]);
+        assert_eq!(diff.shared.len(), 1);
+        assert_eq!((diff.shared[0].span_count_a, diff.shared[0].span_count_b), (1, 2));
+        assert!(state.compare_jobs("a", "missing").is_none());
+    }
+}
@@ -0,0 +1,126 @@
+// Display names: how a service ID becomes the label every view draws.
+//
+// Rules are tried in order. An alias for the exact ID wins, then the value
+// of the display-name column on the service's spans. Otherwise the pattern
+// rewrites the ID and the first matching prefix and suffix are stripped from
+// the result. With no pattern and nothing to strip, labels fall back to
+// `extract_service_number`, so unconfigured replays look as they always have.
+
+use std::collections::{BTreeMap, HashMap};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::extract_service_number;
+
+/// What `set_label_rules` takes; every field is optional.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelRules {
+    /// Labels for exact service IDs.
+    pub aliases: BTreeMap<String, String>,
+    /// Span attribute holding the display name, e.g. `service.display_name`.
+    pub column: Option<String>,
+    /// Regex matched against the service ID.
+    pub pattern: Option<String>,
+    /// What a match becomes, in regex replacement syntax (`$1`, `${name}`).
+    /// Defaults to the first capture group, or the whole match without groups.
+    pub template: Option<String>,
+    pub strip_prefixes: Vec<String>,
+    pub strip_suffixes: Vec<String>,
+}
+
+/// Compiled rules plus the display names read from the column.
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    rules: LabelRules,
+    pattern: Option<Regex>,
+    names: HashMap<String, String>,
+}
+
+impl Labels {
+    pub fn new(rules: LabelRules) -> Result<Self, regex::Error> {
+        let pattern = rules.pattern.as_deref().map(Regex::new).transpose()?;
+        Ok(Self { rules, pattern, names: HashMap::new() })
+    }
+
+    pub fn rules(&self) -> &LabelRules {
+        &self.rules
+    }
+
+    /// Display names from the column, by service ID.
+    pub fn set_names(&mut self, names: HashMap<String, String>) {
+        self.names = names;
+    }
+
+    /// Set or clear the column's display name for one service.
+    pub fn set_name(&mut self, service: &str, name: Option<String>) {
+        match name {
+            Some(name) => self.names.insert(service.to_string(), name),
+            None => self.names.remove(service),
+        };
+    }
+
+    pub fn label(&self, service: &str) -> String {
+        if let Some(label) = self.rules.aliases.get(service).or_else(|| self.names.get(service)) {
+            return label.clone();
+        }
+        let rules = &self.rules;
+        if self.pattern.is_none() && rules.strip_prefixes.is_empty() && rules.strip_suffixes.is_empty() {
+            return extract_service_number(service);
+        }
+
+        let mut label = service.to_string();
+        if let Some(captures) = self.pattern.as_ref().and_then(|pattern| pattern.captures(service)) {
+            label = match &rules.template {
+                Some(template) => {
+                    let mut expanded = String::new();
+                    captures.expand(template, &mut expanded);
+                    expanded
+                }
+                None => captures.get(1).or_else(|| captures.get(0)).map_or("", |m| m.as_str()).to_string(),
+            };
+        }
+        if let Some(rest) = rules.strip_prefixes.iter().find_map(|prefix| label.strip_prefix(prefix.as_str())) {
+            label = rest.to_string();
+        }
+        if let Some(rest) = rules.strip_suffixes.iter().find_map(|suffix| label.strip_suffix(suffix.as_str())) {
+            label = rest.to_string();
+        }
+        if label.is_empty() { service.to_string() } else { label }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unconfigured_labels_match_the_old_extraction() {
+        let labels = Labels::default();
+        assert_eq!(labels.label("job-abc123"), "abc123");
+        assert_eq!(labels.label("checkout-service-7f9a"), "ice-7f9a");
+    }
+
+    #[test]
+    fn aliases_and_column_names_win_over_the_pattern() {
+        let rules = LabelRules {
+            aliases: [("legacy-gw".to_string(), "gateway".to_string())].into(),
+            pattern: Some(r"^(?<team>\w+)-(?<name>\w+)-[0-9a-f]+$".to_string()),
+            template: Some("${name}.${team}".to_string()),
+            strip_suffixes: vec!["-svc".to_string()],
+            ..Default::default()
+        };
+        let mut labels = Labels::new(rules).unwrap();
+        labels.set_names([("pay-api-1".to_string(), "Payments".to_string())].into());
+
+        assert_eq!(labels.label("legacy-gw"), "gateway");
+        assert_eq!(labels.label("pay-api-1"), "Payments");
+        assert_eq!(labels.label("shop-cart-7f9a"), "cart.shop");
+        // No match: only stripping applies, and nothing is cut to empty
+        assert_eq!(labels.label("auth-svc"), "auth");
+        assert_eq!(labels.label("-svc"), "-svc");
+
+        assert!(Labels::new(LabelRules { pattern: Some("(".to_string()), ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+// Service node placement in clip space.
+//
+// Services are laid out automatically, then any pinned ones are moved to
+// their pins. Pins are keyed by service ID, so they survive re-layouts,
+// later appends and the service dropping out and coming back.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ServiceNode;
+
+/// Hand-placed positions by service ID.
+pub type Pins = BTreeMap<String, [f32; 2]>;
+
+pub const LAYOUT_VERSION: u32 = 1;
+
+// Horizontal spacing between service nodes in clip space
+pub const SERVICE_SPACING: f32 = 0.3;
+
+/// Spread services evenly along the x axis, centred on the origin.
+pub fn linear(services: &mut [ServiceNode]) {
+    let count = services.len() as f32;
+    for (i, node) in services.iter_mut().enumerate() {
+        node.x = (i as f32 * SERVICE_SPACING) - (count * SERVICE_SPACING / 2.0);
+        node.y = 0.0;
+    }
+}
+
+/// `count` points evenly spaced on a circle of `radius` around the origin,
+/// clockwise from the top.
+pub fn ring(count: usize, radius: f32) -> Vec<[f32; 2]> {
+    (0..count)
+        .map(|i| {
+            let angle = std::f32::consts::FRAC_PI_2 - i as f32 * std::f32::consts::TAU / count as f32;
+            [radius * angle.cos(), radius * angle.sin()]
+        })
+        .collect()
+}
+
+/// Move pinned services to their pins.
+pub fn apply_pins(services: &mut [ServiceNode], pins: &Pins) {
+    for node in services {
+        if let Some(&[x, y]) = pins.get(&node.id) {
+            node.x = x;
+            node.y = y;
+        }
+    }
+}
+
+/// Pins as exported for sharing a canonical map:
+/// `{ "version": 1, "pins": { "cart": [0.3, 0.1] } }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PinnedLayout {
+    pub version: u32,
+    pub pins: Pins,
+}
+
+impl Default for PinnedLayout {
+    fn default() -> Self {
+        Self { version: LAYOUT_VERSION, pins: Pins::new() }
+    }
+}
+
+impl PinnedLayout {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("layouts always serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_override_the_automatic_layout() {
+        let node = |id: &str| ServiceNode { id: id.to_string(), x: 0.0, y: 0.0, status: "healthy".to_string() };
+        let mut services = [node("a"), node("b")];
+        linear(&mut services);
+        let pins: Pins = [("b".to_string(), [0.5, -0.25]), ("gone".to_string(), [1.0, 1.0])].into();
+        apply_pins(&mut services, &pins);
+        assert_eq!([services[0].x, services[0].y], [-0.3, 0.0]);
+        assert_eq!([services[1].x, services[1].y], [0.5, -0.25]);
+
+        let exported = PinnedLayout { pins, ..Default::default() }.to_json();
+        let imported = PinnedLayout::from_json(&exported).unwrap();
+        assert_eq!(imported.pins.len(), 2);
+        assert!(PinnedLayout::from_json(r#"{ "pins": { "a": [1] } }"#).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+// Target-independent replay engine.
+//
+// Ingestion, replay state, layout, analytics, the scene graph and its
+// tessellation live here so they build as a plain rlib and run under
+// `cargo test` on any host. The wasm-bindgen/web-sys frontend in the parent
+// crate only wires these into JavaScript exports and the WebGPU renderer.
+
+pub mod analytics;
+pub mod annotations;
+pub mod anomaly;
+pub mod bundle;
+pub mod diff;
+pub mod filter;
+pub mod frame;
+pub mod geometry;
+pub mod grouping;
+pub mod ingest;
+pub mod jobs;
+pub mod labels;
+pub mod layout;
+pub mod lod;
+pub mod map;
+pub mod playback;
+pub mod provenance;
+pub mod raster;
+pub mod recording;
+pub mod scene;
+pub mod search;
+pub mod session;
+pub mod snapshot;
+pub mod store;
+pub mod svg;
+pub mod tessellate;
+pub mod theme;
+pub mod trace;
+pub mod waterfall;
+
+mod model;
+
+pub use model::{ReplayData, ServiceNode};
@@ -0,0 +1,254 @@
+// Level of detail: what a scene leaves out when it is drawn small.
+//
+// Decisions are made in screen pixels. Text and icons below a pixel size are
+// hidden, circles too small to read as shapes become square points, and
+// lines whose ends land in the same grid cells are merged into one wider
+// line. Lines with both ends in one cell are dropped. The result is a flat
+// scene for the renderer; the full scene stays the one exported.
+//
+// The pixel scale only follows the camera once the zoom leaves a band
+// around the scale last used, so a zoom resting near a threshold does not
+// make labels flicker on and off.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::frame::Rgba;
+use crate::scene::{DrawItem, Node, Primitive, Scene, Style, Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodOptions {
+    pub enabled: bool,
+    /// Text and icons smaller than this many pixels tall are hidden.
+    pub min_label_px: f32,
+    /// Circles with a smaller radius are drawn as points.
+    pub point_radius_px: f32,
+    /// Grid cell lines are merged in; 0 keeps every line.
+    pub bundle_cell_px: f32,
+    /// Relative zoom change needed before the pixel scale is updated.
+    pub hysteresis: f32,
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_label_px: 6.0,
+            point_radius_px: 3.0,
+            bundle_cell_px: 6.0,
+            hysteresis: 0.25,
+        }
+    }
+}
+
+/// What `simplify` left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LodStats {
+    pub hidden_labels: usize,
+    pub points: usize,
+    pub merged_edges: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Lod {
+    options: LodOptions,
+    scale: Option<f32>,
+}
+
+// Lines merge when they share style and the grid cells of both ends
+#[derive(PartialEq, Eq, Hash)]
+struct EdgeKey {
+    ends: ([i32; 2], [i32; 2]),
+    color: [u32; 4],
+    width: u32,
+}
+
+struct Edge {
+    from: [f32; 2],
+    to: [f32; 2],
+    count: usize,
+    style: Style,
+}
+
+enum Entry<'a> {
+    Item(DrawItem<'a>),
+    Point(Rgba, f32, [f32; 2], f32),
+    Edge(usize),
+}
+
+impl Lod {
+    pub fn new(options: LodOptions) -> Self {
+        Self { options, scale: None }
+    }
+
+    pub fn options(&self) -> &LodOptions {
+        &self.options
+    }
+
+    /// Pixels per scene unit the decisions use, once `update` has been called.
+    pub fn scale(&self) -> Option<f32> {
+        self.scale
+    }
+
+    /// Follow the camera's `pixels_per_unit`. Returns whether the scale used
+    /// for decisions changed.
+    pub fn update(&mut self, pixels_per_unit: f32) -> bool {
+        let band = 1.0 + self.options.hysteresis.max(0.0);
+        match self.scale {
+            Some(scale) if pixels_per_unit <= scale * band && pixels_per_unit >= scale / band => false,
+            _ => {
+                self.scale = Some(pixels_per_unit);
+                true
+            }
+        }
+    }
+
+    /// The flat scene to draw for `scene`, whose root transform is ignored.
+    pub fn simplify(&self, scene: &Scene) -> (Scene, LodStats) {
+        let mut stats = LodStats::default();
+        let mut flat = Scene::new(scene.background);
+        let mut full = scene.clone();
+        if let Some(root) = full.node_mut(Scene::ROOT) {
+            root.transform = Transform::IDENTITY;
+        }
+        let Some(scale) = self.scale.filter(|_| self.options.enabled) else {
+            for item in full.draw_list() {
+                let node = Node::shape(item.primitive.clone(), item.style).transform(item.transform);
+                flat.insert(Scene::ROOT, node);
+            }
+            return (flat, stats);
+        };
+
+        let options = &self.options;
+        let cell = |point: [f32; 2]| point.map(|v| (v * scale / options.bundle_cell_px).floor() as i32);
+        let mut entries = Vec::new();
+        let mut edges: Vec<Edge> = Vec::new();
+        let mut edge_index: HashMap<EdgeKey, usize> = HashMap::new();
+
+        for item in full.draw_list() {
+            let [a, b, c, d, ..] = item.transform.0;
+            let item_scale = (a * d - b * c).abs().sqrt();
+            let pixels = |length: f32| length * item_scale * scale;
+            match item.primitive {
+                Primitive::Text { size, .. } | Primitive::Icon { size, .. } if pixels(*size) < options.min_label_px => {
+                    stats.hidden_labels += 1;
+                }
+                Primitive::Circle { center, radius } if pixels(*radius) < options.point_radius_px => {
+                    let Some(color) = item.style.fill.or(item.style.stroke) else { continue };
+                    // At least a pixel across, so no node disappears
+                    let side = (2.0 * radius * item_scale).max(1.0 / scale);
+                    entries.push(Entry::Point(color, item.style.opacity, item.transform.apply(*center), side));
+                    stats.points += 1;
+                }
+                Primitive::Line { from, to } if options.bundle_cell_px > 0.0 && item.style.stroke.is_some() => {
+                    let (mut from, mut to) = (item.transform.apply(*from), item.transform.apply(*to));
+                    let (mut from_cell, mut to_cell) = (cell(from), cell(to));
+                    if from_cell == to_cell {
+                        stats.merged_edges += 1;
+                        continue;
+                    }
+                    if from_cell > to_cell {
+                        std::mem::swap(&mut from, &mut to);
+                        std::mem::swap(&mut from_cell, &mut to_cell);
+                    }
+                    let width = item.style.stroke_width * item_scale;
+                    let color = item.style.stroke.unwrap_or_default().map(|v| (v * item.style.opacity).to_bits());
+                    let key = EdgeKey { ends: (from_cell, to_cell), color, width: width.to_bits() };
+                    if let Some(&index) = edge_index.get(&key) {
+                        let edge = &mut edges[index];
+                        edge.from = [edge.from[0] + from[0], edge.from[1] + from[1]];
+                        edge.to = [edge.to[0] + to[0], edge.to[1] + to[1]];
+                        edge.count += 1;
+                        stats.merged_edges += 1;
+                    } else {
+                        edge_index.insert(key, edges.len());
+                        entries.push(Entry::Edge(edges.len()));
+                        edges.push(Edge { from, to, count: 1, style: Style { stroke_width: width, ..item.style } });
+                    }
+                }
+                _ => entries.push(Entry::Item(item)),
+            }
+        }
+
+        for entry in entries {
+            let node = match entry {
+                Entry::Item(item) => Node::shape(item.primitive.clone(), item.style).transform(item.transform),
+                Entry::Point(color, opacity, [x, y], side) => {
+                    let point = Primitive::Rect { origin: [x - side / 2.0, y - side / 2.0], size: [side, side] };
+                    Node::shape(point, Style::fill(color).with_opacity(opacity))
+                }
+                Entry::Edge(index) => {
+                    let edge = &edges[index];
+                    let n = edge.count as f32;
+                    let line = Primitive::Line {
+                        from: edge.from.map(|v| v / n),
+                        to: edge.to.map(|v| v / n),
+                    };
+                    // Wider for every merged line, up to four times as wide
+                    let width = edge.style.stroke_width * n.sqrt().min(4.0);
+                    Node::shape(line, Style { stroke_width: width, ..edge.style })
+                }
+            };
+            flat.insert(Scene::ROOT, node);
+        }
+        (flat, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::TEXT_COLOR;
+
+    #[test]
+    fn the_scale_only_follows_zoom_outside_the_band() {
+        let mut lod = Lod::default();
+        assert!(lod.update(100.0));
+        assert!(!lod.update(120.0));
+        assert!(!lod.update(81.0));
+        assert!(lod.update(130.0));
+        assert_eq!(lod.scale(), Some(130.0));
+        // Back to where it started is still inside the new band
+        assert!(!lod.update(105.0));
+    }
+
+    #[test]
+    fn small_labels_hide_and_tiny_nodes_become_points() {
+        let mut scene = Scene::default();
+        scene.add(Primitive::Circle { center: [0.5, 0.0], radius: 0.04 }, Style::fill(TEXT_COLOR));
+        scene.add(Primitive::Text { text: "CART".to_string(), origin: [0.0, 0.0], size: 0.04 }, Style::fill(TEXT_COLOR));
+        let mut lod = Lod::default();
+
+        lod.update(500.0);
+        let (flat, stats) = lod.simplify(&scene);
+        assert_eq!((flat.len(), stats), (2, LodStats::default()));
+
+        lod.update(50.0);
+        let (flat, stats) = lod.simplify(&scene);
+        assert_eq!((stats.hidden_labels, stats.points), (1, 1));
+        let items = flat.draw_list();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].primitive, &Primitive::Rect { origin: [0.46, -0.04], size: [0.08, 0.08] });
+    }
+
+    #[test]
+    fn lines_sharing_end_cells_merge() {
+        let mut scene = Scene::default();
+        let style = Style::stroke(TEXT_COLOR, 0.01);
+        for (from, to) in [([0.0, 0.0], [1.0, 0.0]), ([1.001, 0.0], [0.001, 0.0]), ([0.0, 0.0], [0.0, 1.0])] {
+            scene.add(Primitive::Line { from, to }, style);
+        }
+        scene.add(Primitive::Line { from: [0.5, 0.5], to: [0.501, 0.5] }, style);
+        let mut lod = Lod::default();
+        lod.update(100.0);
+
+        let (flat, stats) = lod.simplify(&scene);
+        assert_eq!(stats.merged_edges, 2);
+        let items = flat.draw_list();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].primitive, &Primitive::Line { from: [0.0005, 0.0], to: [1.0005, 0.0] });
+        assert_eq!(items[0].style.stroke_width, 0.01 * 2f32.sqrt());
+    }
+}
@@ -0,0 +1,435 @@
+// The service map: every shown service at its layout position, with the
+// calls between them as edges and pinned services ringed.
+//
+// The automatic layout is a row that grows with the service count, so the
+// map scales layout space down until the row fits the canvas. Pins and
+// picking work in layout space through the same `MapView`, which only
+// depends on the service count and so stays put while a node is dragged.
+//
+// Expanded groups are drawn as padded hulls around their members. A
+// collapsed group is one larger node at its members' centroid, and edges to
+// any member are redrawn to that node, once per pair of nodes.
+//
+// Edges are bundled through the grouping hierarchy: an edge leaving an
+// expanded group passes its centroid, and edges between different groups,
+// or with no groups at all, pass the centroid of the whole map.
+//
+// Flying to a search result centres the camera on the node its service is
+// drawn as and outlines that node.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::bundle::{spline, straighten};
+use crate::filter::FilterMatch;
+use crate::frame::Rgba;
+use crate::geometry::{text_width, CHAR_HEIGHT};
+use crate::grouping::Group;
+use crate::labels::Labels;
+use crate::layout::{Pins, SERVICE_SPACING};
+use crate::scene::{Node, Primitive, Scene, Style, DIMMED_OPACITY};
+use crate::session::Camera;
+use crate::theme::Theme;
+use crate::ServiceNode;
+
+pub const NODE_COLOR: Rgba = [0.45, 0.5, 0.7, 1.0];
+pub const EDGE_COLOR: Rgba = [0.4, 0.4, 0.5, 1.0];
+pub const PIN_COLOR: Rgba = [1.0, 1.0, 1.0, 1.0];
+pub const HULL_COLOR: Rgba = [0.35, 0.4, 0.6, 1.0];
+
+pub const NODE_RADIUS: f32 = 0.04;
+/// Camera zoom when flying to a node.
+pub const FOCUS_ZOOM: f32 = 4.0;
+// Collapsed groups grow with their member count up to this radius
+const MAX_GROUP_RADIUS: f32 = 0.1;
+const HULL_PADDING: f32 = 0.03;
+const HULL_OPACITY: f32 = 0.25;
+// Widest the automatic row may be before the map scales it down
+const FIT_WIDTH: f32 = 1.8;
+// Extra distance around a node that still picks it
+const PICK_SLOP: f32 = 0.02;
+// Space between a highlighted node and its ring
+const HIGHLIGHT_GAP: f32 = 0.012;
+
+/// Scale between layout space and the map as drawn, before the camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapView {
+    pub scale: f32,
+}
+
+impl MapView {
+    pub fn new(service_count: usize) -> Self {
+        let width = service_count as f32 * SERVICE_SPACING;
+        Self { scale: if width > FIT_WIDTH { FIT_WIDTH / width } else { 1.0 } }
+    }
+
+    pub fn to_screen(&self, point: [f32; 2]) -> [f32; 2] {
+        [point[0] * self.scale, point[1] * self.scale]
+    }
+
+    pub fn to_layout(&self, point: [f32; 2]) -> [f32; 2] {
+        [point[0] / self.scale, point[1] / self.scale]
+    }
+}
+
+/// What `pick` found: a service, or the node of a collapsed group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum Picked {
+    Service(String),
+    Group(String),
+}
+
+enum Target<'a> {
+    Service(&'a ServiceNode),
+    Group(&'a Group),
+}
+
+// One node as drawn: a service, or a collapsed group
+struct Placed<'a> {
+    target: Target<'a>,
+    center: [f32; 2],
+    radius: f32,
+}
+
+// Where every node is drawn, and which node each service is part of
+struct Placement<'a> {
+    nodes: Vec<Placed<'a>>,
+    node_of: HashMap<&'a str, usize>,
+}
+
+fn place<'a>(services: &'a [ServiceNode], groups: &'a [Group]) -> Placement<'a> {
+    let view = MapView::new(services.len());
+    let center: HashMap<&str, [f32; 2]> = services
+        .iter()
+        .map(|node| (node.id.as_str(), view.to_screen([node.x, node.y])))
+        .collect();
+    let mut placement = Placement { nodes: Vec::new(), node_of: HashMap::new() };
+
+    for group in groups.iter().filter(|group| group.collapsed) {
+        let members: Vec<[f32; 2]> = group.members.iter().filter_map(|id| center.get(id.as_str()).copied()).collect();
+        let Some(centroid) = centroid(members.iter().copied()) else { continue };
+        let n = members.len() as f32;
+        for id in &group.members {
+            placement.node_of.insert(id.as_str(), placement.nodes.len());
+        }
+        let radius = (NODE_RADIUS * n.sqrt()).min(MAX_GROUP_RADIUS);
+        placement.nodes.push(Placed { target: Target::Group(group), center: centroid, radius });
+    }
+    for node in services {
+        if placement.node_of.contains_key(node.id.as_str()) {
+            continue;
+        }
+        placement.node_of.insert(node.id.as_str(), placement.nodes.len());
+        let center = center[node.id.as_str()];
+        placement.nodes.push(Placed { target: Target::Service(node), center, radius: NODE_RADIUS });
+    }
+    placement
+}
+
+fn label(text: String, center: [f32; 2], radius: f32) -> Primitive {
+    let size = CHAR_HEIGHT * 0.3;
+    let width = text_width(&text) * size / CHAR_HEIGHT;
+    Primitive::Text { text, origin: [center[0] - width / 2.0, center[1] - radius - size - 0.015], size }
+}
+
+/// Draw `services` at their positions with `edges` (caller, callee) between
+/// them, fading services the filter dims along with their edges, leaving
+/// out edges to services it hides, and ringing pinned ones. Expanded
+/// `groups` get a hull; collapsed ones are drawn as one node. Edges curve
+/// with `bundling` between 0 (straight) and 1.
+#[allow(clippy::too_many_arguments)]
+pub fn map_scene<'a>(
+    services: &[ServiceNode],
+    edges: impl IntoIterator<Item = (&'a str, &'a str)>,
+    pins: &Pins,
+    filter: Option<&FilterMatch>,
+    groups: &[Group],
+    labels: &Labels,
+    theme: &Theme,
+    bundling: f32,
+) -> Scene {
+    let mut scene = Scene::new(theme.background);
+    let placement = place(services, groups);
+    let dims = |id: &str| filter.is_some_and(|filter| filter.dims(id));
+    let hides = |id: &str| filter.is_some_and(|filter| !filter.shows(id));
+    let dimmed = |placed: &Placed| match placed.target {
+        Target::Service(node) => dims(&node.id),
+        Target::Group(group) => group.members.iter().all(|id| dims(id)),
+    };
+    // Expanded group centroids, and which one each node sits in
+    let mut hub_of = HashMap::new();
+    let mut hubs = Vec::new();
+
+    for group in groups.iter().filter(|group| !group.collapsed) {
+        let outline: Vec<[f32; 2]> = group
+            .members
+            .iter()
+            .filter_map(|id| placement.node_of.get(id.as_str()))
+            .flat_map(|&index| {
+                let [x, y] = placement.nodes[index].center;
+                (0..8).map(move |i| {
+                    let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                    let reach = NODE_RADIUS + HULL_PADDING;
+                    [x + reach * angle.cos(), y + reach * angle.sin()]
+                })
+            })
+            .collect();
+        let members: Vec<usize> =
+            group.members.iter().filter_map(|id| placement.node_of.get(id.as_str()).copied()).collect();
+        if let Some(hub) = centroid(members.iter().map(|&index| placement.nodes[index].center)) {
+            hub_of.extend(members.iter().map(|&index| (index, hubs.len())));
+            hubs.push(hub);
+        }
+        let hull = convex_hull(outline);
+        let Some(top) = hull.iter().map(|p| p[1]).reduce(f32::max) else { continue };
+        let left = hull.iter().map(|p| p[0]).fold(f32::INFINITY, f32::min);
+
+        let style = Style::fill(theme.grid).with_stroke(theme.grid, 0.004).with_opacity(HULL_OPACITY);
+        scene.insert(Scene::ROOT, Node::shape(Primitive::Polyline { points: hull, closed: true }, style).z(-2));
+        let name = Primitive::Text { text: group.name.clone(), origin: [left, top + 0.01], size: CHAR_HEIGHT * 0.3 };
+        scene.insert(Scene::ROOT, Node::shape(name, Style::fill(theme.grid)).z(-2));
+    }
+
+    let root = centroid(placement.nodes.iter().map(|placed| placed.center)).unwrap_or_default();
+    let mut drawn = BTreeSet::new();
+    for (from, to) in edges {
+        if hides(from) || hides(to) {
+            continue;
+        }
+        let (Some(&from), Some(&to)) = (placement.node_of.get(from), placement.node_of.get(to)) else { continue };
+        if from == to || !drawn.insert((from.min(to), from.max(to))) {
+            continue;
+        }
+        let (start, end) = (placement.nodes[from].center, placement.nodes[to].center);
+        let edge = if bundling > 0.0 {
+            let (from_hub, to_hub) = (hub_of.get(&from), hub_of.get(&to));
+            let mut route = vec![start];
+            route.extend(from_hub.map(|&hub| hubs[hub]));
+            if from_hub.is_none() || from_hub != to_hub {
+                route.push(root);
+                route.extend(to_hub.map(|&hub| hubs[hub]));
+            }
+            route.push(end);
+            spline(&straighten(&route, bundling))
+        } else {
+            Primitive::Line { from: start, to: end }
+        };
+        let faded = dimmed(&placement.nodes[from]) || dimmed(&placement.nodes[to]);
+        let style = Style::stroke(theme.edge, 0.02).with_opacity(if faded { DIMMED_OPACITY } else { 1.0 });
+        scene.insert(Scene::ROOT, Node::shape(edge, style).z(-1));
+    }
+
+    for placed in &placement.nodes {
+        let (style, text) = match placed.target {
+            Target::Service(node) => {
+                let mut style = Style::fill(theme.node.of(&node.status));
+                if pins.contains_key(&node.id) {
+                    style = style.with_stroke(theme.pin, 0.008);
+                }
+                (style, labels.label(&node.id))
+            }
+            Target::Group(group) => {
+                let text = format!("{} {}", group.name, group.members.len());
+                (Style::fill(theme.node.of(&group.status)), text)
+            }
+        };
+        let opacity = if dimmed(placed) { DIMMED_OPACITY } else { 1.0 };
+        let circle = Primitive::Circle { center: placed.center, radius: placed.radius };
+        scene.add(circle, style.with_opacity(opacity));
+        scene.add(label(text, placed.center, placed.radius), Style::fill(theme.text).with_opacity(opacity));
+    }
+    scene
+}
+
+/// A camera centred on the node `service` is drawn as, which is its
+/// collapsed group's if it has one, zoomed in to `FOCUS_ZOOM`. `None` if
+/// the service is not among `services`.
+pub fn focus(services: &[ServiceNode], groups: &[Group], service: &str) -> Option<Camera> {
+    let placement = place(services, groups);
+    let placed = &placement.nodes[*placement.node_of.get(service)?];
+    Some(Camera { center: placed.center, zoom: FOCUS_ZOOM })
+}
+
+/// Ring the node `service` is drawn as on a `map_scene` of the same
+/// services and groups, captioned with what led there. Returns false if
+/// the service is not on the map.
+pub fn highlight(
+    scene: &mut Scene,
+    services: &[ServiceNode],
+    groups: &[Group],
+    service: &str,
+    caption: &str,
+    theme: &Theme,
+) -> bool {
+    let placement = place(services, groups);
+    let Some(&index) = placement.node_of.get(service) else { return false };
+    let Placed { center, radius, .. } = placement.nodes[index];
+    let ring = Primitive::Circle { center, radius: radius + HIGHLIGHT_GAP };
+    scene.insert(Scene::ROOT, Node::shape(ring, Style::stroke(theme.highlight, 0.006)).z(1));
+    if !caption.is_empty() {
+        let size = CHAR_HEIGHT * 0.3;
+        let width = text_width(caption) * size / CHAR_HEIGHT;
+        let origin = [center[0] - width / 2.0, center[1] + radius + HIGHLIGHT_GAP + 0.015];
+        let text = Primitive::Text { text: caption.to_string(), origin, size };
+        scene.insert(Scene::ROOT, Node::shape(text, Style::fill(theme.highlight)).z(1));
+    }
+    true
+}
+
+/// The service or collapsed group drawn nearest `point` (map space, before
+/// the camera), if `point` is on or just beside it.
+pub fn pick(services: &[ServiceNode], groups: &[Group], point: [f32; 2]) -> Option<Picked> {
+    place(services, groups)
+        .nodes
+        .iter()
+        .map(|placed| ((placed.center[0] - point[0]).hypot(placed.center[1] - point[1]) - placed.radius, placed))
+        .filter(|&(distance, _)| distance <= PICK_SLOP)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, placed)| match placed.target {
+            Target::Service(node) => Picked::Service(node.id.clone()),
+            Target::Group(group) => Picked::Group(group.name.clone()),
+        })
+}
+
+fn centroid(points: impl Iterator<Item = [f32; 2]>) -> Option<[f32; 2]> {
+    let (sum, n) = points.fold(([0.0, 0.0], 0), |(sum, n), p| ([sum[0] + p[0], sum[1] + p[1]], n + 1));
+    (n > 0).then(|| [sum[0] / n as f32, sum[1] / n as f32])
+}
+
+// Andrew's monotone chain; counter-clockwise, without repeating the first point
+fn convex_hull(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]);
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let floor = hull.len();
+        for point in pass {
+            while hull.len() >= floor + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{Counts, Metrics};
+    use crate::filter::FilterMode;
+    use crate::layout;
+    use crate::scene::ERROR_COLOR;
+
+    fn services(count: usize) -> Vec<ServiceNode> {
+        let mut services: Vec<ServiceNode> = (0..count)
+            .map(|i| ServiceNode { id: format!("svc-{}", i), x: 0.0, y: 0.0, status: "healthy".to_string() })
+            .collect();
+        layout::linear(&mut services);
+        services
+    }
+
+    #[test]
+    fn wide_rows_are_scaled_to_fit_and_picked_in_map_space() {
+        let services = services(12);
+        let view = MapView::new(services.len());
+        assert!((view.scale - 0.5).abs() < 1e-6);
+
+        let last = &services[11];
+        let on_screen = view.to_screen([last.x, last.y]);
+        assert!(on_screen[0] < 1.0);
+        let picked = pick(&services, &[], [on_screen[0] + 0.03, on_screen[1]]);
+        assert_eq!(picked, Some(Picked::Service("svc-11".to_string())));
+        assert!(pick(&services, &[], [on_screen[0], 0.5]).is_none());
+        assert_eq!(view.to_layout(on_screen), [last.x, last.y]);
+    }
+
+    #[test]
+    fn pinned_nodes_are_ringed_and_edges_join_known_services() {
+        let services = services(3);
+        let pins: Pins = [("svc-1".to_string(), [0.0, 0.5])].into();
+        let edges = [("svc-0", "svc-1"), ("svc-0", "gone")];
+        let scene = map_scene(&services, edges, &pins, None, &[], &Labels::default(), &Theme::default(), 0.0);
+
+        let draw_list = scene.draw_list();
+        let lines = draw_list.iter().filter(|item| matches!(item.primitive, Primitive::Line { .. })).count();
+        let ringed = draw_list.iter().filter(|item| item.style.stroke == Some(PIN_COLOR)).count();
+        assert_eq!((lines, ringed), (1, 1));
+        assert_eq!(scene.len(), 1 + 3 * 2);
+    }
+
+    #[test]
+    fn edges_fade_or_drop_with_their_filtered_services() {
+        let services = services(3);
+        let edges = [("svc-0", "svc-1"), ("svc-1", "svc-2")];
+        let matched = [("svc-0".to_string(), 1), ("svc-1".to_string(), 1)].into();
+        let mut filter = FilterMatch { services: matched, ..Default::default() };
+        let opacities = |filter: &FilterMatch| -> Vec<f32> {
+            let (labels, theme) = (Labels::default(), Theme::default());
+            let scene = map_scene(&services, edges, &Pins::new(), Some(filter), &[], &labels, &theme, 0.0);
+            let draw_list = scene.draw_list();
+            let lines = draw_list.iter().filter(|item| matches!(item.primitive, Primitive::Line { .. }));
+            lines.map(|item| item.style.opacity).collect()
+        };
+        assert_eq!(opacities(&filter), [1.0, DIMMED_OPACITY]);
+
+        filter.mode = FilterMode::Hide;
+        assert_eq!(opacities(&filter), [1.0]);
+    }
+
+    #[test]
+    fn focus_centres_the_camera_on_the_node_as_drawn() {
+        let services = services(12);
+        let camera = focus(&services, &[], "svc-11").unwrap();
+        let drawn_at = MapView::new(services.len()).to_screen([services[11].x, services[11].y]);
+        assert_eq!(camera, Camera { center: drawn_at, zoom: FOCUS_ZOOM });
+        assert_eq!(camera.transform().apply(drawn_at), [0.0, 0.0]);
+        assert!(focus(&services, &[], "gone").is_none());
+
+        // A collapsed member flies to its group's node, which gets the ring
+        let groups = [group(&["svc-1", "svc-2"], true)];
+        let camera = focus(&services, &groups, "svc-2").unwrap();
+        assert_eq!(pick(&services, &groups, camera.center), Some(Picked::Group("svc".to_string())));
+        let mut scene = Scene::new(Theme::default().background);
+        assert!(highlight(&mut scene, &services, &groups, "svc-2", "GET /cart", &Theme::default()));
+        assert_eq!(scene.len(), 2);
+        assert!(!highlight(&mut scene, &services, &groups, "gone", "", &Theme::default()));
+    }
+
+    fn group(members: &[&str], collapsed: bool) -> Group {
+        Group {
+            name: "svc".to_string(),
+            members: members.iter().map(|id| id.to_string()).collect(),
+            collapsed,
+            status: "error".to_string(),
+            metrics: Metrics::new(&Counts::default(), None),
+        }
+    }
+
+    #[test]
+    fn collapsed_groups_become_one_node_with_merged_edges() {
+        let services = services(4);
+        let edges = [("svc-0", "svc-1"), ("svc-0", "svc-2"), ("svc-1", "svc-2"), ("svc-2", "svc-3")];
+
+        // Expanded: a hull and its name behind all four services
+        let groups = [group(&["svc-1", "svc-2"], false)];
+        let expanded = map_scene(&services, edges, &Pins::new(), None, &groups, &Labels::default(), &Theme::default(), 0.0);
+        assert_eq!(expanded.len(), 2 + 4 + 4 * 2);
+
+        // Collapsed: the group's internal edge disappears and svc-0's two
+        // edges into it become one
+        let groups = [group(&["svc-1", "svc-2"], true)];
+        let collapsed = map_scene(&services, edges, &Pins::new(), None, &groups, &Labels::default(), &Theme::default(), 0.0);
+        assert_eq!(collapsed.len(), 2 + 3 * 2);
+        let red = collapsed.draw_list().iter().filter(|item| item.style.fill == Some(ERROR_COLOR)).count();
+        assert_eq!(red, 1);
+        assert_eq!(pick(&services, &groups, [-0.15, 0.0]), Some(Picked::Group("svc".to_string())));
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceNode {
+    pub id: String,
+    pub x: f32,
+    pub y: f32,
+    pub status: String, // "healthy", "warning", "error"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayData {
+    pub services: Vec<ServiceNode>,
+    pub all_service_ids: Vec<String>, // All unique service IDs from Arrow file
+    pub timestamp: String,
+    pub job_id: String,
+}
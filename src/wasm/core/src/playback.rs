@@ -0,0 +1,93 @@
+// The playback clock: which scene is on screen at a given playback time.
+//
+// Playback rotates through the active services, holding each one for
+// `SERVICE_DWELL_MS`. Live rendering and recordings both sample this clock,
+// so a recording shows exactly what a viewer would have seen. Callers pass
+// the services left after filtering together with the filter match, which
+// dims the services it does not hide.
+
+use crate::filter::FilterMatch;
+use crate::labels::Labels;
+use crate::scene::{filtered_service_scene, Scene};
+use crate::theme::Theme;
+use crate::ServiceNode;
+
+pub const SERVICE_DWELL_MS: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackClock {
+    pub dwell_ms: f64,
+}
+
+impl Default for PlaybackClock {
+    fn default() -> Self {
+        Self { dwell_ms: SERVICE_DWELL_MS }
+    }
+}
+
+impl PlaybackClock {
+    /// Index of the service shown at `time_ms`, wrapping after the last one.
+    pub fn service_index(&self, time_ms: f64, service_count: usize) -> Option<usize> {
+        if service_count == 0 {
+            return None;
+        }
+        let step = (time_ms.max(0.0) / self.dwell_ms).floor() as usize;
+        Some(step % service_count)
+    }
+
+    /// Time for one full rotation through `service_count` services.
+    pub fn cycle_ms(&self, service_count: usize) -> f64 {
+        self.dwell_ms * service_count as f64
+    }
+
+    pub fn scene_at(
+        &self,
+        services: &[ServiceNode],
+        time_ms: f64,
+        filter: Option<&FilterMatch>,
+        labels: &Labels,
+        theme: &Theme,
+    ) -> Scene {
+        match self.service_index(time_ms, services.len()) {
+            Some(index) => filtered_service_scene(&services[index], filter, labels, theme),
+            None => Scene::new(theme.background),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn services_rotate_every_dwell_period() {
+        let clock = PlaybackClock { dwell_ms: 100.0 };
+        let shown: Vec<_> = [0.0, 99.9, 100.0, 250.0, 300.0, -5.0]
+            .iter()
+            .map(|&t| clock.service_index(t, 3))
+            .collect();
+
+        assert_eq!(shown, [Some(0), Some(0), Some(1), Some(2), Some(0), Some(0)]);
+        assert_eq!(clock.service_index(0.0, 0), None);
+        assert_eq!(clock.cycle_ms(3), 300.0);
+    }
+
+    #[test]
+    fn filtered_out_services_are_dimmed() {
+        let service = |id: &str| ServiceNode { id: id.to_string(), x: 0.0, y: 0.0, status: "healthy".to_string() };
+        let services = [service("cart"), service("auth")];
+        let filter = FilterMatch {
+            services: [("cart".to_string(), 1)].into(),
+            spans: 1,
+            ..Default::default()
+        };
+        let clock = PlaybackClock { dwell_ms: 100.0 };
+        let opacity = |time_ms| {
+            let scene = clock.scene_at(&services, time_ms, Some(&filter), &Labels::default(), &Theme::default());
+            scene.draw_list()[0].style.opacity
+        };
+
+        assert_eq!(opacity(0.0), 1.0);
+        assert_eq!(opacity(100.0), crate::scene::DIMMED_OPACITY);
+    }
+}
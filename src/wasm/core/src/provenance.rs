@@ -0,0 +1,112 @@
+// Source-file provenance for ingested chunks.
+//
+// The viewer ingests many replay files, usually keyed by their S3 object key.
+// The registry remembers which version of each file was ingested and which
+// record batches and jobs it fed, so an unchanged file can be skipped and a
+// replaced file can be withdrawn before its new version is merged.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+/// Compact handle for a registered source, stored on every span it delivered.
+pub type SourceId = u32;
+
+/// One source batch that delivered a span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    pub source: SourceId,
+    pub batch: usize,
+}
+
+/// What a single record batch of a source contained.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProvenance {
+    pub index: usize,
+    pub rows: usize,
+    pub jobs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceEntry {
+    pub id: SourceId,
+    pub last_modified: Option<String>,
+    pub ingested_at: String,
+    pub batches: Vec<BatchProvenance>,
+    pub jobs: BTreeSet<String>,
+}
+
+/// Summary row returned by `list_sources`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSummary {
+    pub key: String,
+    pub last_modified: Option<String>,
+    pub ingested_at: String,
+    pub jobs: Vec<String>,
+    pub batches: Vec<BatchProvenance>,
+    pub span_count: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct SourceRegistry {
+    next_id: SourceId,
+    sources: BTreeMap<String, SourceEntry>,
+}
+
+impl SourceRegistry {
+    /// True when `key` was already ingested at exactly this `last_modified`.
+    /// Sources without a last-modified value are never considered current.
+    pub fn is_current(&self, key: &str, last_modified: Option<&str>) -> bool {
+        match (self.sources.get(key), last_modified) {
+            (Some(entry), Some(last_modified)) => {
+                entry.last_modified.as_deref() == Some(last_modified)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &SourceEntry)> {
+        self.sources.iter()
+    }
+
+    /// Register a new version of `key`. The caller removes any previous version first.
+    pub fn register(
+        &mut self,
+        key: &str,
+        last_modified: Option<String>,
+        ingested_at: &str,
+        batches: Vec<BatchProvenance>,
+    ) -> SourceId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        let jobs = batches
+            .iter()
+            .flat_map(|batch| batch.jobs.iter().cloned())
+            .collect();
+        self.sources.insert(
+            key.to_string(),
+            SourceEntry {
+                id,
+                last_modified,
+                ingested_at: ingested_at.to_string(),
+                batches,
+                jobs,
+            },
+        );
+        id
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<SourceEntry> {
+        self.sources.remove(key)
+    }
+
+    /// Drop `job` from every source after that job was cleared; sources that
+    /// no longer feed any job are forgotten.
+    pub fn forget_job(&mut self, job: &str) {
+        self.sources.retain(|_, entry| {
+            let fed_job = entry.jobs.remove(job);
+            !(fed_job && entry.jobs.is_empty())
+        });
+    }
+}
@@ -0,0 +1,280 @@
+// CPU reference rasterizer. Draws a `Frame` the way the wgpu pipeline does:
+// the top-left fill rule, colors interpolated across each triangle, alpha
+// blended in linear space and encoded to sRGB on store to match the sRGB
+// surface `init_webgpu` prefers. It takes one sample at each pixel centre
+// where the GPU multisamples, so goldens stay exact across adapters.
+
+use crate::frame::{encode_srgb, to_unorm, Frame, Rgba, Vertex};
+
+/// An 8-bit RGBA image, row-major with the top row first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4, "pixel buffer does not match {}x{}", width, height);
+        Self { width, height, pixels }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+}
+
+pub fn rasterize(frame: &Frame, width: u32, height: u32) -> Image {
+    let mut target = vec![frame.clear; width as usize * height as usize];
+
+    for triangle in frame.vertices.chunks_exact(3) {
+        draw_triangle(&mut target, width, height, triangle);
+    }
+
+    let pixels = target
+        .iter()
+        .flat_map(|&[r, g, b, a]| [encode_srgb(r), encode_srgb(g), encode_srgb(b), to_unorm(a)])
+        .collect();
+    Image::new(width, height, pixels)
+}
+
+// Clip space to pixel space, y pointing down like the framebuffer
+fn to_pixels(vertex: &Vertex, width: u32, height: u32) -> [f32; 2] {
+    [
+        (vertex.position[0] + 1.0) * 0.5 * width as f32,
+        (1.0 - vertex.position[1]) * 0.5 * height as f32,
+    ]
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// With positive winding (clockwise on screen) a top edge runs left to right
+// and a left edge runs upwards; only those own the pixels exactly on them
+fn is_top_left(a: [f32; 2], b: [f32; 2]) -> bool {
+    (a[1] == b[1] && b[0] > a[0]) || b[1] < a[1]
+}
+
+fn draw_triangle(target: &mut [Rgba], width: u32, height: u32, triangle: &[Vertex]) {
+    let mut v = [triangle[0], triangle[1], triangle[2]];
+    let mut p = v.map(|vertex| to_pixels(&vertex, width, height));
+
+    let mut area = edge(p[0], p[1], p[2]);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    if area < 0.0 {
+        v.swap(1, 2);
+        p.swap(1, 2);
+        area = -area;
+    }
+
+    let min_x = p.iter().map(|q| q[0]).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let min_y = p.iter().map(|q| q[1]).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_x = (p.iter().map(|q| q[0]).fold(f32::MIN, f32::max).ceil().max(0.0) as u32).min(width);
+    let max_y = (p.iter().map(|q| q[1]).fold(f32::MIN, f32::max).ceil().max(0.0) as u32).min(height);
+
+    let edges = [(1, 2), (2, 0), (0, 1)];
+    let owns_edge = edges.map(|(a, b)| is_top_left(p[a], p[b]));
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let sample = [x as f32 + 0.5, y as f32 + 0.5];
+            let w = edges.map(|(a, b)| edge(p[a], p[b], sample));
+            let inside = (0..3).all(|i| w[i] > 0.0 || (w[i] == 0.0 && owns_edge[i]));
+            if !inside {
+                continue;
+            }
+
+            let mut color = [0.0; 4];
+            for (i, weight) in w.iter().enumerate() {
+                for (c, channel) in color.iter_mut().enumerate() {
+                    *channel += v[i].color[c] * weight / area;
+                }
+            }
+
+            let dst = &mut target[y as usize * width as usize + x as usize];
+            *dst = blend(color, *dst);
+        }
+    }
+}
+
+// BlendState::ALPHA_BLENDING: src * a + dst * (1 - a), alpha likewise
+fn blend(src: Rgba, dst: Rgba) -> Rgba {
+    let a = src[3].clamp(0.0, 1.0);
+    let mut out = [0.0; 4];
+    for c in 0..4 {
+        out[c] = src[c] * a + dst[c] * (1.0 - a);
+    }
+    out
+}
+
+/// How far a rendered image may drift from its golden before a test fails.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffTolerance {
+    /// Perceptual color distance, 0 to 1, below which pixels count as equal.
+    pub threshold: f32,
+    /// Fraction of pixels allowed to exceed `threshold`.
+    pub max_mismatched: f32,
+}
+
+impl Default for DiffTolerance {
+    fn default() -> Self {
+        // Absorbs edge-rule and rounding differences between rasterizers
+        // while still catching a moved, missing or recolored glyph segment
+        Self {
+            threshold: 0.1,
+            max_mismatched: 0.002,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffReport {
+    pub mismatched: usize,
+    pub total: usize,
+    pub max_distance: f32,
+    /// Mismatched pixels in red over a faded copy of the expected image.
+    pub diff: Image,
+}
+
+impl DiffReport {
+    pub fn mismatched_fraction(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.mismatched as f32 / self.total as f32
+        }
+    }
+
+    pub fn within(&self, tolerance: &DiffTolerance) -> bool {
+        self.mismatched_fraction() <= tolerance.max_mismatched
+    }
+}
+
+/// Compare two images pixel by pixel in YIQ space, which weighs luma over
+/// chroma roughly the way the eye does. Images of different sizes compare
+/// as entirely mismatched.
+pub fn diff(expected: &Image, actual: &Image, tolerance: &DiffTolerance) -> DiffReport {
+    let total = expected.width as usize * expected.height as usize;
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        let red = [255, 0, 0, 255].repeat(total);
+        return DiffReport {
+            mismatched: total.max(1),
+            total: total.max(1),
+            max_distance: 1.0,
+            diff: Image::new(expected.width, expected.height, red),
+        };
+    }
+
+    let mut mismatched = 0;
+    let mut max_distance: f32 = 0.0;
+    let mut pixels = Vec::with_capacity(total * 4);
+
+    for (a, b) in expected.pixels.chunks_exact(4).zip(actual.pixels.chunks_exact(4)) {
+        let distance = perceptual_distance(a, b);
+        max_distance = max_distance.max(distance);
+        if distance > tolerance.threshold {
+            mismatched += 1;
+            pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = (luma(a) * 0.25 + 191.0) as u8;
+            pixels.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    DiffReport {
+        mismatched,
+        total,
+        max_distance,
+        diff: Image::new(expected.width, expected.height, pixels),
+    }
+}
+
+// Colors composited over white so transparent pixels compare sensibly
+fn over_white(pixel: &[u8]) -> [f32; 3] {
+    let a = pixel[3] as f32 / 255.0;
+    [0, 1, 2].map(|c| 255.0 + (pixel[c] as f32 - 255.0) * a)
+}
+
+fn luma(pixel: &[u8]) -> f32 {
+    let [r, g, b] = over_white(pixel);
+    0.298_895_3 * r + 0.586_622_5 * g + 0.114_482_3 * b
+}
+
+// Weighted YIQ distance normalized so black vs white is 1.0
+fn perceptual_distance(a: &[u8], b: &[u8]) -> f32 {
+    const MAX_DELTA: f32 = 35_215.0;
+    let [r1, g1, b1] = over_white(a);
+    let [r2, g2, b2] = over_white(b);
+    let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+
+    let y = 0.298_895_3 * dr + 0.586_622_5 * dg + 0.114_482_3 * db;
+    let i = 0.595_978 * dr - 0.274_176_1 * dg - 0.321_801_9 * db;
+    let q = 0.211_470_2 * dr - 0.522_617_2 * dg + 0.311_147 * db;
+
+    ((0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_DELTA).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(frame: &mut Frame, x0: f32, y0: f32, x1: f32, y1: f32, color: Rgba) {
+        frame.push_triangles(&[x0, y0, x1, y0, x0, y1, x1, y0, x1, y1, x0, y1], color);
+    }
+
+    fn lit(image: &Image) -> usize {
+        image.pixels.chunks_exact(4).filter(|p| p[0] > 0).count()
+    }
+
+    #[test]
+    fn full_screen_quad_covers_every_pixel_once() {
+        let mut frame = Frame::new([0.0, 0.0, 0.0, 1.0]);
+        quad(&mut frame, -1.0, -1.0, 1.0, 1.0, [1.0, 1.0, 1.0, 0.5]);
+        let image = rasterize(&frame, 8, 8);
+
+        // The shared diagonal must not be blended twice
+        let expected = encode_srgb(0.5);
+        assert!(image.pixels.chunks_exact(4).all(|p| p[0] == expected));
+    }
+
+    #[test]
+    fn adjacent_quads_do_not_overlap_or_leave_gaps() {
+        let mut frame = Frame::new([0.0, 0.0, 0.0, 1.0]);
+        quad(&mut frame, -1.0, -1.0, 0.0, 1.0, [1.0, 0.0, 0.0, 1.0]);
+        quad(&mut frame, 0.0, -1.0, 1.0, 1.0, [1.0, 0.0, 0.0, 1.0]);
+        let image = rasterize(&frame, 10, 6);
+
+        assert_eq!(lit(&image), 60);
+    }
+
+    #[test]
+    fn clip_space_y_points_up() {
+        let mut frame = Frame::new([0.0, 0.0, 0.0, 1.0]);
+        quad(&mut frame, -1.0, 0.0, 1.0, 1.0, [1.0, 1.0, 1.0, 1.0]);
+        let image = rasterize(&frame, 4, 4);
+
+        assert_eq!(image.pixel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(image.pixel(0, 3), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn diff_tolerates_small_color_drift_but_not_missing_shapes() {
+        let mut frame = Frame::new([0.0, 0.0, 0.0, 1.0]);
+        quad(&mut frame, -0.5, -0.5, 0.5, 0.5, [1.0, 0.8, 0.2, 1.0]);
+        let expected = rasterize(&frame, 16, 16);
+
+        let mut drifted = expected.clone();
+        drifted.pixels.iter_mut().step_by(4).for_each(|r| *r = r.saturating_sub(3));
+        assert!(diff(&expected, &drifted, &DiffTolerance::default()).within(&DiffTolerance::default()));
+
+        let blank = rasterize(&Frame::new([0.0, 0.0, 0.0, 1.0]), 16, 16);
+        let report = diff(&expected, &blank, &DiffTolerance::default());
+        assert_eq!(report.mismatched, 64);
+        assert!(!report.within(&DiffTolerance::default()));
+    }
+}
@@ -0,0 +1,397 @@
+// Recording playback to animation frames.
+//
+// `plan` samples the playback clock at a fixed frame rate over a time range
+// and tessellates each sample. Consecutive identical frames merge into one
+// longer frame, so a 10 second dwell on one service renders once instead of
+// once per tick. Each planned frame is then rendered (offscreen on the GPU,
+// or by the CPU rasterizer) and handed to a `FrameEncoder`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::filter::FilterMatch;
+use crate::frame::Frame;
+use crate::labels::Labels;
+use crate::playback::PlaybackClock;
+use crate::raster::Image;
+use crate::tessellate::tessellate;
+use crate::theme::Theme;
+use crate::ServiceNode;
+
+/// Upper bound on frames sampled for one recording.
+pub const MAX_RECORDING_FRAMES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    /// Animated PNG, lossless.
+    #[default]
+    Apng,
+    /// GIF with a per-frame quantized palette.
+    Gif,
+    /// No container: raw RGBA frames are handed to the caller, e.g. for WebCodecs.
+    Rgba,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RecordingOptions {
+    pub start_ms: f64,
+    /// Defaults to one full rotation through the services.
+    pub end_ms: Option<f64>,
+    pub fps: f64,
+    /// Default to the canvas size.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub format: RecordingFormat,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            start_ms: 0.0,
+            end_ms: None,
+            fps: 10.0,
+            width: None,
+            height: None,
+            format: RecordingFormat::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordingError {
+    InvalidRange { start_ms: f64, end_ms: f64 },
+    InvalidFps(f64),
+    TooManyFrames { frames: usize, max: usize },
+    FrameSize { expected: (u32, u32), actual: (u32, u32) },
+    Encode(String),
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingError::InvalidRange { start_ms, end_ms } => {
+                write!(f, "Recording range {}ms..{}ms is empty", start_ms, end_ms)
+            }
+            RecordingError::InvalidFps(fps) => write!(f, "Frame rate {} must be positive", fps),
+            RecordingError::TooManyFrames { frames, max } => {
+                write!(f, "Recording needs {} frames, more than the {} allowed", frames, max)
+            }
+            RecordingError::FrameSize { expected, actual } => write!(
+                f,
+                "Frame is {}x{}, recording is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            RecordingError::Encode(message) => write!(f, "Encoding failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// One distinct frame of a recording and how long it stays on screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedFrame {
+    pub time_ms: f64,
+    pub duration_ms: f64,
+    /// Number of clock ticks merged into this frame.
+    pub ticks: usize,
+    pub frame: Frame,
+}
+
+/// Reported to the caller after each frame is rendered.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingProgress {
+    pub frame: usize,
+    pub frames: usize,
+    pub time_ms: f64,
+    pub duration_ms: f64,
+}
+
+impl PlannedFrame {
+    pub fn progress(&self, index: usize, frames: usize) -> RecordingProgress {
+        RecordingProgress {
+            frame: index + 1,
+            frames,
+            time_ms: self.time_ms,
+            duration_ms: self.duration_ms,
+        }
+    }
+}
+
+pub fn plan(
+    clock: &PlaybackClock,
+    services: &[ServiceNode],
+    filter: Option<&FilterMatch>,
+    labels: &Labels,
+    theme: &Theme,
+    options: &RecordingOptions,
+) -> Result<Vec<PlannedFrame>, RecordingError> {
+    let start_ms = options.start_ms;
+    let end_ms = options.end_ms.unwrap_or(start_ms + clock.cycle_ms(services.len().max(1)));
+    if !start_ms.is_finite() || !end_ms.is_finite() || end_ms <= start_ms {
+        return Err(RecordingError::InvalidRange { start_ms, end_ms });
+    }
+    if !options.fps.is_finite() || options.fps <= 0.0 {
+        return Err(RecordingError::InvalidFps(options.fps));
+    }
+
+    let tick_ms = 1000.0 / options.fps;
+    let ticks = ((end_ms - start_ms) / tick_ms).ceil() as usize;
+    if ticks > MAX_RECORDING_FRAMES {
+        return Err(RecordingError::TooManyFrames { frames: ticks, max: MAX_RECORDING_FRAMES });
+    }
+
+    let mut planned: Vec<PlannedFrame> = Vec::new();
+    for tick in 0..ticks {
+        let time_ms = start_ms + tick as f64 * tick_ms;
+        let duration_ms = tick_ms.min(end_ms - time_ms);
+        let frame = tessellate(&clock.scene_at(services, time_ms, filter, labels, theme));
+        match planned.last_mut() {
+            Some(last) if last.frame == frame => {
+                last.duration_ms += duration_ms;
+                last.ticks += 1;
+            }
+            _ => planned.push(PlannedFrame { time_ms, duration_ms, ticks: 1, frame }),
+        }
+    }
+    Ok(planned)
+}
+
+/// Accepts rendered frames in order and produces the encoded file.
+pub trait FrameEncoder {
+    fn add(&mut self, image: &Image, duration_ms: f64) -> Result<(), RecordingError>;
+    fn finish(self: Box<Self>) -> Result<Vec<u8>, RecordingError>;
+}
+
+/// An encoder for `format`, or `None` for raw RGBA output.
+pub fn encoder(
+    format: RecordingFormat,
+    width: u32,
+    height: u32,
+    frame_count: usize,
+) -> Result<Option<Box<dyn FrameEncoder>>, RecordingError> {
+    Ok(match format {
+        RecordingFormat::Apng => Some(Box::new(ApngEncoder::new(width, height, frame_count)?)),
+        RecordingFormat::Gif => Some(Box::new(GifEncoder::new(width, height)?)),
+        RecordingFormat::Rgba => None,
+    })
+}
+
+fn encode_error(error: impl fmt::Display) -> RecordingError {
+    RecordingError::Encode(error.to_string())
+}
+
+fn check_frame(image: &Image, width: u32, height: u32) -> Result<(), RecordingError> {
+    if (image.width, image.height) != (width, height) {
+        return Err(RecordingError::FrameSize {
+            expected: (width, height),
+            actual: (image.width, image.height),
+        });
+    }
+    Ok(())
+}
+
+// The png and gif writers own their output; sharing the Vec lets us take
+// the bytes back once they are done with it
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut self.0.borrow_mut())
+    }
+}
+
+struct ApngEncoder {
+    output: SharedBuffer,
+    writer: png::Writer<SharedBuffer>,
+    width: u32,
+    height: u32,
+}
+
+impl ApngEncoder {
+    fn new(width: u32, height: u32, frame_count: usize) -> Result<Self, RecordingError> {
+        let output = SharedBuffer::default();
+        let mut encoder = png::Encoder::new(output.clone(), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        // Zero plays loops forever
+        encoder.set_animated(frame_count.max(1) as u32, 0).map_err(encode_error)?;
+        let writer = encoder.write_header().map_err(encode_error)?;
+        Ok(Self { output, writer, width, height })
+    }
+}
+
+impl FrameEncoder for ApngEncoder {
+    fn add(&mut self, image: &Image, duration_ms: f64) -> Result<(), RecordingError> {
+        check_frame(image, self.width, self.height)?;
+        let delay_ms = duration_ms.round().clamp(1.0, u16::MAX as f64) as u16;
+        self.writer.set_frame_delay(delay_ms, 1000).map_err(encode_error)?;
+        self.writer.write_image_data(&image.pixels).map_err(encode_error)
+    }
+
+    fn finish(self: Box<Self>) -> Result<Vec<u8>, RecordingError> {
+        self.writer.finish().map_err(encode_error)?;
+        Ok(self.output.take())
+    }
+}
+
+struct GifEncoder {
+    encoder: gif::Encoder<SharedBuffer>,
+    output: SharedBuffer,
+    width: u32,
+    height: u32,
+}
+
+impl GifEncoder {
+    // NeuQuant sampling factor: 1 is best quality, 30 fastest
+    const QUANTIZE_SPEED: i32 = 10;
+
+    fn new(width: u32, height: u32) -> Result<Self, RecordingError> {
+        let too_large = || RecordingError::Encode(format!("GIF frames are limited to 65535 pixels per side, got {}x{}", width, height));
+        let (w, h) = (u16::try_from(width).map_err(|_| too_large())?, u16::try_from(height).map_err(|_| too_large())?);
+        let output = SharedBuffer::default();
+        let mut encoder = gif::Encoder::new(output.clone(), w, h, &[]).map_err(encode_error)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(encode_error)?;
+        Ok(Self { encoder, output, width, height })
+    }
+}
+
+impl FrameEncoder for GifEncoder {
+    fn add(&mut self, image: &Image, duration_ms: f64) -> Result<(), RecordingError> {
+        check_frame(image, self.width, self.height)?;
+        let mut pixels = image.pixels.clone();
+        let mut frame = gif::Frame::from_rgba_speed(self.width as u16, self.height as u16, &mut pixels, Self::QUANTIZE_SPEED);
+        // GIF delays are in hundredths of a second
+        frame.delay = (duration_ms / 10.0).round().clamp(1.0, u16::MAX as f64) as u16;
+        self.encoder.write_frame(&frame).map_err(encode_error)
+    }
+
+    fn finish(self: Box<Self>) -> Result<Vec<u8>, RecordingError> {
+        self.encoder.into_inner().map_err(encode_error)?;
+        Ok(self.output.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raster::rasterize;
+
+    fn services(ids: &[&str]) -> Vec<ServiceNode> {
+        ids.iter()
+            .map(|id| ServiceNode {
+                id: id.to_string(),
+                x: 0.0,
+                y: 0.0,
+                status: "active".to_string(),
+            })
+            .collect()
+    }
+
+    fn options(end_ms: f64, fps: f64, format: RecordingFormat) -> RecordingOptions {
+        RecordingOptions {
+            end_ms: Some(end_ms),
+            fps,
+            format,
+            ..RecordingOptions::default()
+        }
+    }
+
+    fn record(planned: &[PlannedFrame], format: RecordingFormat) -> Vec<u8> {
+        let mut encoder = encoder(format, 32, 16, planned.len()).unwrap().unwrap();
+        for frame in planned {
+            encoder.add(&rasterize(&frame.frame, 32, 16), frame.duration_ms).unwrap();
+        }
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn identical_ticks_merge_into_one_frame() {
+        let clock = PlaybackClock { dwell_ms: 1000.0 };
+        let labels = Labels::default();
+        let planned = plan(&clock, &services(&["a", "b"]), None, &labels, &Theme::default(), &options(2500.0, 4.0, RecordingFormat::Apng)).unwrap();
+
+        let summary: Vec<_> = planned.iter().map(|f| (f.time_ms, f.duration_ms, f.ticks)).collect();
+        assert_eq!(summary, [(0.0, 1000.0, 4), (1000.0, 1000.0, 4), (2000.0, 500.0, 2)]);
+        assert_eq!(planned[0].frame, planned[2].frame);
+    }
+
+    #[test]
+    fn default_range_is_one_rotation() {
+        let clock = PlaybackClock { dwell_ms: 1000.0 };
+        let labels = Labels::default();
+        let planned = plan(&clock, &services(&["a", "b", "c"]), None, &labels, &Theme::default(), &RecordingOptions::default()).unwrap();
+
+        assert_eq!(planned.len(), 3);
+        assert_eq!(planned.iter().map(|f| f.ticks).sum::<usize>(), 30);
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        let clock = PlaybackClock::default();
+        let labels = Labels::default();
+        let svc = services(&["a"]);
+
+        assert!(matches!(plan(&clock, &svc, None, &labels, &Theme::default(), &options(0.0, 10.0, RecordingFormat::Gif)), Err(RecordingError::InvalidRange { .. })));
+        assert!(matches!(plan(&clock, &svc, None, &labels, &Theme::default(), &options(1000.0, 0.0, RecordingFormat::Gif)), Err(RecordingError::InvalidFps(_))));
+        assert!(matches!(
+            plan(&clock, &svc, None, &labels, &Theme::default(), &options(3_600_000.0, 30.0, RecordingFormat::Gif)),
+            Err(RecordingError::TooManyFrames { .. })
+        ));
+    }
+
+    #[test]
+    fn apng_declares_every_planned_frame() {
+        let clock = PlaybackClock { dwell_ms: 1000.0 };
+        let labels = Labels::default();
+        let planned = plan(&clock, &services(&["a", "b"]), None, &labels, &Theme::default(), &options(2000.0, 5.0, RecordingFormat::Apng)).unwrap();
+        let bytes = record(&planned, RecordingFormat::Apng);
+
+        let reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 2);
+        assert_eq!(control.num_plays, 0);
+    }
+
+    #[test]
+    fn gif_has_one_image_per_planned_frame() {
+        let clock = PlaybackClock { dwell_ms: 1000.0 };
+        let labels = Labels::default();
+        let planned = plan(&clock, &services(&["a", "b", "c"]), None, &labels, &Theme::default(), &options(3000.0, 5.0, RecordingFormat::Gif)).unwrap();
+        let bytes = record(&planned, RecordingFormat::Gif);
+
+        let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, [100, 100, 100]);
+    }
+
+    #[test]
+    fn frames_must_match_the_recording_size() {
+        let mut gif = encoder(RecordingFormat::Gif, 32, 16, 1).unwrap().unwrap();
+        let wrong = rasterize(&Frame::new([0.0; 4]), 16, 16);
+        assert!(matches!(gif.add(&wrong, 100.0), Err(RecordingError::FrameSize { .. })));
+        assert!(encoder(RecordingFormat::Rgba, 32, 16, 1).unwrap().is_none());
+    }
+}
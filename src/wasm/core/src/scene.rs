@@ -0,0 +1,485 @@
+// Retained scene graph of typed primitives.
+//
+// Views build a `Scene`; backends consume it. `tessellate` lowers it to a
+// `Frame` for wgpu and the CPU rasterizer, and other exporters can walk the
+// same tree. Nodes live in an arena and are addressed by `NodeId`. Siblings
+// draw in ascending `z`, ties in insertion order, and every child draws
+// above its parent.
+
+use crate::filter::FilterMatch;
+use crate::frame::Rgba;
+use crate::geometry::{text_width, CHAR_HEIGHT};
+use crate::labels::Labels;
+use crate::theme::Theme;
+use crate::ServiceNode;
+
+pub const BACKGROUND: Rgba = [0.0, 0.0, 0.0, 1.0];
+pub const TEXT_COLOR: Rgba = [1.0, 0.8, 0.2, 1.0];
+/// Opacity of services a filter dims.
+pub const DIMMED_OPACITY: f32 = 0.25;
+pub const HIGHLIGHT_COLOR: Rgba = [0.25, 0.6, 1.0, 1.0];
+pub const WARNING_COLOR: Rgba = [1.0, 0.6, 0.1, 1.0];
+pub const ERROR_COLOR: Rgba = [1.0, 0.25, 0.25, 1.0];
+
+pub type NodeId = usize;
+
+/// 2D affine transform `[a, b, c, d, e, f]`, mapping (x, y) to
+/// (a*x + c*y + e, b*x + d*y + f) like SVG's `matrix()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform(pub [f32; 6]);
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        Self([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Self {
+        Self([sx, 0.0, 0.0, sy, 0.0, 0.0])
+    }
+
+    /// Counter-clockwise rotation in radians.
+    pub fn rotate(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &Transform) -> Self {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = next.0;
+        Self([
+            a2 * a1 + c2 * b1,
+            b2 * a1 + d2 * b1,
+            a2 * c1 + c2 * d1,
+            b2 * c1 + d2 * d1,
+            a2 * e1 + c2 * f1 + e2,
+            b2 * e1 + d2 * f1 + f2,
+        ])
+    }
+
+    pub fn apply(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [a, b, c, d, e, f] = self.0;
+        [a * x + c * y + e, b * x + d * y + f]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub fill: Option<Rgba>,
+    pub stroke: Option<Rgba>,
+    pub stroke_width: f32,
+    /// Multiplies the alpha of this node and everything below it.
+    pub opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: None,
+            stroke: None,
+            stroke_width: 0.01,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn fill(color: Rgba) -> Self {
+        Self {
+            fill: Some(color),
+            ..Self::default()
+        }
+    }
+
+    pub fn stroke(color: Rgba, width: f32) -> Self {
+        Self {
+            stroke: Some(color),
+            stroke_width: width,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_stroke(mut self, color: Rgba, width: f32) -> Self {
+        self.stroke = Some(color);
+        self.stroke_width = width;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Dot,
+    Warning,
+    Error,
+}
+
+/// Shapes in the node's local coordinates, y up like clip space.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    Rect { origin: [f32; 2], size: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+    Line { from: [f32; 2], to: [f32; 2] },
+    /// Closed polylines can be filled; fills assume a convex outline.
+    Polyline { points: Vec<[f32; 2]>, closed: bool },
+    /// Chained cubic Béziers from `start`: each segment is its two control
+    /// points and its end. Curves are only stroked.
+    Curve { start: [f32; 2], segments: Vec<[[f32; 2]; 3]> },
+    /// Bitmap-font text; `origin` is the bottom-left of the first cell and
+    /// `size` the cell height.
+    Text { text: String, origin: [f32; 2], size: f32 },
+    /// A symbol centred on `center` fitting a `size` square.
+    Icon { icon: Icon, center: [f32; 2], size: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Group,
+    Shape(Primitive),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub content: Content,
+    pub transform: Transform,
+    pub style: Style,
+    pub z: i32,
+    pub visible: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    pub fn group() -> Self {
+        Self::with_content(Content::Group)
+    }
+
+    pub fn shape(primitive: Primitive, style: Style) -> Self {
+        Self {
+            style,
+            ..Self::with_content(Content::Shape(primitive))
+        }
+    }
+
+    fn with_content(content: Content) -> Self {
+        Self {
+            content,
+            transform: Transform::IDENTITY,
+            style: Style::default(),
+            z: 0,
+            visible: true,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn z(mut self, z: i32) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A shape ready to draw, with its ancestors' transforms and opacity folded in.
+#[derive(Debug, Clone, Copy)]
+pub struct DrawItem<'a> {
+    pub id: NodeId,
+    pub primitive: &'a Primitive,
+    pub transform: Transform,
+    pub style: Style,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub background: Rgba,
+    nodes: Vec<Option<Node>>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new(BACKGROUND)
+    }
+}
+
+impl Scene {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new(background: Rgba) -> Self {
+        Self {
+            background,
+            nodes: vec![Some(Node::group())],
+        }
+    }
+
+    /// Insert `node` as the last child of `parent`. Panics if `parent` was removed.
+    pub fn insert(&mut self, parent: NodeId, mut node: Node) -> NodeId {
+        let id = self.nodes.len();
+        node.parent = Some(parent);
+        node.children.clear();
+        self.nodes[parent]
+            .as_mut()
+            .expect("parent node was removed")
+            .children
+            .push(id);
+        self.nodes.push(Some(node));
+        id
+    }
+
+    pub fn add(&mut self, primitive: Primitive, style: Style) -> NodeId {
+        self.insert(Self::ROOT, Node::shape(primitive, style))
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id).and_then(Option::as_ref)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id).and_then(Option::as_mut)
+    }
+
+    /// Remove a node and its subtree. The root can only be cleared.
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        if id == Self::ROOT {
+            return false;
+        }
+        let Some(node) = self.nodes.get_mut(id).and_then(Option::take) else {
+            return false;
+        };
+        if let Some(parent) = node.parent.and_then(|p| self.node_mut(p)) {
+            parent.children.retain(|&child| child != id);
+        }
+        for child in node.children {
+            self.remove(child);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        if let Some(root) = self.node_mut(Self::ROOT) {
+            root.children.clear();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.iter().flatten().count() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Children of `id` in draw order.
+    pub fn ordered_children(&self, id: NodeId) -> Vec<NodeId> {
+        let mut children = self.node(id).map(|n| n.children.clone()).unwrap_or_default();
+        children.sort_by_key(|&child| (self.nodes[child].as_ref().map_or(0, |n| n.z), child));
+        children
+    }
+
+    /// Every visible shape, back to front.
+    pub fn draw_list(&self) -> Vec<DrawItem<'_>> {
+        let mut items = Vec::new();
+        self.collect(Self::ROOT, Transform::IDENTITY, 1.0, &mut items);
+        items
+    }
+
+    fn collect<'a>(&'a self, id: NodeId, parent: Transform, opacity: f32, items: &mut Vec<DrawItem<'a>>) {
+        let Some(node) = self.node(id) else { return };
+        if !node.visible {
+            return;
+        }
+        let transform = node.transform.then(&parent);
+        let opacity = opacity * node.style.opacity;
+
+        if let Content::Shape(primitive) = &node.content {
+            items.push(DrawItem {
+                id,
+                primitive,
+                transform,
+                style: Style { opacity, ..node.style },
+            });
+        }
+        for child in self.ordered_children(id) {
+            self.collect(child, transform, opacity, items);
+        }
+    }
+}
+
+pub fn text_scene(text: &str, theme: &Theme) -> Scene {
+    let mut scene = Scene::new(theme.background);
+    scene.add(
+        Primitive::Text {
+            text: text.to_string(),
+            origin: [-0.5, 0.0],
+            size: CHAR_HEIGHT,
+        },
+        Style::fill(theme.text),
+    );
+    scene
+}
+
+/// The single-service view: its clean identifier at the centre of the canvas,
+/// followed by an icon when its status is "warning" or "error".
+pub fn service_scene(service: &ServiceNode, labels: &Labels, theme: &Theme) -> Scene {
+    let mut scene = Scene::new(theme.background);
+    let label = labels.label(&service.id);
+    let width = text_width(&label);
+    scene.add(
+        Primitive::Text {
+            text: label,
+            origin: [0.0, 0.0],
+            size: CHAR_HEIGHT,
+        },
+        Style::fill(theme.text),
+    );
+
+    let status = match service.status.as_str() {
+        "warning" => Some((Icon::Warning, theme.node.warning)),
+        "error" => Some((Icon::Error, theme.node.error)),
+        _ => None,
+    };
+    if let Some((icon, color)) = status {
+        scene.add(
+            Primitive::Icon { icon, center: [width + CHAR_HEIGHT * 0.7, CHAR_HEIGHT * 0.5], size: CHAR_HEIGHT },
+            Style::fill(color),
+        );
+    }
+    scene
+}
+
+/// `service_scene`, faded when `filter` dims the service.
+pub fn filtered_service_scene(
+    service: &ServiceNode,
+    filter: Option<&FilterMatch>,
+    labels: &Labels,
+    theme: &Theme,
+) -> Scene {
+    let mut scene = service_scene(service, labels, theme);
+    if filter.is_some_and(|filter| filter.dims(&service.id)) {
+        for child in scene.ordered_children(Scene::ROOT) {
+            if let Some(node) = scene.node_mut(child) {
+                node.style.opacity *= DIMMED_OPACITY;
+            }
+        }
+    }
+    scene
+}
+
+/// Outline the label `service_scene` draws and caption it with what led
+/// there, e.g. the search match the view flew to.
+pub fn highlight_service(scene: &mut Scene, service: &ServiceNode, caption: &str, labels: &Labels, theme: &Theme) {
+    let pad = CHAR_HEIGHT * 0.3;
+    let width = text_width(&labels.label(&service.id));
+    scene.add(
+        Primitive::Rect {
+            origin: [-pad, -pad],
+            size: [width + 2.0 * pad, CHAR_HEIGHT + 2.0 * pad],
+        },
+        Style::stroke(theme.highlight, 0.01),
+    );
+    if !caption.is_empty() {
+        let size = CHAR_HEIGHT * 0.5;
+        scene.add(
+            Primitive::Text {
+                text: caption.to_string(),
+                origin: [0.0, -pad - size - 0.03],
+                size,
+            },
+            Style::fill(theme.highlight),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dot(z: i32) -> Node {
+        Node::shape(
+            Primitive::Circle { center: [0.0, 0.0], radius: 0.1 },
+            Style::fill([1.0; 4]),
+        )
+        .z(z)
+    }
+
+    fn close(a: [f32; 2], b: [f32; 2]) -> bool {
+        (a[0] - b[0]).abs() < 1e-6 && (a[1] - b[1]).abs() < 1e-6
+    }
+
+    #[test]
+    fn transforms_compose_in_application_order() {
+        let t = Transform::scale(2.0, 2.0).then(&Transform::translate(1.0, 0.0));
+        assert!(close(t.apply([1.0, 1.0]), [3.0, 2.0]));
+
+        let r = Transform::rotate(std::f32::consts::FRAC_PI_2);
+        assert!(close(r.apply([1.0, 0.0]), [0.0, 1.0]));
+    }
+
+    #[test]
+    fn draw_list_orders_by_z_then_insertion_and_nests_children() {
+        let mut scene = Scene::default();
+        let top = scene.insert(Scene::ROOT, dot(5));
+        let group = scene.insert(
+            Scene::ROOT,
+            Node::group().transform(Transform::translate(0.5, 0.0)).z(1),
+        );
+        let inner = scene.insert(group, dot(0));
+        let first = scene.insert(Scene::ROOT, dot(1));
+
+        let order: Vec<_> = scene.draw_list().iter().map(|item| item.id).collect();
+        assert_eq!(order, [inner, first, top]);
+        assert!(close(scene.draw_list()[0].transform.apply([0.0, 0.0]), [0.5, 0.0]));
+    }
+
+    #[test]
+    fn hidden_subtrees_and_opacity_propagate() {
+        let mut scene = Scene::default();
+        let mut faded = Node::group();
+        faded.style.opacity = 0.5;
+        let group = scene.insert(Scene::ROOT, faded);
+        scene.insert(group, dot(0));
+        let hidden = scene.insert(Scene::ROOT, dot(0));
+        scene.node_mut(hidden).unwrap().visible = false;
+
+        let items = scene.draw_list();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].style.opacity, 0.5);
+    }
+
+    #[test]
+    fn removing_a_group_removes_its_subtree() {
+        let mut scene = Scene::default();
+        let group = scene.insert(Scene::ROOT, Node::group());
+        let child = scene.insert(group, dot(0));
+        scene.add(Primitive::Line { from: [0.0, 0.0], to: [1.0, 1.0] }, Style::default());
+
+        assert!(scene.remove(group));
+        assert!(scene.node(child).is_none());
+        assert_eq!(scene.len(), 1);
+        assert!(!scene.remove(Scene::ROOT));
+    }
+}
@@ -0,0 +1,338 @@
+// Full-text search over a span store.
+//
+// Every span contributes search targets: its service, its operation, its
+// own ID and its string/integer attribute values. Targets are tokenized
+// into lowercase alphanumeric terms and kept in an inverted index that the
+// store updates as spans arrive and leave, so queries never rescan spans.
+// Targets shared by many spans (a service, a route) are reference counted
+// and leave the index with their last span.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::AttrValue;
+
+/// Results returned when the caller does not ask for a limit.
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+// Longest term kept in the index; longer tokens are truncated
+const MAX_TERM_CHARS: usize = 64;
+// Index terms a single query prefix may expand to
+const MAX_PREFIX_TERMS: usize = 256;
+
+/// Something a search can land on. Every target names the service it
+/// belongs to, which is where views fly to. A serialized `SearchResult`
+/// reads back as its target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SearchTarget {
+    Service { service: String },
+    Operation { service: String, operation: String },
+    Attribute { service: String, key: String, value: String },
+    Span { service: String, span_id: String },
+}
+
+impl SearchTarget {
+    pub fn service(&self) -> &str {
+        match self {
+            SearchTarget::Service { service }
+            | SearchTarget::Operation { service, .. }
+            | SearchTarget::Attribute { service, .. }
+            | SearchTarget::Span { service, .. } => service,
+        }
+    }
+
+    /// Short text for result lists and highlight captions.
+    pub fn label(&self) -> String {
+        match self {
+            SearchTarget::Service { service } => service.clone(),
+            SearchTarget::Operation { operation, .. } => operation.clone(),
+            SearchTarget::Attribute { key, value, .. } => format!("{} = {}", key, value),
+            SearchTarget::Span { span_id, .. } => span_id.clone(),
+        }
+    }
+
+    // The text that is tokenized into index terms
+    fn text(&self) -> &str {
+        match self {
+            SearchTarget::Service { service } => service,
+            SearchTarget::Operation { operation, .. } => operation,
+            SearchTarget::Attribute { value, .. } => value,
+            SearchTarget::Span { span_id, .. } => span_id,
+        }
+    }
+
+    // Services outrank operations, which outrank attribute values and span IDs
+    fn weight(&self) -> f32 {
+        match self {
+            SearchTarget::Service { .. } => 1.0,
+            SearchTarget::Operation { .. } => 0.9,
+            SearchTarget::Attribute { .. } => 0.8,
+            SearchTarget::Span { .. } => 0.7,
+        }
+    }
+}
+
+/// One ranked match.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub target: SearchTarget,
+    pub label: String,
+    pub score: f32,
+    /// Spans behind this target.
+    pub spans: usize,
+}
+
+/// The search targets one span contributes.
+pub fn span_targets(
+    service: &str,
+    key: &str,
+    operation: Option<&str>,
+    attributes: &[(Arc<str>, AttrValue)],
+) -> Vec<SearchTarget> {
+    let mut targets = vec![
+        SearchTarget::Service { service: service.to_string() },
+        SearchTarget::Span { service: service.to_string(), span_id: key.to_string() },
+    ];
+    if let Some(operation) = operation {
+        targets.push(SearchTarget::Operation {
+            service: service.to_string(),
+            operation: operation.to_string(),
+        });
+    }
+    for (name, value) in attributes {
+        if matches!(value, AttrValue::Str(_) | AttrValue::Int(_)) {
+            targets.push(SearchTarget::Attribute {
+                service: service.to_string(),
+                key: name.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+    targets
+}
+
+type TargetId = u32;
+
+#[derive(Debug)]
+struct Entry {
+    target: SearchTarget,
+    refs: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    ids: HashMap<SearchTarget, TargetId>,
+    entries: Vec<Option<Entry>>,
+    free: Vec<TargetId>,
+    postings: BTreeMap<String, HashSet<TargetId>>,
+}
+
+impl SearchIndex {
+    /// Count one more span behind each target, indexing new ones.
+    pub fn add(&mut self, targets: Vec<SearchTarget>) {
+        for target in targets {
+            if let Some(&id) = self.ids.get(&target) {
+                if let Some(entry) = self.entries[id as usize].as_mut() {
+                    entry.refs += 1;
+                }
+                continue;
+            }
+
+            let id = match self.free.pop() {
+                Some(id) => id,
+                None => {
+                    self.entries.push(None);
+                    (self.entries.len() - 1) as TargetId
+                }
+            };
+            for term in tokenize(target.text()) {
+                self.postings.entry(term).or_default().insert(id);
+            }
+            self.ids.insert(target.clone(), id);
+            self.entries[id as usize] = Some(Entry { target, refs: 1 });
+        }
+    }
+
+    /// Undo `add` for one span, unindexing targets no span refers to anymore.
+    pub fn remove(&mut self, targets: Vec<SearchTarget>) {
+        for target in targets {
+            let Some(&id) = self.ids.get(&target) else { continue };
+            let Some(entry) = self.entries[id as usize].as_mut() else { continue };
+            entry.refs -= 1;
+            if entry.refs > 0 {
+                continue;
+            }
+
+            for term in tokenize(target.text()) {
+                if let Some(posting) = self.postings.get_mut(&term) {
+                    posting.remove(&id);
+                    if posting.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+            self.ids.remove(&target);
+            self.entries[id as usize] = None;
+            self.free.push(id);
+        }
+    }
+
+    /// Number of distinct indexed targets.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Targets matching every word of `query`, best first. Words match
+    /// whole terms or term prefixes; exact and closer matches score higher.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let words = tokenize(query);
+        if words.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<TargetId, f32>> = None;
+        for word in &words {
+            let matches = self.word_scores(word);
+            scores = Some(match scores {
+                None => matches,
+                Some(mut scores) => {
+                    scores.retain(|id, score| match matches.get(id) {
+                        Some(more) => {
+                            *score += more;
+                            true
+                        }
+                        None => false,
+                    });
+                    scores
+                }
+            });
+        }
+
+        let query = query.trim().to_lowercase();
+        let mut results: Vec<SearchResult> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let entry = self.entries[id as usize].as_ref()?;
+                let exact = if entry.target.text().to_lowercase() == query { 1.0 } else { 0.0 };
+                Some(SearchResult {
+                    label: entry.target.label(),
+                    score: (score / words.len() as f32 + exact) * entry.target.weight(),
+                    spans: entry.refs,
+                    target: entry.target.clone(),
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then(b.spans.cmp(&a.spans))
+                .then_with(|| a.target.cmp(&b.target))
+        });
+        results.truncate(limit);
+        results
+    }
+
+    // Best score of each target for one query word
+    fn word_scores(&self, word: &str) -> HashMap<TargetId, f32> {
+        let mut scores: HashMap<TargetId, f32> = HashMap::new();
+        let terms = self
+            .postings
+            .range(word.to_string()..)
+            .take_while(|(term, _)| term.starts_with(word))
+            .take(MAX_PREFIX_TERMS);
+        for (term, ids) in terms {
+            let score = if term == word {
+                1.0
+            } else {
+                0.8 * word.len() as f32 / term.len() as f32
+            };
+            for &id in ids {
+                let best = scores.entry(id).or_default();
+                *best = best.max(score);
+            }
+        }
+        scores
+    }
+}
+
+// Lowercase alphanumeric runs
+fn tokenize(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.chars().take(MAX_TERM_CHARS).collect::<String>().to_lowercase())
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(service: &str, key: &str, operation: &str, route: &str) -> Vec<SearchTarget> {
+        let attributes = [(Arc::from("http.route"), AttrValue::Str(route.to_string()))];
+        span_targets(service, key, Some(operation), &attributes)
+    }
+
+    fn labels(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.label.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_exact_before_prefix_and_services_first() {
+        let mut index = SearchIndex::default();
+        index.add(span("checkout", "a1f3", "POST /checkout", "/api/checkout"));
+        index.add(span("checkout-worker", "b2c4", "process", "/jobs"));
+        index.add(span("cart", "c0de", "GET /cart", "/api/cart/checkout-preview"));
+
+        let results = index.search("checkout", 10);
+        assert_eq!(labels(&results)[..2], ["checkout", "checkout-worker"]);
+        assert!(results.iter().any(|r| r.label == "http.route = /api/cart/checkout-preview"));
+
+        let results = index.search("POST check", 10);
+        assert_eq!(labels(&results), ["POST /checkout"]);
+        assert_eq!(results[0].target.service(), "checkout");
+
+        assert_eq!(labels(&index.search("c0", 10)), ["c0de"]);
+        assert!(index.search("  ", 10).is_empty());
+        assert_eq!(index.search("checkout", 1).len(), 1);
+
+        // Results handed to JS come back as their targets to fly to
+        let json = serde_json::to_string(&results[0]).unwrap();
+        assert_eq!(serde_json::from_str::<SearchTarget>(&json).unwrap(), results[0].target);
+    }
+
+    #[test]
+    fn shared_targets_leave_with_their_last_span() {
+        let mut index = SearchIndex::default();
+        index.add(span("auth", "1", "login", "/login"));
+        index.add(span("auth", "2", "login", "/login"));
+        assert_eq!(index.search("login", 10)[0].spans, 2);
+
+        index.remove(span("auth", "1", "login", "/login"));
+        assert_eq!(index.search("login", 10)[0].spans, 1);
+        assert!(index.search("1", 10).is_empty());
+
+        index.remove(span("auth", "2", "login", "/login"));
+        assert!(index.is_empty());
+        assert!(index.postings.is_empty());
+
+        // Freed slots are reused
+        index.add(span("billing", "3", "charge", "/pay"));
+        assert_eq!(index.entries.len(), 5);
+    }
+}
@@ -0,0 +1,275 @@
+// Saved view state: what `save_session` writes and `load_session` restores.
+//
+// A session is a JSON document with a `version` field. Loading first runs
+// the migrations from the saved version up to `SESSION_VERSION`, then reads
+// the result with every field defaulted. A session from an older build
+// therefore loads with later fields at their defaults, and one from a newer
+// build loads with the fields this build does not know about ignored.
+//
+// Values that cannot be applied at all, such as a zero playback speed,
+// reject the whole session before anything is restored. Parts that only
+// fail against this build or the loaded data, such as a filter that no
+// longer parses, are dropped and reported as warnings.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::annotations::Annotation;
+use crate::filter::FilterMode;
+use crate::grouping::Grouping;
+use crate::labels::LabelRules;
+use crate::layout::Pins;
+use crate::scene::Transform;
+
+pub const SESSION_VERSION: u32 = 1;
+
+// Upgrades run in order: MIGRATIONS[0] turns a version 1 session into
+// version 2, and so on. Add one whenever a field is renamed or reshaped;
+// new fields only need a default.
+type Migration = fn(&mut Value);
+const MIGRATIONS: &[Migration] = &[];
+
+/// Pan and zoom applied to every scene on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Camera {
+    /// Clip-space point shown at the centre of the canvas.
+    pub center: [f32; 2],
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { center: [0.0, 0.0], zoom: 1.0 }
+    }
+}
+
+impl Camera {
+    pub fn transform(&self) -> Transform {
+        Transform::translate(-self.center[0], -self.center[1]).then(&Transform::scale(self.zoom, self.zoom))
+    }
+
+    /// The scene point drawn at clip-space `point`; the inverse of `transform`.
+    pub fn unproject(&self, point: [f32; 2]) -> [f32; 2] {
+        [point[0] / self.zoom + self.center[0], point[1] / self.zoom + self.center[1]]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedFilter {
+    pub expr: String,
+    pub mode: FilterMode,
+}
+
+/// The outlined service and the selected trace.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Selection {
+    pub service: Option<String>,
+    pub caption: Option<String>,
+    pub trace: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub version: u32,
+    /// The active job.
+    pub job: Option<String>,
+    pub camera: Camera,
+    pub filter: Option<SavedFilter>,
+    /// Playback clock time, which picks the service on screen.
+    pub playhead_ms: f64,
+    /// Playback rate; 2 rotates through services twice as fast.
+    pub speed: f64,
+    pub selection: Selection,
+    /// User annotations by job.
+    pub annotations: BTreeMap<String, Vec<Annotation>>,
+    /// Pinned service positions by job. The automatic layout is
+    /// deterministic, so other positions are not saved.
+    pub positions: BTreeMap<String, Pins>,
+    /// How the service map groups services, and which groups are collapsed.
+    pub grouping: Grouping,
+    /// How service IDs become display names.
+    pub labels: LabelRules,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            job: None,
+            camera: Camera::default(),
+            filter: None,
+            playhead_ms: 0.0,
+            speed: 1.0,
+            selection: Selection::default(),
+            annotations: BTreeMap::new(),
+            positions: BTreeMap::new(),
+            grouping: Grouping::default(),
+            labels: LabelRules::default(),
+        }
+    }
+}
+
+/// A part of a session that was dropped while the rest was restored.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionWarning {
+    /// The saved job is not loaded; the active job stays.
+    UnknownJob { job: String },
+    /// The saved filter no longer parses; no filter is active.
+    FilterDropped { expr: String, message: String },
+    /// The saved label pattern no longer compiles; the current rules stay.
+    LabelsDropped { message: String },
+}
+
+impl fmt::Display for SessionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionWarning::UnknownJob { job } => write!(f, "Saved job '{}' is not loaded", job),
+            SessionWarning::FilterDropped { expr, message } => {
+                write!(f, "Saved filter '{}' dropped: {}", expr, message)
+            }
+            SessionWarning::LabelsDropped { message } => write!(f, "Saved label rules dropped: {}", message),
+        }
+    }
+}
+
+/// What loading a session did: the service at the saved playhead, where
+/// the rotation resumes, and what could not be restored.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Restored {
+    pub service_index: usize,
+    pub warnings: Vec<SessionWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    Malformed(String),
+    MissingVersion,
+    /// A value no view can take, e.g. a zero playback speed.
+    Invalid(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Malformed(message) => write!(f, "Malformed session: {}", message),
+            SessionError::MissingVersion => write!(f, "Not a session: no version field"),
+            SessionError::Invalid(message) => write!(f, "Invalid session: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl Session {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("sessions always serialize")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SessionError> {
+        let malformed = |e: serde_json::Error| SessionError::Malformed(e.to_string());
+        let mut value: Value = serde_json::from_slice(bytes).map_err(malformed)?;
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(SessionError::MissingVersion)?;
+        for migration in MIGRATIONS.iter().skip(version.saturating_sub(1) as usize) {
+            migration(&mut value);
+        }
+        let mut session: Session = serde_json::from_value(value).map_err(malformed)?;
+        session.version = SESSION_VERSION;
+        session.check()?;
+        Ok(session)
+    }
+
+    // Reject values that would leave the view unusable
+    fn check(&self) -> Result<(), SessionError> {
+        let invalid = |message: String| Err(SessionError::Invalid(message));
+        if !(self.speed.is_finite() && self.speed > 0.0) {
+            return invalid(format!("playback speed {}", self.speed));
+        }
+        let Camera { center: [x, y], zoom } = self.camera;
+        if !(zoom.is_finite() && zoom > 0.0 && x.is_finite() && y.is_finite()) {
+            return invalid(format!("camera ({}, {}) zoom {}", x, y, zoom));
+        }
+        if !(self.playhead_ms.is_finite() && self.playhead_ms >= 0.0) {
+            return invalid(format!("playhead {}ms", self.playhead_ms));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{AnnotationKind, AnnotationSource};
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let mut session = Session {
+            job: Some("nightly".to_string()),
+            camera: Camera { center: [0.2, -0.1], zoom: 2.0 },
+            filter: Some(SavedFilter { expr: "status = error".to_string(), mode: FilterMode::Hide }),
+            playhead_ms: 25_000.0,
+            speed: 2.0,
+            selection: Selection { service: Some("cart".to_string()), caption: None, trace: Some("t1".to_string()) },
+            ..Default::default()
+        };
+        session.annotations.insert("nightly".to_string(), vec![Annotation {
+            id: 3,
+            timestamp_ns: 10,
+            end_ns: None,
+            kind: AnnotationKind::Deploy,
+            label: "v2".to_string(),
+            service: None,
+            severity: None,
+            source: AnnotationSource::User,
+        }]);
+        session.positions.insert("nightly".to_string(), [("cart".to_string(), [0.5, 0.25])].into());
+
+        assert_eq!(Session::from_bytes(&session.to_bytes()).unwrap(), session);
+    }
+
+    #[test]
+    fn older_and_newer_sessions_load() {
+        // An older build's session lacks later fields; a newer one adds fields
+        // this build ignores
+        let older = br#"{ "version": 1, "job": "a" }"#;
+        let session = Session::from_bytes(older).unwrap();
+        assert_eq!((session.job.as_deref(), session.speed, session.camera), (Some("a"), 1.0, Camera::default()));
+
+        let newer = br#"{ "version": 7, "job": "b", "theme": "light", "camera": { "zoom": 3, "tilt": 1 } }"#;
+        let session = Session::from_bytes(newer).unwrap();
+        assert_eq!((session.job.as_deref(), session.camera.zoom, session.version), (Some("b"), 3.0, SESSION_VERSION));
+
+        assert_eq!(Session::from_bytes(br#"{ "job": "a" }"#), Err(SessionError::MissingVersion));
+        assert!(matches!(Session::from_bytes(b"not json"), Err(SessionError::Malformed(_))));
+    }
+
+    #[test]
+    fn unusable_values_reject_the_session() {
+        for bad in [
+            br#"{ "version": 1, "speed": 0 }"#.as_slice(),
+            br#"{ "version": 1, "speed": -2 }"#,
+            br#"{ "version": 1, "camera": { "zoom": 0 } }"#,
+            br#"{ "version": 1, "playhead_ms": -1 }"#,
+        ] {
+            assert!(matches!(Session::from_bytes(bad), Err(SessionError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn camera_pans_then_zooms() {
+        let camera = Camera { center: [0.5, 0.0], zoom: 2.0 };
+        assert_eq!(camera.transform().apply([0.5, 0.0]), [0.0, 0.0]);
+        assert_eq!(camera.transform().apply([1.0, 0.25]), [1.0, 0.5]);
+        assert_eq!(camera.unproject([1.0, 0.5]), [1.0, 0.25]);
+    }
+}
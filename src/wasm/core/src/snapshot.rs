@@ -0,0 +1,177 @@
+// Still-image snapshots: turning GPU readback rows into an `Image` and
+// encoding/decoding PNG, with no browser APIs involved so captures work
+// from a worker.
+
+use std::fmt;
+
+use crate::raster::Image;
+
+/// Byte order of a readback buffer's pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelOrder {
+    Rgba,
+    Bgra,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    InvalidSize { width: u32, height: u32, max: u32 },
+    TooLarge { width: u32, height: u32, sample_count: u32, bytes: u64, max: u64 },
+    ShortBuffer { expected: usize, actual: usize },
+    Png(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidSize { width, height, max } => {
+                write!(f, "Snapshot size {}x{} must be between 1 and {} per side", width, height, max)
+            }
+            SnapshotError::TooLarge { width, height, sample_count, bytes, max } => write!(
+                f,
+                "Capture {}x{} at {}x multisampling needs {} bytes, over the device's {} byte buffer limit",
+                width, height, sample_count, bytes, max
+            ),
+            SnapshotError::ShortBuffer { expected, actual } => {
+                write!(f, "Readback buffer holds {} bytes, expected at least {}", actual, expected)
+            }
+            SnapshotError::Png(message) => write!(f, "PNG error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+pub fn check_size(width: u32, height: u32, max: u32) -> Result<(), SnapshotError> {
+    if width == 0 || height == 0 || width > max || height > max {
+        return Err(SnapshotError::InvalidSize { width, height, max });
+    }
+    Ok(())
+}
+
+/// Check that the largest allocation of a capture fits in `max` bytes: the
+/// readback buffer of `bytes_per_row` rows, or the multisampled target,
+/// which holds `sample_count` samples of every pixel.
+pub fn check_memory(
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    sample_count: u32,
+    max: u64,
+) -> Result<(), SnapshotError> {
+    let bytes = bytes_per_row as u64 * height as u64 * sample_count.max(1) as u64;
+    if bytes > max {
+        return Err(SnapshotError::TooLarge { width, height, sample_count, bytes, max });
+    }
+    Ok(())
+}
+
+/// Row stride for a texture-to-buffer copy: 4 bytes per pixel rounded up
+/// to the copy alignment (256 on WebGPU).
+pub fn padded_bytes_per_row(width: u32, alignment: u32) -> u32 {
+    (width * 4).div_ceil(alignment) * alignment
+}
+
+/// Strip row padding from a readback buffer and reorder pixels to RGBA.
+pub fn from_padded_rows(
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    order: PixelOrder,
+    data: &[u8],
+) -> Result<Image, SnapshotError> {
+    let row_bytes = width as usize * 4;
+    let expected = bytes_per_row as usize * (height as usize).saturating_sub(1) + row_bytes;
+    if data.len() < expected {
+        return Err(SnapshotError::ShortBuffer { expected, actual: data.len() });
+    }
+
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    if order == PixelOrder::Bgra {
+        pixels.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+    }
+    Ok(Image::new(width, height, pixels))
+}
+
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, SnapshotError> {
+    let png_error = |e: png::EncodingError| SnapshotError::Png(e.to_string());
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, image.width, image.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&image.pixels).map_err(png_error)?;
+        writer.finish().map_err(png_error)?;
+    }
+    Ok(bytes)
+}
+
+/// Decode an 8-bit RGBA PNG, as written by `encode_png`.
+pub fn decode_png(bytes: &[u8]) -> Result<Image, SnapshotError> {
+    let png_error = |e: png::DecodingError| SnapshotError::Png(e.to_string());
+    let mut reader = png::Decoder::new(bytes).read_info().map_err(png_error)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(png_error)?;
+    if (info.color_type, info.bit_depth) != (png::ColorType::Rgba, png::BitDepth::Eight) {
+        return Err(SnapshotError::Png(format!(
+            "expected 8-bit RGBA, found {:?} {:?}",
+            info.bit_depth, info.color_type
+        )));
+    }
+    pixels.truncate(info.buffer_size());
+    Ok(Image::new(info.width, info.height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readback_rows_are_unpadded_and_swizzled() {
+        let stride = padded_bytes_per_row(3, 256);
+        assert_eq!(stride, 256);
+
+        let mut data = vec![0u8; stride as usize * 2];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[stride as usize + 8..stride as usize + 12].copy_from_slice(&[5, 6, 7, 8]);
+
+        let image = from_padded_rows(3, 2, stride, PixelOrder::Bgra, &data).unwrap();
+        assert_eq!(image.pixels.len(), 24);
+        assert_eq!(image.pixel(0, 0), [3, 2, 1, 4]);
+        assert_eq!(image.pixel(2, 1), [7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn short_buffers_and_bad_sizes_are_rejected() {
+        let error = from_padded_rows(2, 2, 256, PixelOrder::Rgba, &[0; 260]).unwrap_err();
+        assert_eq!(error, SnapshotError::ShortBuffer { expected: 264, actual: 260 });
+        assert!(check_size(0, 10, 8192).is_err());
+        assert!(check_size(8193, 10, 8192).is_err());
+        assert!(check_size(3840, 2160, 8192).is_ok());
+    }
+
+    #[test]
+    fn capture_memory_counts_every_sample() {
+        let max = 256 << 20;
+        assert!(check_memory(8192, 8192, 32768, 1, max).is_ok());
+        let error = check_memory(8192, 8192, 32768, 4, max).unwrap_err();
+        let expected = SnapshotError::TooLarge { width: 8192, height: 8192, sample_count: 4, bytes: 1 << 30, max };
+        assert_eq!(error, expected);
+        assert!(check_memory(4096, 4096, 16384, 4, max).is_ok());
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let pixels = (0..4 * 3 * 2).map(|i| i as u8 * 10).collect();
+        let image = Image::new(3, 2, pixels);
+        let bytes = encode_png(&image).unwrap();
+
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(decode_png(&bytes).unwrap(), image);
+    }
+}
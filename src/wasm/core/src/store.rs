@@ -0,0 +1,848 @@
+// Span store with persistent indexes and a bounded retention policy.
+//
+// Every ingested row becomes a span keyed by its ID. Duplicate keys are
+// dropped on insert, services are tracked incrementally, and the retention
+// policy is enforced after each insert by evicting from the front of the
+// arrival/time indexes instead of rebuilding anything.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::diff::Totals;
+use crate::filter::{Filter, FilterMatch, FilterMode, SpanFields, SpanMatch};
+use crate::layout::{self, Pins};
+use crate::provenance::{Origin, SourceId};
+use crate::search::{span_targets, SearchIndex, SearchResult};
+use crate::trace::TraceSpan;
+use crate::{ReplayData, ServiceNode};
+
+/// Limits applied to the span store. `None` disables a limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Maximum number of spans kept; the oldest arrivals are evicted first.
+    pub max_spans: Option<usize>,
+    /// Maximum age in nanoseconds relative to the newest span timestamp.
+    pub max_age_ns: Option<i64>,
+    /// Maximum number of services; the least recently seen one is evicted
+    /// together with all of its spans.
+    pub max_services: Option<usize>,
+    /// Maximum number of jobs kept, each in its own store; the least
+    /// recently used job other than the active one is evicted whole.
+    pub max_jobs: Option<usize>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_spans: Some(500_000),
+            max_age_ns: None,
+            max_services: Some(10_000),
+            max_jobs: Some(8),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// An age limit given in milliseconds, as JavaScript passes it, in
+    /// nanoseconds. `None` for NaN, infinite or negative ages, which no
+    /// limit can mean; ages past the `i64` range saturate.
+    pub fn max_age_from_ms(ms: f64) -> Option<i64> {
+        (ms.is_finite() && ms >= 0.0).then_some((ms * 1_000_000.0) as i64)
+    }
+}
+
+/// A span attribute value, typed after the column it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl fmt::Display for AttrValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttrValue::Str(v) => f.write_str(v),
+            AttrValue::Int(v) => write!(f, "{}", v),
+            AttrValue::Float(v) => write!(f, "{}", v),
+            AttrValue::Bool(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// One decoded row, before it is assigned a slot in the store.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpanRecord {
+    pub key: String,
+    pub service: String,
+    pub timestamp_ns: Option<i64>,
+    pub duration_ns: Option<i64>,
+    pub operation: Option<String>,
+    pub status: Option<String>,
+    /// Trace the span belongs to and its parent span's ID within it.
+    pub trace_id: Option<String>,
+    pub parent_id: Option<String>,
+    /// Remaining columns by name. Names are shared across a batch's rows.
+    pub attributes: Vec<(Arc<str>, AttrValue)>,
+    /// Job named by the row itself, if the schema has a job column.
+    pub job: Option<String>,
+    /// Index of the record batch the row came from within its chunk.
+    pub batch: usize,
+}
+
+#[derive(Debug, Clone)]
+struct StoredSpan {
+    key: String,
+    service: String,
+    timestamp_ns: Option<i64>,
+    duration_ns: Option<i64>,
+    operation: Option<String>,
+    status: Option<String>,
+    trace_id: Option<String>,
+    parent_id: Option<String>,
+    attributes: Vec<(Arc<str>, AttrValue)>,
+    // Every source batch that delivered this span
+    origins: Vec<Origin>,
+    // Also delivered by an ingest without a source key, so source removal keeps it
+    unsourced: bool,
+}
+
+impl StoredSpan {
+    // What a filter sees of this span
+    fn fields<'a>(&'a self, job: &'a str) -> SpanFields<'a> {
+        SpanFields {
+            id: &self.key,
+            service: &self.service,
+            job,
+            duration_ns: self.duration_ns,
+            operation: self.operation.as_deref(),
+            status: self.status.as_deref(),
+            attributes: &self.attributes,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ServiceEntry {
+    slot: usize,
+    spans: BTreeSet<u64>,
+    last_seq: u64,
+}
+
+/// Counters describing what a single ingest did to the store.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IngestStats {
+    pub added_spans: usize,
+    pub duplicate_spans: usize,
+    pub expired_spans: usize,
+    pub new_services: usize,
+    pub evicted_spans: usize,
+    pub evicted_services: usize,
+    pub evicted_jobs: usize,
+    pub removed_spans: usize,
+}
+
+impl std::ops::AddAssign for IngestStats {
+    fn add_assign(&mut self, other: Self) {
+        self.added_spans += other.added_spans;
+        self.duplicate_spans += other.duplicate_spans;
+        self.expired_spans += other.expired_spans;
+        self.new_services += other.new_services;
+        self.evicted_spans += other.evicted_spans;
+        self.evicted_services += other.evicted_services;
+        self.evicted_jobs += other.evicted_jobs;
+        self.removed_spans += other.removed_spans;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SpanStore {
+    policy: RetentionPolicy,
+    next_seq: u64,
+    spans: HashMap<u64, StoredSpan>,
+    by_key: HashMap<String, u64>,
+    // Arrival order; may hold sequence numbers already evicted by another index
+    arrival: VecDeque<u64>,
+    by_time: BTreeSet<(i64, u64)>,
+    newest_ns: Option<i64>,
+    services: Vec<ServiceNode>,
+    service_index: HashMap<String, ServiceEntry>,
+    service_recency: BTreeMap<u64, String>,
+    by_source: HashMap<SourceId, HashSet<u64>>,
+    by_trace: HashMap<String, BTreeSet<u64>>,
+    search: SearchIndex,
+    // Spans the last ingest, source removal or policy change dropped, so
+    // what was derived from them can be taken back
+    removed: Vec<(u64, StoredSpan)>,
+    // Pinned service positions; they override the automatic layout
+    pins: Pins,
+}
+
+impl SpanStore {
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the retention policy and immediately evict anything it no longer allows.
+    pub fn set_policy(&mut self, policy: RetentionPolicy) -> IngestStats {
+        self.policy = policy;
+        self.removed.clear();
+        let mut stats = IngestStats::default();
+        self.enforce(&mut stats);
+        if stats.evicted_spans > 0 || stats.evicted_services > 0 {
+            self.relayout();
+        }
+        stats
+    }
+
+    pub fn services(&self) -> &[ServiceNode] {
+        &self.services
+    }
+
+    pub fn span_count(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Pinned service positions, kept even while the service is evicted.
+    pub fn pins(&self) -> &Pins {
+        &self.pins
+    }
+
+    /// Replace every pin and lay the services out again.
+    pub fn set_pins(&mut self, pins: Pins) {
+        self.pins = pins;
+        self.relayout();
+    }
+
+    pub fn pin(&mut self, service: &str, position: [f32; 2]) {
+        self.pins.insert(service.to_string(), position);
+        self.relayout();
+    }
+
+    /// Return a service to the automatic layout. False if it was not pinned.
+    pub fn unpin(&mut self, service: &str) -> bool {
+        let pinned = self.pins.remove(service).is_some();
+        if pinned {
+            self.relayout();
+        }
+        pinned
+    }
+
+    pub fn service_span_count(&self, service: &str) -> usize {
+        self.service_index.get(service).map_or(0, |entry| entry.spans.len())
+    }
+
+    /// Drop every span and service while keeping the retention policy and
+    /// the pins, which apply again as their services return.
+    pub fn clear(&mut self) {
+        let pins = std::mem::take(&mut self.pins);
+        *self = Self::new(self.policy);
+        self.pins = pins;
+    }
+
+    /// Number of retained spans that `source` delivered.
+    pub fn source_span_count(&self, source: SourceId) -> usize {
+        self.by_source.get(&source).map_or(0, HashSet::len)
+    }
+
+    /// Merge decoded records into the store, skipping keys it already holds.
+    ///
+    /// When `source` is set every record is attributed to it, including
+    /// duplicates, so the span survives until all of its sources are removed.
+    pub fn ingest<I>(&mut self, records: I, source: Option<SourceId>) -> IngestStats
+    where
+        I: IntoIterator<Item = SpanRecord>,
+    {
+        let mut stats = IngestStats::default();
+        self.removed.clear();
+
+        for record in records {
+            if let Some(&seq) = self.by_key.get(&record.key) {
+                self.attribute(seq, source, record.batch);
+                stats.duplicate_spans += 1;
+                continue;
+            }
+
+            if let (Some(ts), Some(newest), Some(max_age)) =
+                (record.timestamp_ns, self.newest_ns, self.policy.max_age_ns)
+            {
+                if ts < newest.saturating_sub(max_age) {
+                    stats.expired_spans += 1;
+                    continue;
+                }
+            }
+
+            let batch = record.batch;
+            let seq = self.insert(record, &mut stats);
+            self.attribute(seq, source, batch);
+            self.enforce(&mut stats);
+        }
+
+        if stats.new_services > 0 || stats.evicted_services > 0 {
+            self.relayout();
+        }
+
+        stats
+    }
+
+    /// Withdraw everything `source` contributed. Spans also delivered by
+    /// another source, or by an ingest without a source, are kept.
+    pub fn remove_source(&mut self, source: SourceId) -> IngestStats {
+        let mut stats = IngestStats::default();
+        self.removed.clear();
+        let Some(seqs) = self.by_source.remove(&source) else {
+            return stats;
+        };
+
+        for seq in seqs {
+            let Some(span) = self.spans.get_mut(&seq) else { continue };
+            span.origins.retain(|origin| origin.source != source);
+            if span.origins.is_empty() && !span.unsourced && self.remove_span(seq, &mut stats) {
+                stats.removed_spans += 1;
+            }
+        }
+
+        if stats.evicted_services > 0 {
+            self.relayout();
+        }
+        stats
+    }
+
+    // Record that `source` delivered the span at `seq`
+    fn attribute(&mut self, seq: u64, source: Option<SourceId>, batch: usize) {
+        let Some(span) = self.spans.get_mut(&seq) else { return };
+        match source {
+            Some(source) => {
+                if span.origins.iter().all(|origin| origin.source != source) {
+                    span.origins.push(Origin { source, batch });
+                    self.by_source.entry(source).or_default().insert(seq);
+                }
+            }
+            None => span.unsourced = true,
+        }
+    }
+
+    /// Build the serializable view handed to JavaScript.
+    pub fn snapshot(&self, job_id: &str, timestamp: &str) -> ReplayData {
+        ReplayData {
+            services: self.services.clone(),
+            all_service_ids: self.services.iter().map(|s| s.id.clone()).collect(),
+            timestamp: timestamp.to_string(),
+            job_id: job_id.to_string(),
+        }
+    }
+
+    /// Evaluate `filter` against every span. `job` is the job this store
+    /// holds, which the filter sees as each span's `job` field.
+    pub fn filter(&self, job: &str, filter: &Filter, mode: FilterMode) -> FilterMatch {
+        let mut matched = FilterMatch { mode, ..Default::default() };
+        for span in self.spans.values() {
+            if filter.matches(&span.fields(job)) {
+                *matched.services.entry(span.service.clone()).or_default() += 1;
+                matched.spans += 1;
+            }
+        }
+        matched
+    }
+
+    /// Bring `matched`, evaluated before the last ingest, source removal or
+    /// policy change, up to date: add the spans stored at or after `seq`
+    /// that `filter` matches and take back the matching spans it dropped.
+    pub fn refilter(&self, job: &str, filter: &Filter, seq: u64, matched: &mut FilterMatch) {
+        for (_, span) in self.removed.iter().filter(|&&(removed, _)| removed < seq) {
+            let Some(count) = matched.services.get_mut(&span.service) else { continue };
+            if filter.matches(&span.fields(job)) {
+                *count -= 1;
+                matched.spans -= 1;
+                if *count == 0 {
+                    matched.services.remove(&span.service);
+                }
+            }
+        }
+        for span in self.spans_since(seq) {
+            if filter.matches(&span.fields(job)) {
+                *matched.services.entry(span.service.clone()).or_default() += 1;
+                matched.spans += 1;
+            }
+        }
+    }
+
+    /// Services with a span stored at or after `seq`, or dropped by the
+    /// last ingest, source removal or policy change.
+    pub fn touched_services(&self, seq: u64) -> BTreeSet<&str> {
+        let removed = self.removed.iter().map(|(_, span)| span.service.as_str());
+        self.spans_since(seq).map(|span| span.service.as_str()).chain(removed).collect()
+    }
+
+    // Retained spans stored at or after `seq`, in arrival order
+    fn spans_since(&self, seq: u64) -> impl Iterator<Item = &StoredSpan> {
+        let from = self.arrival.partition_point(|&arrived| arrived < seq);
+        self.arrival.range(from..).filter_map(|seq| self.spans.get(seq))
+    }
+
+    /// Evaluate `filter` against the spans of one trace.
+    pub fn filter_trace(&self, job: &str, filter: &Filter, trace_id: &str, mode: FilterMode) -> SpanMatch {
+        let seqs = self.by_trace.get(trace_id).into_iter().flatten();
+        let spans = seqs
+            .filter_map(|seq| self.spans.get(seq))
+            .filter(|span| filter.matches(&span.fields(job)))
+            .map(|span| span.key.clone())
+            .collect();
+        SpanMatch { mode, spans }
+    }
+
+    /// Ranked matches for `query` among services, operations, span IDs and
+    /// attribute values.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        self.search.search(query, limit)
+    }
+
+    /// The timed spans of one trace, in arrival order.
+    pub fn trace(&self, trace_id: &str) -> Vec<TraceSpan> {
+        let Some(seqs) = self.by_trace.get(trace_id) else {
+            return Vec::new();
+        };
+        seqs.iter()
+            .filter_map(|seq| {
+                let span = self.spans.get(seq)?;
+                Some(TraceSpan {
+                    span_id: span.key.clone(),
+                    parent_id: span.parent_id.clone(),
+                    service: span.service.clone(),
+                    operation: span.operation.clone(),
+                    start_ns: span.timestamp_ns?,
+                    duration_ns: span.duration_ns?,
+                    status: span.status.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// `(service, timestamp_ns, duration_ns, status)` of every span with a
+    /// timestamp, oldest first.
+    pub fn timed_spans(&self) -> impl Iterator<Item = (&str, i64, Option<i64>, Option<&str>)> {
+        self.by_time.iter().filter_map(|(ts, seq)| {
+            let span = self.spans.get(seq)?;
+            Some((span.service.as_str(), *ts, span.duration_ns, span.status.as_deref()))
+        })
+    }
+
+    /// Sequence number the next stored span gets; pass it to
+    /// `timed_spans_since` to read back what an ingest added.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Like `timed_spans`, for the retained spans stored at or after `seq`,
+    /// in arrival order.
+    pub fn timed_spans_since(&self, seq: u64) -> impl Iterator<Item = (&str, i64, Option<i64>, Option<&str>)> {
+        self.spans_since(seq).filter_map(|span| {
+            Some((span.service.as_str(), span.timestamp_ns?, span.duration_ns, span.status.as_deref()))
+        })
+    }
+
+    /// Per-service and per-edge totals of the spans starting in `range`,
+    /// or of every span when there is no range. Edges run from a parent
+    /// span's service to its child's; calls within a service are left out.
+    pub fn totals(&self, range: Option<(i64, i64)>) -> Totals {
+        let mut totals = Totals::default();
+        let mut add = |span: &StoredSpan| {
+            let status = span.status.as_deref();
+            totals.services.entry(span.service.clone()).or_default().add(span.duration_ns, status);
+            let parent = span.parent_id.as_ref().and_then(|id| self.spans.get(self.by_key.get(id)?));
+            if let Some(parent) = parent.filter(|parent| parent.service != span.service) {
+                let edge = (parent.service.clone(), span.service.clone());
+                totals.edges.entry(edge).or_default().add(span.duration_ns, status);
+            }
+        };
+        match range {
+            Some((start, end)) => {
+                for (_, seq) in self.by_time.range((start, 0)..(end, 0)) {
+                    if let Some(span) = self.spans.get(seq) {
+                        add(span);
+                    }
+                }
+                totals.duration_ns = Some(end.saturating_sub(start));
+            }
+            None => {
+                self.spans.values().for_each(add);
+                totals.duration_ns = self.time_range().map(|(oldest, newest)| newest - oldest);
+            }
+        }
+        totals
+    }
+
+    /// The value of attribute `key` on the service's newest span that has it.
+    pub fn service_attribute(&self, service: &str, key: &str) -> Option<String> {
+        let entry = self.service_index.get(service)?;
+        entry.spans.iter().rev().find_map(|seq| {
+            let span = self.spans.get(seq)?;
+            span.attributes.iter().find(|(name, _)| &**name == key).map(|(_, value)| value.to_string())
+        })
+    }
+
+    /// Oldest and newest span timestamps.
+    pub fn time_range(&self) -> Option<(i64, i64)> {
+        Some((self.by_time.first()?.0, self.by_time.last()?.0))
+    }
+
+    /// Set each service's `ServiceNode.status`.
+    pub fn set_statuses(&mut self, status: impl Fn(&str) -> &'static str) {
+        for node in &mut self.services {
+            let status = status(&node.id);
+            if node.status != status {
+                node.status = status.to_string();
+            }
+        }
+    }
+
+    /// IDs of every trace with at least one retained span.
+    pub fn trace_ids(&self) -> impl Iterator<Item = &str> {
+        self.by_trace.keys().map(String::as_str)
+    }
+
+    fn insert(&mut self, record: SpanRecord, stats: &mut IngestStats) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        if let Some(ts) = record.timestamp_ns {
+            self.by_time.insert((ts, seq));
+            self.newest_ns = Some(self.newest_ns.map_or(ts, |n| n.max(ts)));
+        }
+        self.arrival.push_back(seq);
+        self.by_key.insert(record.key.clone(), seq);
+        if let Some(trace_id) = &record.trace_id {
+            self.by_trace.entry(trace_id.clone()).or_default().insert(seq);
+        }
+
+        match self.service_index.get_mut(&record.service) {
+            Some(entry) => {
+                self.service_recency.remove(&entry.last_seq);
+                entry.last_seq = seq;
+                entry.spans.insert(seq);
+            }
+            None => {
+                self.service_index.insert(
+                    record.service.clone(),
+                    ServiceEntry {
+                        slot: self.services.len(),
+                        spans: BTreeSet::from([seq]),
+                        last_seq: seq,
+                    },
+                );
+                self.services.push(ServiceNode {
+                    id: record.service.clone(),
+                    x: 0.0,
+                    y: 0.0,
+                    status: "healthy".to_string(),
+                });
+                stats.new_services += 1;
+            }
+        }
+        self.service_recency.insert(seq, record.service.clone());
+        self.search.add(span_targets(
+            &record.service,
+            &record.key,
+            record.operation.as_deref(),
+            &record.attributes,
+        ));
+
+        self.spans.insert(
+            seq,
+            StoredSpan {
+                key: record.key,
+                service: record.service,
+                timestamp_ns: record.timestamp_ns,
+                duration_ns: record.duration_ns,
+                operation: record.operation,
+                status: record.status,
+                trace_id: record.trace_id,
+                parent_id: record.parent_id,
+                attributes: record.attributes,
+                origins: Vec::new(),
+                unsourced: false,
+            },
+        );
+        stats.added_spans += 1;
+        seq
+    }
+
+    fn enforce(&mut self, stats: &mut IngestStats) {
+        if let Some(max_spans) = self.policy.max_spans {
+            while self.spans.len() > max_spans {
+                let Some(seq) = self.arrival.pop_front() else { break };
+                if self.remove_span(seq, stats) {
+                    stats.evicted_spans += 1;
+                }
+            }
+        }
+
+        if let (Some(max_age), Some(newest)) = (self.policy.max_age_ns, self.newest_ns) {
+            let cutoff = newest.saturating_sub(max_age);
+            while let Some(&(ts, seq)) = self.by_time.first() {
+                if ts >= cutoff {
+                    break;
+                }
+                if self.remove_span(seq, stats) {
+                    stats.evicted_spans += 1;
+                }
+            }
+        }
+
+        if let Some(max_services) = self.policy.max_services {
+            while self.service_index.len() > max_services {
+                let Some((_, service)) = self.service_recency.pop_first() else { break };
+                let Some(entry) = self.service_index.get(&service) else { continue };
+                let seqs: Vec<u64> = entry.spans.iter().copied().collect();
+                for seq in seqs {
+                    if self.remove_span(seq, stats) {
+                        stats.evicted_spans += 1;
+                    }
+                }
+            }
+        }
+
+        // Lazy arrival entries pile up when spans leave through other indexes
+        while let Some(&seq) = self.arrival.front() {
+            if self.spans.contains_key(&seq) {
+                break;
+            }
+            self.arrival.pop_front();
+        }
+    }
+
+    // Remove one span from every index; drops its service once it has no spans left.
+    fn remove_span(&mut self, seq: u64, stats: &mut IngestStats) -> bool {
+        let Some(span) = self.spans.remove(&seq) else {
+            return false;
+        };
+
+        self.by_key.remove(&span.key);
+        self.search.remove(span_targets(
+            &span.service,
+            &span.key,
+            span.operation.as_deref(),
+            &span.attributes,
+        ));
+        if let Some(ts) = span.timestamp_ns {
+            self.by_time.remove(&(ts, seq));
+        }
+        if let Some(trace_id) = &span.trace_id {
+            if let Some(seqs) = self.by_trace.get_mut(trace_id) {
+                seqs.remove(&seq);
+                if seqs.is_empty() {
+                    self.by_trace.remove(trace_id);
+                }
+            }
+        }
+        for origin in &span.origins {
+            if let Some(seqs) = self.by_source.get_mut(&origin.source) {
+                seqs.remove(&seq);
+            }
+        }
+
+        let now_empty = match self.service_index.get_mut(&span.service) {
+            Some(entry) => {
+                entry.spans.remove(&seq);
+                if entry.last_seq == seq {
+                    self.service_recency.remove(&seq);
+                    if let Some(&latest) = entry.spans.last() {
+                        entry.last_seq = latest;
+                        self.service_recency.insert(latest, span.service.clone());
+                    }
+                }
+                entry.spans.is_empty()
+            }
+            None => false,
+        };
+
+        if now_empty {
+            self.remove_service(&span.service);
+            stats.evicted_services += 1;
+        }
+
+        if self.spans.is_empty() {
+            self.newest_ns = None;
+        }
+
+        self.removed.push((seq, span));
+        true
+    }
+
+    fn remove_service(&mut self, service: &str) {
+        let Some(entry) = self.service_index.remove(service) else {
+            return;
+        };
+        self.service_recency.remove(&entry.last_seq);
+        self.services.remove(entry.slot);
+        for node in &self.services[entry.slot..] {
+            if let Some(moved) = self.service_index.get_mut(&node.id) {
+                moved.slot -= 1;
+            }
+        }
+    }
+
+    fn relayout(&mut self) {
+        layout::linear(&mut self.services);
+        layout::apply_pins(&mut self.services, &self.pins);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &str, service: &str, timestamp_ns: Option<i64>) -> SpanRecord {
+        SpanRecord {
+            key: key.to_string(),
+            service: service.to_string(),
+            timestamp_ns,
+            ..Default::default()
+        }
+    }
+
+    fn service_ids(store: &SpanStore) -> Vec<&str> {
+        store.services().iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn duplicate_keys_are_skipped() {
+        let mut store = SpanStore::default();
+        store.ingest(vec![record("a", "svc-1", None), record("b", "svc-2", None)], None);
+        let stats = store.ingest(vec![record("a", "svc-1", None), record("c", "svc-1", None)], None);
+
+        assert_eq!(stats.duplicate_spans, 1);
+        assert_eq!(stats.added_spans, 1);
+        assert_eq!(stats.new_services, 0);
+        assert_eq!(store.span_count(), 3);
+        assert_eq!(service_ids(&store), ["svc-1", "svc-2"]);
+    }
+
+    #[test]
+    fn max_spans_evicts_oldest_arrivals() {
+        let mut store = SpanStore::new(RetentionPolicy {
+            max_spans: Some(2),
+            max_age_ns: None,
+            max_services: None,
+            max_jobs: None,
+        });
+        let stats = store.ingest(
+            vec![
+                record("a", "svc-1", None),
+                record("b", "svc-2", None),
+                record("c", "svc-2", None),
+            ],
+            None,
+        );
+
+        assert_eq!(stats.evicted_spans, 1);
+        assert_eq!(stats.evicted_services, 1);
+        assert_eq!(service_ids(&store), ["svc-2"]);
+        assert_eq!(store.services()[0].x, -0.15);
+    }
+
+    #[test]
+    fn max_age_is_relative_to_newest_timestamp() {
+        let mut store = SpanStore::new(RetentionPolicy {
+            max_spans: None,
+            max_age_ns: Some(100),
+            max_services: None,
+            max_jobs: None,
+        });
+        store.ingest(vec![record("a", "svc-1", Some(1_000)), record("b", "svc-2", Some(1_050))], None);
+        let stats = store.ingest(vec![record("c", "svc-2", Some(1_120)), record("d", "svc-3", Some(900))], None);
+
+        assert_eq!(stats.evicted_spans, 1);
+        assert_eq!(stats.expired_spans, 1);
+        assert_eq!(service_ids(&store), ["svc-2"]);
+    }
+
+    #[test]
+    fn max_age_from_ms_rejects_ages_no_limit_can_mean() {
+        assert_eq!(RetentionPolicy::max_age_from_ms(1.5), Some(1_500_000));
+        assert_eq!(RetentionPolicy::max_age_from_ms(0.0), Some(0));
+        assert_eq!(RetentionPolicy::max_age_from_ms(1e300), Some(i64::MAX));
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -1.0] {
+            assert_eq!(RetentionPolicy::max_age_from_ms(bad), None);
+        }
+    }
+
+    #[test]
+    fn max_services_evicts_least_recently_seen() {
+        let mut store = SpanStore::new(RetentionPolicy {
+            max_spans: None,
+            max_age_ns: None,
+            max_services: Some(2),
+            max_jobs: None,
+        });
+        store.ingest(
+            vec![
+                record("a", "svc-1", None),
+                record("b", "svc-2", None),
+                record("c", "svc-1", None),
+                record("d", "svc-3", None),
+            ],
+            None,
+        );
+
+        assert_eq!(service_ids(&store), ["svc-1", "svc-3"]);
+        assert_eq!(store.service_span_count("svc-1"), 2);
+        assert_eq!(store.span_count(), 3);
+    }
+
+    #[test]
+    fn tightening_the_policy_evicts_immediately() {
+        let mut store = SpanStore::default();
+        store.ingest((0..10).map(|i| record(&i.to_string(), "svc", None)), None);
+        let stats = store.set_policy(RetentionPolicy {
+            max_spans: Some(4),
+            ..RetentionPolicy::default()
+        });
+
+        assert_eq!(stats.evicted_spans, 6);
+        assert_eq!(store.span_count(), 4);
+    }
+
+    #[test]
+    fn removing_a_source_keeps_spans_delivered_elsewhere() {
+        let mut store = SpanStore::default();
+        store.ingest(vec![record("a", "svc-1", None), record("b", "svc-2", None)], Some(1));
+        store.ingest(vec![record("b", "svc-2", None)], Some(2));
+        store.ingest(vec![record("a", "svc-1", None)], None);
+        store.ingest(vec![record("c", "svc-3", None)], Some(1));
+
+        let stats = store.remove_source(1);
+
+        assert_eq!(stats.removed_spans, 1);
+        assert_eq!(service_ids(&store), ["svc-1", "svc-2"]);
+        assert_eq!(store.source_span_count(1), 0);
+        assert_eq!(store.source_span_count(2), 1);
+    }
+
+    #[test]
+    fn search_index_follows_ingest_and_eviction() {
+        let mut store = SpanStore::new(RetentionPolicy {
+            max_spans: Some(2),
+            ..RetentionPolicy::default()
+        });
+        let checkout = SpanRecord {
+            operation: Some("POST /checkout".to_string()),
+            ..record("a", "payments", None)
+        };
+        store.ingest(vec![checkout, record("b", "auth", None)], None);
+
+        let results = store.search("checkout", 10);
+        assert_eq!(results[0].label, "POST /checkout");
+        assert_eq!(results[0].target.service(), "payments");
+
+        store.ingest(vec![record("c", "auth", None)], None);
+        assert!(store.search("checkout", 10).is_empty());
+        assert!(store.search("payments", 10).is_empty());
+        assert_eq!(store.search("auth", 10)[0].spans, 2);
+    }
+}
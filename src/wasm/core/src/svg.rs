@@ -0,0 +1,295 @@
+// Standalone SVG export of a `Scene`.
+//
+// The scene tree maps onto nested `<g>` elements with the same transforms,
+// z-order and opacity, so the file matches what the renderer draws. Text
+// becomes real `<text>` elements that stay selectable and searchable when
+// pasted into documents. Colors are encoded to sRGB like the canvas.
+
+use std::fmt::Write;
+
+use crate::frame::{encode_srgb, Rgba};
+use crate::geometry::{text_width, CHAR_HEIGHT};
+use crate::scene::{Content, Icon, NodeId, Primitive, Scene, Style, Transform};
+
+pub fn export_svg(scene: &Scene, width: u32, height: u32) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%"{}/>"#, paint("fill", Some(scene.background)));
+
+    // Clip space (-1..1, y up) to SVG pixels (y down)
+    let (half_w, half_h) = (width as f32 / 2.0, height as f32 / 2.0);
+    let viewport = Transform([half_w, 0.0, 0.0, -half_h, half_w, half_h]);
+    let _ = writeln!(svg, r#"<g transform="{}">"#, matrix(&viewport));
+    for child in scene.ordered_children(Scene::ROOT) {
+        write_node(&mut svg, scene, child);
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+fn write_node(svg: &mut String, scene: &Scene, id: NodeId) {
+    let Some(node) = scene.node(id) else { return };
+    if !node.visible {
+        return;
+    }
+
+    let mut attributes = String::new();
+    if node.transform != Transform::IDENTITY {
+        let _ = write!(attributes, r#" transform="{}""#, matrix(&node.transform));
+    }
+    if node.style.opacity != 1.0 {
+        let _ = write!(attributes, r#" opacity="{}""#, num(node.style.opacity));
+    }
+
+    let children = scene.ordered_children(id);
+    match &node.content {
+        Content::Group => {
+            let _ = writeln!(svg, "<g{}>", attributes);
+        }
+        Content::Shape(primitive) if children.is_empty() => {
+            write_primitive(svg, primitive, &node.style, &attributes);
+            return;
+        }
+        Content::Shape(primitive) => {
+            let _ = writeln!(svg, "<g{}>", attributes);
+            write_primitive(svg, primitive, &node.style, "");
+        }
+    }
+    for child in children {
+        write_node(svg, scene, child);
+    }
+    svg.push_str("</g>\n");
+}
+
+fn write_primitive(svg: &mut String, primitive: &Primitive, style: &Style, attributes: &str) {
+    let shape_paint = format!("{}{}", paint("fill", style.fill), stroke(style));
+    let _ = match primitive {
+        Primitive::Rect { origin, size } => writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}/>"#,
+            num(origin[0]),
+            num(origin[1]),
+            num(size[0]),
+            num(size[1]),
+            shape_paint,
+            attributes
+        ),
+        Primitive::Circle { center, radius } => writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#,
+            num(center[0]),
+            num(center[1]),
+            num(*radius),
+            shape_paint,
+            attributes
+        ),
+        Primitive::Line { from, to } => writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}{}/>"#,
+            num(from[0]),
+            num(from[1]),
+            num(to[0]),
+            num(to[1]),
+            stroke(style),
+            attributes
+        ),
+        Primitive::Polyline { points, closed } => {
+            let (tag, fill) = if *closed {
+                ("polygon", paint("fill", style.fill))
+            } else {
+                ("polyline", paint("fill", None))
+            };
+            writeln!(svg, r#"<{} points="{}"{}{}{}/>"#, tag, points_attr(points), fill, stroke(style), attributes)
+        }
+        Primitive::Curve { start, segments } => {
+            let mut d = format!("M{},{}", num(start[0]), num(start[1]));
+            for [c1, c2, to] in segments {
+                let _ = write!(d, " C{}", points_attr(&[*c1, *c2, *to]));
+            }
+            writeln!(svg, r#"<path d="{}"{}{}{}/>"#, d, paint("fill", None), stroke(style), attributes)
+        }
+        Primitive::Text { text, origin, size } => {
+            // Flip back to y-down so glyphs are upright; textLength pins the
+            // advance to the bitmap font's so labels line up with the canvas
+            let scale = size / CHAR_HEIGHT;
+            writeln!(
+                svg,
+                r#"<text transform="translate({} {}) scale(1 -1)" font-family="monospace" font-size="{}" textLength="{}" lengthAdjust="spacingAndGlyphs"{}{}>{}</text>"#,
+                num(origin[0]),
+                num(origin[1]),
+                num(*size),
+                num(text_width(text) * scale),
+                paint("fill", style.fill.or(style.stroke)),
+                attributes,
+                escape(text)
+            )
+        }
+        Primitive::Icon { icon, center: [cx, cy], size } => {
+            let color = style.fill.or(style.stroke);
+            let half = size * 0.5;
+            match icon {
+                Icon::Dot => writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#,
+                    num(*cx),
+                    num(*cy),
+                    num(half),
+                    paint("fill", color),
+                    attributes
+                ),
+                Icon::Warning => writeln!(
+                    svg,
+                    r#"<polygon points="{}"{}{}/>"#,
+                    points_attr(&[[*cx, cy + half], [cx - half, cy - half], [cx + half, cy - half]]),
+                    paint("fill", color),
+                    attributes
+                ),
+                Icon::Error => {
+                    let arm = half * 0.8;
+                    writeln!(
+                        svg,
+                        r#"<path d="M{} {}L{} {}M{} {}L{} {}" fill="none" stroke-width="{}" stroke-linecap="square"{}{}/>"#,
+                        num(cx - arm),
+                        num(cy - arm),
+                        num(cx + arm),
+                        num(cy + arm),
+                        num(cx - arm),
+                        num(cy + arm),
+                        num(cx + arm),
+                        num(cy - arm),
+                        num(size * 0.2),
+                        paint("stroke", color),
+                        attributes
+                    )
+                }
+            }
+        }
+    };
+}
+
+fn stroke(style: &Style) -> String {
+    match style.stroke {
+        Some(color) => format!(
+            r#"{} stroke-width="{}" stroke-linecap="square""#,
+            paint("stroke", Some(color)),
+            num(style.stroke_width)
+        ),
+        None => String::new(),
+    }
+}
+
+// ` fill="#rrggbb"` plus an opacity attribute when translucent
+fn paint(property: &str, color: Option<Rgba>) -> String {
+    let Some([r, g, b, a]) = color else {
+        return format!(r#" {}="none""#, property);
+    };
+    let mut attribute = format!(
+        r##" {}="#{:02x}{:02x}{:02x}""##,
+        property,
+        encode_srgb(r),
+        encode_srgb(g),
+        encode_srgb(b)
+    );
+    if a < 1.0 {
+        let _ = write!(attribute, r#" {}-opacity="{}""#, property, num(a.max(0.0)));
+    }
+    attribute
+}
+
+fn matrix(transform: &Transform) -> String {
+    let values: Vec<String> = transform.0.iter().map(|&v| num(v)).collect();
+    format!("matrix({})", values.join(" "))
+}
+
+fn points_attr(points: &[[f32; 2]]) -> String {
+    let pairs: Vec<String> = points.iter().map(|[x, y]| format!("{},{}", num(*x), num(*y))).collect();
+    pairs.join(" ")
+}
+
+// Short decimal form: at most 4 places, no trailing zeros, no "-0"
+fn num(value: f32) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{service_scene, Node, TEXT_COLOR};
+    use crate::labels::Labels;
+    use crate::theme::Theme;
+    use crate::ServiceNode;
+
+    #[test]
+    fn service_view_exports_real_text() {
+        let scene = service_scene(&ServiceNode {
+            id: "job-<api>&co".to_string(),
+            x: 0.0,
+            y: 0.0,
+            status: "active".to_string(),
+        }, &Labels::default(), &Theme::default());
+        let svg = export_svg(&scene, 1600, 900);
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="1600" height="900""#));
+        assert!(svg.contains(r#"<g transform="matrix(800 0 0 -450 800 450)">"#));
+        assert!(svg.contains(">&lt;api&gt;&amp;co</text>"));
+        assert!(svg.contains(r##"fill="#ffe77c""##));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn groups_keep_transform_opacity_and_draw_order() {
+        let mut scene = Scene::default();
+        let group = scene.insert(
+            Scene::ROOT,
+            Node::group().transform(Transform::translate(0.5, 0.0)).z(2),
+        );
+        scene.node_mut(group).unwrap().style.opacity = 0.5;
+        scene.insert(
+            group,
+            Node::shape(Primitive::Circle { center: [0.0, 0.0], radius: 0.25 }, Style::fill(TEXT_COLOR)),
+        );
+        scene.add(
+            Primitive::Polyline { points: vec![[0.0, 0.0], [1.0, 0.5]], closed: false },
+            Style::stroke([1.0, 1.0, 1.0, 0.5], 0.02),
+        );
+        let svg = export_svg(&scene, 200, 100);
+
+        let polyline = svg.find("<polyline").unwrap();
+        let group = svg.find(r#"<g transform="matrix(1 0 0 1 0.5 0)" opacity="0.5">"#).unwrap();
+        assert!(polyline < group);
+        assert!(svg.contains(r##"points="0,0 1,0.5" fill="none" stroke="#ffffff" stroke-opacity="0.5" stroke-width="0.02""##));
+        assert!(svg.contains(r#"<circle cx="0" cy="0" r="0.25""#));
+    }
+
+    #[test]
+    fn numbers_are_compact() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(-0.00001), "0");
+        assert_eq!(num(0.12345), "0.1235");
+    }
+}
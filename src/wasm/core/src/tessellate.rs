@@ -0,0 +1,235 @@
+// Lowers a `Scene` to a triangle-list `Frame`.
+//
+// Fills draw before strokes. Strokes are one quad per segment with square
+// caps, so joins are covered but translucent strokes overlap at corners.
+
+use std::f32::consts::TAU;
+
+use crate::frame::{Frame, Rgba};
+use crate::geometry::{create_text_geometry, CHAR_HEIGHT};
+use crate::scene::{DrawItem, Icon, Primitive, Scene, Transform};
+
+const CIRCLE_SEGMENTS: usize = 48;
+// Straight segments each cubic of a curve is flattened into
+const CURVE_STEPS: usize = 16;
+
+pub fn tessellate(scene: &Scene) -> Frame {
+    let mut frame = Frame::new(scene.background);
+    for item in scene.draw_list() {
+        tessellate_item(&mut frame, &item);
+    }
+    frame
+}
+
+fn with_opacity(color: Rgba, opacity: f32) -> Rgba {
+    [color[0], color[1], color[2], color[3] * opacity]
+}
+
+fn tessellate_item(frame: &mut Frame, item: &DrawItem) {
+    let fill = item.style.fill.map(|c| with_opacity(c, item.style.opacity));
+    let stroke = item.style.stroke.map(|c| with_opacity(c, item.style.opacity));
+    let width = item.style.stroke_width;
+    let mut out = Output { frame, transform: item.transform };
+
+    match item.primitive {
+        Primitive::Rect { origin: [x, y], size: [w, h] } => {
+            let outline = [[*x, *y], [x + w, *y], [x + w, y + h], [*x, y + h]];
+            if let Some(color) = fill {
+                out.convex(&outline, color);
+            }
+            if let Some(color) = stroke {
+                out.polyline(&outline, true, width, color);
+            }
+        }
+        Primitive::Circle { center, radius } => {
+            let outline = circle_points(*center, *radius);
+            if let Some(color) = fill {
+                out.convex(&outline, color);
+            }
+            if let Some(color) = stroke {
+                out.polyline(&outline, true, width, color);
+            }
+        }
+        Primitive::Line { from, to } => {
+            if let Some(color) = stroke {
+                out.segment(*from, *to, width, color);
+            }
+        }
+        Primitive::Polyline { points, closed } => {
+            if let (Some(color), true) = (fill, *closed) {
+                out.convex(points, color);
+            }
+            if let Some(color) = stroke {
+                out.polyline(points, *closed, width, color);
+            }
+        }
+        Primitive::Curve { start, segments } => {
+            if let Some(color) = stroke {
+                out.polyline(&curve_points(*start, segments), false, width, color);
+            }
+        }
+        Primitive::Text { text, origin, size } => {
+            if let Some(color) = fill.or(stroke) {
+                let scale = size / CHAR_HEIGHT;
+                let local = Transform::scale(scale, scale).then(&Transform::translate(origin[0], origin[1]));
+                out.triangles(&create_text_geometry(text, 0.0, 0.0), &local, color);
+            }
+        }
+        Primitive::Icon { icon, center, size } => {
+            if let Some(color) = fill.or(stroke) {
+                out.icon(*icon, *center, *size, color);
+            }
+        }
+    }
+}
+
+fn circle_points(center: [f32; 2], radius: f32) -> Vec<[f32; 2]> {
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            [center[0] + radius * angle.cos(), center[1] + radius * angle.sin()]
+        })
+        .collect()
+}
+
+fn curve_points(start: [f32; 2], segments: &[[[f32; 2]; 3]]) -> Vec<[f32; 2]> {
+    let mut points = vec![start];
+    let mut from = start;
+    for &[c1, c2, to] in segments {
+        points.extend((1..=CURVE_STEPS).map(|step| {
+            let t = step as f32 / CURVE_STEPS as f32;
+            let u = 1.0 - t;
+            let weights = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+            [0, 1].map(|axis| {
+                weights[0] * from[axis] + weights[1] * c1[axis] + weights[2] * c2[axis] + weights[3] * to[axis]
+            })
+        }));
+        from = to;
+    }
+    points
+}
+
+// Emits triangles through the item's transform
+struct Output<'a> {
+    frame: &'a mut Frame,
+    transform: Transform,
+}
+
+impl Output<'_> {
+    fn triangle(&mut self, points: [[f32; 2]; 3], color: Rgba) {
+        let flat: Vec<f32> = points
+            .iter()
+            .flat_map(|&p| self.transform.apply(p))
+            .collect();
+        self.frame.push_triangles(&flat, color);
+    }
+
+    fn triangles(&mut self, positions: &[f32], local: &Transform, color: Rgba) {
+        let transform = local.then(&self.transform);
+        let flat: Vec<f32> = positions
+            .chunks_exact(2)
+            .flat_map(|p| transform.apply([p[0], p[1]]))
+            .collect();
+        self.frame.push_triangles(&flat, color);
+    }
+
+    // Triangle fan from the first point
+    fn convex(&mut self, outline: &[[f32; 2]], color: Rgba) {
+        for pair in outline.get(1..).unwrap_or_default().windows(2) {
+            self.triangle([outline[0], pair[0], pair[1]], color);
+        }
+    }
+
+    fn segment(&mut self, from: [f32; 2], to: [f32; 2], width: f32, color: Rgba) {
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 || width <= 0.0 {
+            return;
+        }
+        let half = width * 0.5;
+        let (ux, uy) = (dx / length * half, dy / length * half);
+        let (nx, ny) = (-uy, ux);
+
+        let a = [from[0] - ux + nx, from[1] - uy + ny];
+        let b = [from[0] - ux - nx, from[1] - uy - ny];
+        let c = [to[0] + ux - nx, to[1] + uy - ny];
+        let d = [to[0] + ux + nx, to[1] + uy + ny];
+        self.triangle([a, b, c], color);
+        self.triangle([a, c, d], color);
+    }
+
+    fn polyline(&mut self, points: &[[f32; 2]], closed: bool, width: f32, color: Rgba) {
+        for pair in points.windows(2) {
+            self.segment(pair[0], pair[1], width, color);
+        }
+        if let (true, [first, .., last]) = (closed, points) {
+            self.segment(*last, *first, width, color);
+        }
+    }
+
+    fn icon(&mut self, icon: Icon, [cx, cy]: [f32; 2], size: f32, color: Rgba) {
+        let half = size * 0.5;
+        match icon {
+            Icon::Dot => self.convex(&circle_points([cx, cy], half), color),
+            Icon::Warning => self.triangle([[cx, cy + half], [cx - half, cy - half], [cx + half, cy - half]], color),
+            Icon::Error => {
+                let arm = half * 0.8;
+                self.segment([cx - arm, cy - arm], [cx + arm, cy + arm], size * 0.2, color);
+                self.segment([cx - arm, cy + arm], [cx + arm, cy - arm], size * 0.2, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Node, Style};
+
+    #[test]
+    fn filled_and_stroked_rect_emits_fill_then_four_sides() {
+        let mut scene = Scene::default();
+        scene.add(
+            Primitive::Rect { origin: [0.0, 0.0], size: [1.0, 1.0] },
+            Style::fill([1.0, 0.0, 0.0, 1.0]).with_stroke([0.0, 1.0, 0.0, 1.0], 0.1),
+        );
+        let frame = tessellate(&scene);
+
+        assert_eq!(frame.vertices.len(), 6 + 4 * 6);
+        assert_eq!(frame.vertices[0].color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(frame.vertices[6].color, [0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn text_matches_the_bitmap_font_at_its_origin() {
+        let mut scene = Scene::default();
+        let group = scene.insert(Scene::ROOT, Node::group().transform(Transform::translate(0.25, 0.0)));
+        scene.insert(
+            group,
+            Node::shape(
+                Primitive::Text { text: "HI".to_string(), origin: [-0.5, 0.1], size: CHAR_HEIGHT },
+                Style::fill([1.0; 4]),
+            ),
+        );
+        let frame = tessellate(&scene);
+        let expected = create_text_geometry("HI", -0.25, 0.1);
+
+        let positions: Vec<f32> = frame.vertices.iter().flat_map(|v| v.position).collect();
+        assert_eq!(positions.len(), expected.len());
+        assert!(positions.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+
+    #[test]
+    fn opacity_scales_alpha_and_unstyled_shapes_draw_nothing() {
+        let mut scene = Scene::default();
+        scene.add(
+            Primitive::Icon { icon: Icon::Warning, center: [0.0, 0.0], size: 0.2 },
+            Style::fill([1.0; 4]).with_opacity(0.25),
+        );
+        scene.add(Primitive::Line { from: [0.0, 0.0], to: [1.0, 0.0] }, Style::default());
+        let frame = tessellate(&scene);
+
+        assert_eq!(frame.vertices.len(), 3);
+        assert_eq!(frame.vertices[0].color[3], 0.25);
+    }
+}
@@ -0,0 +1,246 @@
+// Color themes for every view.
+//
+// A theme names a color for each role the views draw: the background, text,
+// highlights, map nodes by status, edges and the grid of structure behind
+// the data, plus the diff, trace and annotation colors. `dark` is the
+// palette the viewer has always used. `light` suits bright rooms and print,
+// and the deuteranopia and protanopia palettes draw on the Okabe-Ito set so
+// that status, diff direction and the critical path never rely on telling
+// red from green. Custom themes deserialize over the dark palette, so they
+// only need the colors they change.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::annotations::{INCIDENT_COLOR, NOTE_COLOR};
+use crate::diff::{ADDED_COLOR, BETTER_COLOR, REMOVED_COLOR, UNCHANGED_COLOR, WORSE_COLOR};
+use crate::frame::Rgba;
+use crate::map::{EDGE_COLOR, HULL_COLOR, NODE_COLOR, PIN_COLOR};
+use crate::scene::{BACKGROUND, ERROR_COLOR, HIGHLIGHT_COLOR, TEXT_COLOR, WARNING_COLOR};
+use crate::waterfall::{BAR_COLOR, CRITICAL_COLOR, ERROR_BAR_COLOR};
+
+/// Names `Theme::named` knows.
+pub const THEMES: [&str; 4] = ["dark", "light", "deuteranopia", "protanopia"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusColors {
+    pub healthy: Rgba,
+    pub warning: Rgba,
+    pub error: Rgba,
+}
+
+impl Default for StatusColors {
+    fn default() -> Self {
+        Self { healthy: NODE_COLOR, warning: WARNING_COLOR, error: ERROR_COLOR }
+    }
+}
+
+impl StatusColors {
+    pub fn of(&self, status: &str) -> Rgba {
+        match status {
+            "warning" => self.warning,
+            "error" => self.error,
+            _ => self.healthy,
+        }
+    }
+}
+
+/// Diff shading: changed services and edges shade from `unchanged` toward
+/// `worse` or `better`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffColors {
+    pub added: Rgba,
+    pub removed: Rgba,
+    pub unchanged: Rgba,
+    pub worse: Rgba,
+    pub better: Rgba,
+}
+
+impl Default for DiffColors {
+    fn default() -> Self {
+        Self {
+            added: ADDED_COLOR,
+            removed: REMOVED_COLOR,
+            unchanged: UNCHANGED_COLOR,
+            worse: WORSE_COLOR,
+            better: BETTER_COLOR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceColors {
+    pub bar: Rgba,
+    pub error_bar: Rgba,
+    pub critical: Rgba,
+}
+
+impl Default for TraceColors {
+    fn default() -> Self {
+        Self { bar: BAR_COLOR, error_bar: ERROR_BAR_COLOR, critical: CRITICAL_COLOR }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub background: Rgba,
+    pub text: Rgba,
+    /// Search highlights, deploy markers and added services in a diff.
+    pub highlight: Rgba,
+    /// Map node fill and status icons.
+    pub node: StatusColors,
+    pub edge: Rgba,
+    /// Structure behind the data, such as group hulls.
+    pub grid: Rgba,
+    /// Ring around pinned services.
+    pub pin: Rgba,
+    pub diff: DiffColors,
+    pub trace: TraceColors,
+    pub incident: Rgba,
+    pub note: Rgba,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTheme(pub String);
+
+impl fmt::Display for UnknownTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown theme: {} (expected one of {})", self.0, THEMES.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownTheme {}
+
+// Okabe-Ito colors shared by the colorblind-safe palettes
+const ORANGE: Rgba = [0.9, 0.62, 0.0, 1.0];
+const SKY_BLUE: Rgba = [0.34, 0.71, 0.91, 1.0];
+const YELLOW: Rgba = [0.94, 0.89, 0.26, 1.0];
+const VERMILLION: Rgba = [0.84, 0.37, 0.0, 1.0];
+const REDDISH_PURPLE: Rgba = [0.8, 0.47, 0.65, 1.0];
+
+impl Theme {
+    pub fn named(name: &str) -> Result<Self, UnknownTheme> {
+        match name {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            "deuteranopia" => Ok(Self::deuteranopia()),
+            "protanopia" => Ok(Self::protanopia()),
+            _ => Err(UnknownTheme(name.to_string())),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            background: BACKGROUND,
+            text: TEXT_COLOR,
+            highlight: HIGHLIGHT_COLOR,
+            node: StatusColors::default(),
+            edge: EDGE_COLOR,
+            grid: HULL_COLOR,
+            pin: PIN_COLOR,
+            diff: DiffColors::default(),
+            trace: TraceColors::default(),
+            incident: INCIDENT_COLOR,
+            note: NOTE_COLOR,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: [0.97, 0.97, 0.95, 1.0],
+            text: [0.15, 0.15, 0.2, 1.0],
+            highlight: [0.1, 0.4, 0.85, 1.0],
+            node: StatusColors {
+                healthy: [0.35, 0.42, 0.65, 1.0],
+                warning: [0.85, 0.5, 0.0, 1.0],
+                error: [0.85, 0.15, 0.15, 1.0],
+            },
+            edge: [0.6, 0.6, 0.68, 1.0],
+            grid: [0.7, 0.74, 0.86, 1.0],
+            pin: [0.1, 0.1, 0.1, 1.0],
+            diff: DiffColors {
+                added: [0.1, 0.4, 0.85, 1.0],
+                removed: [0.6, 0.6, 0.65, 1.0],
+                unchanged: [0.7, 0.7, 0.75, 1.0],
+                worse: [0.85, 0.15, 0.15, 1.0],
+                better: [0.1, 0.65, 0.3, 1.0],
+            },
+            trace: TraceColors {
+                bar: [0.7, 0.75, 0.88, 1.0],
+                error_bar: [0.92, 0.62, 0.62, 1.0],
+                critical: [0.9, 0.3, 0.1, 1.0],
+            },
+            incident: [0.75, 0.1, 0.5, 1.0],
+            note: [0.45, 0.45, 0.5, 1.0],
+        }
+    }
+
+    /// Status and diff direction by blue against yellow and orange, which
+    /// stay apart without green-sensitive cones.
+    pub fn deuteranopia() -> Self {
+        let dark = Self::dark();
+        Self {
+            highlight: SKY_BLUE,
+            node: StatusColors { warning: YELLOW, error: VERMILLION, ..dark.node },
+            diff: DiffColors { added: REDDISH_PURPLE, worse: ORANGE, better: SKY_BLUE, ..dark.diff },
+            trace: TraceColors { error_bar: [0.5, 0.25, 0.05, 1.0], critical: ORANGE, ..dark.trace },
+            incident: REDDISH_PURPLE,
+            ..dark
+        }
+    }
+
+    /// Like the deuteranopia palette, but errors are a bright orange because
+    /// reds look dark without red-sensitive cones.
+    pub fn protanopia() -> Self {
+        let deuteranopia = Self::deuteranopia();
+        Self {
+            node: StatusColors { error: ORANGE, ..deuteranopia.node },
+            trace: TraceColors { error_bar: [0.5, 0.35, 0.05, 1.0], critical: YELLOW, ..deuteranopia.trace },
+            ..deuteranopia
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_named_theme_resolves() {
+        for name in THEMES {
+            assert!(Theme::named(name).is_ok(), "{}", name);
+        }
+        assert_eq!(Theme::default(), Theme::named("dark").unwrap());
+        let unknown = Theme::named("sepia").unwrap_err().to_string();
+        assert_eq!(unknown, "Unknown theme: sepia (expected one of dark, light, deuteranopia, protanopia)");
+    }
+
+    #[test]
+    fn custom_themes_only_need_the_colors_they_change() {
+        let json = r#"{ "background": [1, 1, 1, 1], "node": { "error": [0, 0, 1, 1] } }"#;
+        let theme: Theme = serde_json::from_str(json).unwrap();
+        assert_eq!(theme.background, [1.0; 4]);
+        assert_eq!(theme.node.error, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!((theme.node.warning, theme.text), (WARNING_COLOR, TEXT_COLOR));
+    }
+
+    #[test]
+    fn colorblind_palettes_avoid_red_green_pairs() {
+        for theme in [Theme::deuteranopia(), Theme::protanopia()] {
+            // Worse and better differ in the blue channel, not just red against green
+            assert!((theme.diff.better[2] - theme.diff.worse[2]).abs() > 0.5);
+            assert_ne!(theme.node.error, ERROR_COLOR);
+        }
+    }
+}
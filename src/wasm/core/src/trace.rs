@@ -0,0 +1,360 @@
+// Traces and their critical path.
+//
+// Spans carrying a `trace_id` and `parent_span_id` form one tree per trace.
+// The critical path is the chain of work that determined the trace's
+// end-to-end latency. Walking back from the root's end, each step follows
+// the child that finished last and then resumes from that child's start, so
+// of several concurrent children only the one the parent waited for is on
+// the path. Time in a span that no such child covers is the span's own work.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::Serialize;
+
+/// Traces listed when the caller does not ask for a limit.
+pub const DEFAULT_TRACE_LIMIT: usize = 50;
+
+/// A span with the timing and parentage needed to place it in its trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceSpan {
+    pub span_id: String,
+    pub parent_id: Option<String>,
+    pub service: String,
+    pub operation: Option<String>,
+    pub start_ns: i64,
+    pub duration_ns: i64,
+    pub status: Option<String>,
+}
+
+impl TraceSpan {
+    pub fn end_ns(&self) -> i64 {
+        self.start_ns.saturating_add(self.duration_ns.max(0))
+    }
+}
+
+/// One stretch of the critical path spent in a single span.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathSegment {
+    pub span_id: String,
+    pub service: String,
+    pub operation: Option<String>,
+    pub start_ns: i64,
+    pub end_ns: i64,
+}
+
+/// Time a service spent on the critical path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceShare {
+    pub service: String,
+    pub time_ns: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CriticalPath {
+    pub trace_id: String,
+    pub root_span_id: String,
+    pub start_ns: i64,
+    pub duration_ns: i64,
+    /// Path segments in time order; they tile the root span.
+    pub segments: Vec<PathSegment>,
+    /// Every span with at least one segment, in order of first appearance.
+    pub span_ids: Vec<String>,
+    /// Services by time on the path, largest first.
+    pub services: Vec<ServiceShare>,
+}
+
+impl CriticalPath {
+    pub fn contains_span(&self, span_id: &str) -> bool {
+        self.span_ids.iter().any(|id| id == span_id)
+    }
+}
+
+/// Summary row returned by `list_traces`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceSummary {
+    pub trace_id: String,
+    pub span_count: usize,
+    pub root_service: String,
+    pub start_ns: i64,
+    pub duration_ns: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceError {
+    /// No span with timing belongs to the trace.
+    UnknownTrace(String),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::UnknownTrace(trace_id) => {
+                write!(f, "No timed spans found for trace '{}'", trace_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+// Parent/child links of one trace, by index into its span slice
+struct Tree {
+    roots: Vec<usize>,
+    children: Vec<Vec<usize>>,
+}
+
+impl Tree {
+    // Spans whose parent is missing from the trace are treated as roots
+    fn new(spans: &[TraceSpan]) -> Self {
+        let index: HashMap<&str, usize> = spans
+            .iter()
+            .enumerate()
+            .map(|(i, span)| (span.span_id.as_str(), i))
+            .collect();
+        let mut tree = Tree {
+            roots: Vec::new(),
+            children: vec![Vec::new(); spans.len()],
+        };
+        for (i, span) in spans.iter().enumerate() {
+            match span.parent_id.as_deref().and_then(|parent| index.get(parent)) {
+                Some(&parent) if parent != i => tree.children[parent].push(i),
+                _ => tree.roots.push(i),
+            }
+        }
+        tree
+    }
+
+    // The root that ended last bounds the trace's latency
+    fn main_root(&self, spans: &[TraceSpan]) -> Option<usize> {
+        self.roots.iter().copied().max_by(|&a, &b| {
+            spans[a]
+                .end_ns()
+                .cmp(&spans[b].end_ns())
+                .then(spans[b].start_ns.cmp(&spans[a].start_ns))
+        })
+    }
+}
+
+/// Compute the critical path of one trace's spans.
+pub fn critical_path(trace_id: &str, spans: &[TraceSpan]) -> Result<CriticalPath, TraceError> {
+    let tree = Tree::new(spans);
+    let root = tree
+        .main_root(spans)
+        .ok_or_else(|| TraceError::UnknownTrace(trace_id.to_string()))?;
+
+    // Latest-ending children first
+    let mut children = tree.children;
+    for kids in &mut children {
+        kids.sort_by(|&a, &b| spans[b].end_ns().cmp(&spans[a].end_ns()).then(a.cmp(&b)));
+    }
+
+    struct Step {
+        span: usize,
+        cursor: i64,
+        next_child: usize,
+    }
+
+    // Explicit stack so deep traces cannot overflow the wasm stack.
+    // Segments come out newest first.
+    let mut segments: Vec<PathSegment> = Vec::new();
+    let segment = |span: &TraceSpan, start_ns: i64, end_ns: i64| PathSegment {
+        span_id: span.span_id.clone(),
+        service: span.service.clone(),
+        operation: span.operation.clone(),
+        start_ns,
+        end_ns,
+    };
+    let mut stack = vec![Step { span: root, cursor: spans[root].end_ns(), next_child: 0 }];
+    while let Some(step) = stack.last_mut() {
+        let span = &spans[step.span];
+        let kids = &children[step.span];
+        let mut descend = None;
+        while let Some(&child_index) = kids.get(step.next_child) {
+            step.next_child += 1;
+            let child = &spans[child_index];
+            if child.start_ns >= step.cursor || child.end_ns() <= span.start_ns {
+                continue;
+            }
+            let child_end = child.end_ns().min(step.cursor);
+            if child_end < step.cursor {
+                segments.push(segment(span, child_end, step.cursor));
+            }
+            step.cursor = child.start_ns.max(span.start_ns);
+            descend = Some(Step { span: child_index, cursor: child_end, next_child: 0 });
+            break;
+        }
+        match descend {
+            Some(next) => stack.push(next),
+            None => {
+                if step.cursor > span.start_ns {
+                    segments.push(segment(span, span.start_ns, step.cursor));
+                }
+                stack.pop();
+            }
+        }
+    }
+    segments.reverse();
+
+    let mut span_ids: Vec<String> = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut shares: Vec<ServiceShare> = Vec::new();
+    for segment in &segments {
+        if seen.insert(&segment.span_id) {
+            span_ids.push(segment.span_id.clone());
+        }
+        let time_ns = segment.end_ns - segment.start_ns;
+        match shares.iter_mut().find(|share| share.service == segment.service) {
+            Some(share) => share.time_ns += time_ns,
+            None => shares.push(ServiceShare { service: segment.service.clone(), time_ns }),
+        }
+    }
+    shares.sort_by_key(|share| std::cmp::Reverse(share.time_ns));
+
+    let root = &spans[root];
+    Ok(CriticalPath {
+        trace_id: trace_id.to_string(),
+        root_span_id: root.span_id.clone(),
+        start_ns: root.start_ns,
+        duration_ns: root.end_ns() - root.start_ns,
+        segments,
+        span_ids,
+        services: shares,
+    })
+}
+
+/// Spans in waterfall order: depth first from each root, siblings by start
+/// time. Returns `(index into spans, depth)` pairs.
+pub fn tree_order(spans: &[TraceSpan]) -> Vec<(usize, usize)> {
+    let tree = Tree::new(spans);
+    let by_start = |ids: &mut Vec<usize>| {
+        ids.sort_by(|&a, &b| spans[a].start_ns.cmp(&spans[b].start_ns).then(a.cmp(&b)));
+    };
+
+    let mut order = Vec::with_capacity(spans.len());
+    let mut roots = tree.roots;
+    by_start(&mut roots);
+    let mut children = tree.children;
+    let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|root| (root, 0)).collect();
+    while let Some((span, depth)) = stack.pop() {
+        order.push((span, depth));
+        let mut kids = std::mem::take(&mut children[span]);
+        by_start(&mut kids);
+        stack.extend(kids.into_iter().rev().map(|kid| (kid, depth + 1)));
+    }
+    order
+}
+
+/// Summarize a trace for `list_traces`.
+pub fn summarize(trace_id: &str, spans: &[TraceSpan]) -> Option<TraceSummary> {
+    let tree = Tree::new(spans);
+    let root = &spans[tree.main_root(spans)?];
+    let start_ns = spans.iter().map(|span| span.start_ns).min()?;
+    let end_ns = spans.iter().map(TraceSpan::end_ns).max()?;
+    Some(TraceSummary {
+        trace_id: trace_id.to_string(),
+        span_count: spans.len(),
+        root_service: root.service.clone(),
+        start_ns,
+        duration_ns: end_ns - start_ns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: &str, parent: Option<&str>, service: &str, start_ns: i64, duration_ns: i64) -> TraceSpan {
+        TraceSpan {
+            span_id: id.to_string(),
+            parent_id: parent.map(str::to_string),
+            service: service.to_string(),
+            operation: None,
+            start_ns,
+            duration_ns,
+            status: None,
+        }
+    }
+
+    fn path_of(path: &CriticalPath) -> Vec<(&str, i64, i64)> {
+        path.segments
+            .iter()
+            .map(|s| (s.span_id.as_str(), s.start_ns, s.end_ns))
+            .collect()
+    }
+
+    #[test]
+    fn follows_the_child_the_parent_waited_for() {
+        // root 0..100 fans out to a (10..40) and b (10..70) concurrently,
+        // then calls c (75..95). b calls d (20..60).
+        let spans = [
+            span("root", None, "gateway", 0, 100),
+            span("a", Some("root"), "auth", 10, 30),
+            span("b", Some("root"), "cart", 10, 60),
+            span("c", Some("root"), "payments", 75, 20),
+            span("d", Some("b"), "db", 20, 40),
+        ];
+        let path = critical_path("t1", &spans).unwrap();
+
+        assert_eq!(
+            path_of(&path),
+            [
+                ("root", 0, 10),
+                ("b", 10, 20),
+                ("d", 20, 60),
+                ("b", 60, 70),
+                ("root", 70, 75),
+                ("c", 75, 95),
+                ("root", 95, 100),
+            ]
+        );
+        assert_eq!(path.span_ids, ["root", "b", "d", "c"]);
+        assert!(!path.contains_span("a"));
+        assert_eq!(path.duration_ns, 100);
+        assert_eq!(path.services[0], ServiceShare { service: "db".to_string(), time_ns: 40 });
+        let total: i64 = path.services.iter().map(|s| s.time_ns).sum();
+        assert_eq!(total, 100);
+    }
+
+    #[test]
+    fn children_running_past_the_parent_are_clipped() {
+        // An async child outliving its parent only counts up to the parent's end
+        let spans = [
+            span("root", None, "gateway", 0, 50),
+            span("late", Some("root"), "queue", 30, 100),
+            span("orphan", Some("missing"), "batch", 5, 10),
+        ];
+        let path = critical_path("t2", &spans).unwrap();
+
+        assert_eq!(path_of(&path), [("root", 0, 30), ("late", 30, 50)]);
+        assert_eq!(critical_path("t3", &[]), Err(TraceError::UnknownTrace("t3".to_string())));
+    }
+
+    #[test]
+    fn deep_chains_do_not_recurse() {
+        let ids: Vec<String> = (0..50_000).map(|i| i.to_string()).collect();
+        let spans: Vec<TraceSpan> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| span(id, i.checked_sub(1).map(|p| ids[p].as_str()), "svc", i as i64, 100_000 - 2 * i as i64))
+            .collect();
+
+        let path = critical_path("deep", &spans).unwrap();
+        assert_eq!(path.span_ids.len(), 50_000);
+        assert_eq!(tree_order(&spans).last(), Some(&(49_999, 49_999)));
+    }
+
+    #[test]
+    fn tree_order_is_depth_first_by_start() {
+        let spans = [
+            span("late", Some("root"), "b", 50, 10),
+            span("root", None, "a", 0, 100),
+            span("early", Some("root"), "c", 10, 10),
+            span("leaf", Some("early"), "d", 12, 5),
+        ];
+        assert_eq!(tree_order(&spans), [(1, 0), (2, 1), (3, 2), (0, 1)]);
+
+        let summary = summarize("t", &spans).unwrap();
+        assert_eq!((summary.root_service.as_str(), summary.duration_ns, summary.span_count), ("a", 100, 4));
+    }
+}
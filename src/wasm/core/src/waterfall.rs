@@ -0,0 +1,224 @@
+// The trace waterfall: one row per span in tree order, bars on a shared
+// time axis, with the critical path drawn over the bars it runs through and
+// annotations inside the trace as vertical markers. An active filter fades
+// or drops the rows of spans it does not match.
+
+use std::collections::HashMap;
+
+use crate::annotations::Annotation;
+use crate::filter::SpanMatch;
+use crate::frame::Rgba;
+use crate::geometry::{text_width, CHAR_HEIGHT};
+use crate::labels::Labels;
+use crate::scene::{Node, Primitive, Scene, Style, DIMMED_OPACITY};
+use crate::theme::Theme;
+use crate::trace::{self, CriticalPath, TraceSpan};
+
+pub const BAR_COLOR: Rgba = [0.3, 0.35, 0.5, 1.0];
+pub const ERROR_BAR_COLOR: Rgba = [0.55, 0.2, 0.2, 1.0];
+pub const CRITICAL_COLOR: Rgba = [1.0, 0.35, 0.2, 1.0];
+
+/// Rows drawn before the waterfall is cut off.
+pub const MAX_ROWS: usize = 64;
+
+const LEFT: f32 = -0.95;
+const RIGHT: f32 = 0.95;
+const TOP: f32 = 0.75;
+const BOTTOM: f32 = -0.95;
+// Bars never get thinner than this, so instant spans stay visible
+const MIN_BAR_WIDTH: f32 = 0.004;
+const MARKER_WIDTH: f32 = 0.006;
+
+/// Draw `spans` as a waterfall, overlaying `path` when given and marking
+/// the `annotations` that fall inside the trace. The header names the root
+/// service and the trace's duration. With a `filter`, spans it does not
+/// match are faded, or left out of the rows in hide mode; the time axis
+/// still spans the whole trace.
+pub fn waterfall_scene(
+    spans: &[TraceSpan],
+    path: Option<&CriticalPath>,
+    annotations: &[Annotation],
+    filter: Option<&SpanMatch>,
+    labels: &Labels,
+    theme: &Theme,
+) -> Scene {
+    let mut scene = Scene::new(theme.background);
+    let mut order = trace::tree_order(spans);
+    order.retain(|&(index, _)| filter.is_none_or(|f| f.shows(&spans[index].span_id)));
+    let Some(summary) = trace::summarize("", spans) else {
+        return scene;
+    };
+
+    let header = format!(
+        "{} {}MS",
+        labels.label(&summary.root_service),
+        format_ms(summary.duration_ns)
+    );
+    scene.add(
+        Primitive::Text { text: header, origin: [LEFT, TOP + 0.05], size: CHAR_HEIGHT * 0.8 },
+        Style::fill(theme.text),
+    );
+
+    let rows = order.len().min(MAX_ROWS);
+    let row_height = ((TOP - BOTTOM) / rows as f32).min(CHAR_HEIGHT);
+    let bar_height = row_height * 0.8;
+    let label_size = (bar_height * 0.7).min(CHAR_HEIGHT * 0.5);
+    let x_scale = (RIGHT - LEFT) / summary.duration_ns.max(1) as f32;
+    let x = |ns: i64| LEFT + (ns - summary.start_ns) as f32 * x_scale;
+
+    let mut row_of: HashMap<&str, f32> = HashMap::new();
+    for (row, &(index, _)) in order.iter().take(rows).enumerate() {
+        let span = &spans[index];
+        let y = TOP - (row + 1) as f32 * row_height;
+        row_of.insert(&span.span_id, y);
+
+        let x0 = x(span.start_ns);
+        let width = (x(span.end_ns()) - x0).max(MIN_BAR_WIDTH);
+        let color = match span.status.as_deref() {
+            Some(status) if status.eq_ignore_ascii_case("error") => theme.trace.error_bar,
+            _ => theme.trace.bar,
+        };
+        let opacity = if filter.is_some_and(|f| f.dims(&span.span_id)) { DIMMED_OPACITY } else { 1.0 };
+        scene.add(
+            Primitive::Rect { origin: [x0, y], size: [width, bar_height] },
+            Style::fill(color).with_opacity(opacity),
+        );
+
+        // Labels start at their bar but stay on screen
+        let label = labels.label(&span.service);
+        let label_x = x0.min(RIGHT - text_width(&label) * label_size / CHAR_HEIGHT).max(LEFT);
+        let label = Primitive::Text { text: label, origin: [label_x, y + bar_height * 0.15], size: label_size };
+        scene.insert(Scene::ROOT, Node::shape(label, Style::fill(theme.text).with_opacity(opacity)).z(2));
+    }
+
+    for segment in path.map(|path| path.segments.as_slice()).unwrap_or_default() {
+        let Some(&y) = row_of.get(segment.span_id.as_str()) else { continue };
+        let x0 = x(segment.start_ns);
+        let width = (x(segment.end_ns) - x0).max(MIN_BAR_WIDTH);
+        let bar = Primitive::Rect { origin: [x0, y], size: [width, bar_height] };
+        let opacity = if filter.is_some_and(|f| f.dims(&segment.span_id)) { DIMMED_OPACITY } else { 1.0 };
+        scene.insert(Scene::ROOT, Node::shape(bar, Style::fill(theme.trace.critical).with_opacity(opacity)).z(1));
+    }
+
+    let bottom = TOP - rows as f32 * row_height;
+    let end_ns = summary.start_ns + summary.duration_ns;
+    for annotation in annotations {
+        if annotation.timestamp_ns < summary.start_ns || annotation.timestamp_ns > end_ns {
+            continue;
+        }
+        let x0 = x(annotation.timestamp_ns).min(RIGHT - MARKER_WIDTH);
+        let marker = Primitive::Rect { origin: [x0, bottom], size: [MARKER_WIDTH, TOP - bottom] };
+        scene.insert(Scene::ROOT, Node::shape(marker, Style::fill(annotation.color(theme))).z(3));
+    }
+
+    scene
+}
+
+// Milliseconds with one decimal below ten
+fn format_ms(ns: i64) -> String {
+    let ms = ns as f64 / 1_000_000.0;
+    if ms < 10.0 {
+        format!("{:.1}", ms)
+    } else {
+        format!("{:.0}", ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterMode;
+
+    fn span(id: &str, parent: Option<&str>, start_ns: i64, duration_ns: i64) -> TraceSpan {
+        TraceSpan {
+            span_id: id.to_string(),
+            parent_id: parent.map(str::to_string),
+            service: format!("svc-{}", id),
+            operation: None,
+            start_ns,
+            duration_ns,
+            status: None,
+        }
+    }
+
+    fn fills(scene: &Scene, color: Rgba) -> Vec<[f32; 2]> {
+        scene
+            .draw_list()
+            .iter()
+            .filter(|item| item.style.fill == Some(color))
+            .filter_map(|item| match item.primitive {
+                Primitive::Rect { size, .. } => Some(*size),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn critical_segments_overlay_their_rows() {
+        let spans = [
+            span("root", None, 0, 100_000_000),
+            span("a", Some("root"), 10_000_000, 30_000_000),
+            span("b", Some("root"), 10_000_000, 60_000_000),
+        ];
+        let path = trace::critical_path("t", &spans).unwrap();
+        let scene = waterfall_scene(&spans, Some(&path), &[], None, &Labels::default(), &Theme::default());
+
+        assert_eq!(fills(&scene, BAR_COLOR).len(), 3);
+        // root before b, b, root after b
+        let critical = fills(&scene, CRITICAL_COLOR);
+        assert_eq!(critical.len(), 3);
+        let total: f32 = critical.iter().map(|size| size[0]).sum();
+        assert!((total - (RIGHT - LEFT)).abs() < 1e-4);
+
+        let header = scene.draw_list().into_iter().find_map(|item| match item.primitive {
+            Primitive::Text { text, .. } if text.ends_with("MS") => Some(text.clone()),
+            _ => None,
+        });
+        assert_eq!(header.as_deref(), Some("svc-root 100MS"));
+    }
+
+    #[test]
+    fn filtered_spans_fade_or_leave_their_rows() {
+        let spans = [
+            span("root", None, 0, 100_000_000),
+            span("a", Some("root"), 10_000_000, 30_000_000),
+            span("b", Some("root"), 50_000_000, 40_000_000),
+        ];
+        let draw = |mode| {
+            let filter = SpanMatch { mode, spans: ["root", "b"].map(str::to_string).into() };
+            waterfall_scene(&spans, None, &[], Some(&filter), &Labels::default(), &Theme::default())
+        };
+        let bars = |scene: &Scene| -> Vec<(f32, f32)> {
+            scene
+                .draw_list()
+                .iter()
+                .filter_map(|item| match item.primitive {
+                    Primitive::Rect { size, .. } if item.style.fill == Some(BAR_COLOR) => {
+                        Some((size[0], item.style.opacity))
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let dimmed = bars(&draw(FilterMode::Dim));
+        let opacities: Vec<f32> = dimmed.iter().map(|&(_, opacity)| opacity).collect();
+        assert_eq!(opacities, [1.0, DIMMED_OPACITY, 1.0]);
+
+        // Hidden rows go but the axis still covers the whole trace
+        let hidden = bars(&draw(FilterMode::Hide));
+        assert_eq!(hidden.len(), 2);
+        assert!(hidden.iter().all(|&(_, opacity)| opacity == 1.0));
+        assert!((hidden[0].0 - (RIGHT - LEFT)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rows_are_capped() {
+        let spans: Vec<TraceSpan> = (0..MAX_ROWS as i64 + 10)
+            .map(|i| span(&i.to_string(), None, i, 10))
+            .collect();
+        let scene = waterfall_scene(&spans, None, &[], None, &Labels::default(), &Theme::default());
+        assert_eq!(fills(&scene, BAR_COLOR).len(), MAX_ROWS);
+        assert!(waterfall_scene(&[], None, &[], None, &Labels::default(), &Theme::default()).is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+// Golden-image tests for the CPU reference rasterizer.
+//
+// Each case renders a frame and compares it against `tests/golden/<name>.png`
+// with a perceptual tolerance. After an intended rendering change, refresh
+// the goldens with:
+//
+//     UPDATE_GOLDEN=1 cargo test -p playback-core --test golden
+//
+// On failure the actual render and a diff image are written under the
+// target directory and their paths are included in the panic message.
+
+use std::path::{Path, PathBuf};
+
+use playback_core::annotations::{Annotation, AnnotationKind, AnnotationSource};
+use playback_core::bundle::DEFAULT_BUNDLING;
+use playback_core::diff::{self, Counts, DiffSide, Totals};
+use playback_core::filter::{FilterMode, SpanMatch};
+use playback_core::geometry::CHAR_HEIGHT;
+use playback_core::grouping;
+use playback_core::labels::Labels;
+use playback_core::layout::{self, Pins};
+use playback_core::map;
+use playback_core::raster::{self, DiffTolerance, Image};
+use playback_core::scene::{self, Icon, Node, Primitive, Scene, Style, Transform, TEXT_COLOR};
+use playback_core::snapshot;
+use playback_core::tessellate::tessellate;
+use playback_core::theme::Theme;
+use playback_core::trace::{self, TraceSpan};
+use playback_core::waterfall;
+use playback_core::ServiceNode;
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 128;
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+fn read_png(path: &Path) -> Image {
+    snapshot::decode_png(&std::fs::read(path).unwrap()).unwrap()
+}
+
+fn write_png(path: &Path, image: &Image) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, snapshot::encode_png(image).unwrap()).unwrap();
+}
+
+fn assert_golden(name: &str, scene: &Scene) {
+    let actual = raster::rasterize(&tessellate(scene), WIDTH, HEIGHT);
+    let golden = golden_path(name);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&golden, &actual);
+        return;
+    }
+    assert!(
+        golden.exists(),
+        "missing golden {}; run with UPDATE_GOLDEN=1 to create it",
+        golden.display()
+    );
+
+    let tolerance = DiffTolerance::default();
+    let report = raster::diff(&read_png(&golden), &actual, &tolerance);
+    if !report.within(&tolerance) {
+        let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        let actual_path = out.join(format!("{}.actual.png", name));
+        let diff_path = out.join(format!("{}.diff.png", name));
+        write_png(&actual_path, &actual);
+        write_png(&diff_path, &report.diff);
+        panic!(
+            "{}: {} of {} pixels differ (max distance {:.3}); actual: {}, diff: {}",
+            name,
+            report.mismatched,
+            report.total,
+            report.max_distance,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn service(id: &str) -> ServiceNode {
+    ServiceNode {
+        id: id.to_string(),
+        x: 0.0,
+        y: 0.0,
+        status: "active".to_string(),
+    }
+}
+
+#[test]
+fn empty_frame() {
+    assert_golden("empty", &Scene::default());
+}
+
+#[test]
+fn hello_text() {
+    assert_golden("hello_text", &scene::text_scene("Hello WASM!", &Theme::default()));
+}
+
+#[test]
+fn service_with_job_prefix() {
+    assert_golden("service_job_prefix", &scene::service_scene(&service("job-abc123"), &Labels::default(), &Theme::default()));
+}
+
+#[test]
+fn service_with_long_id() {
+    assert_golden("service_long_id", &scene::service_scene(&service("checkout-service-7f9a"), &Labels::default(), &Theme::default()));
+}
+
+#[test]
+fn service_with_error_status() {
+    let failing = ServiceNode { status: "error".to_string(), ..service("job-auth42") };
+    assert_golden("service_error_status", &scene::service_scene(&failing, &Labels::default(), &Theme::default()));
+}
+
+#[test]
+fn highlighted_service() {
+    let checkout = service("checkout-service-7f9a");
+    let (labels, theme) = (Labels::default(), Theme::default());
+    let mut highlighted = scene::service_scene(&checkout, &labels, &theme);
+    scene::highlight_service(&mut highlighted, &checkout, "POST /CART", &labels, &theme);
+    assert_golden("service_highlight", &highlighted);
+}
+
+#[test]
+fn full_character_set() {
+    let mut charset = Scene::default();
+    for (line, y) in [("ABCDEFGHIJKLM", 0.5), ("NOPQRSTUVWXYZ", 0.1), ("0123456789+=!,.:;?", -0.3)] {
+        charset.add(
+            Primitive::Text { text: line.to_string(), origin: [-0.8, y], size: CHAR_HEIGHT },
+            Style::fill(TEXT_COLOR),
+        );
+    }
+    assert_golden("charset", &charset);
+}
+
+#[test]
+fn primitives_transforms_and_z_order() {
+    let mut shapes = Scene::default();
+    shapes.add(
+        Primitive::Rect { origin: [-0.9, -0.8], size: [0.5, 0.6] },
+        Style::fill([0.1, 0.3, 0.8, 1.0]).with_stroke([1.0, 1.0, 1.0, 1.0], 0.02),
+    );
+    shapes.add(
+        Primitive::Circle { center: [-0.2, 0.4], radius: 0.3 },
+        Style::stroke([0.2, 0.9, 0.3, 1.0], 0.03),
+    );
+    shapes.add(
+        Primitive::Polyline { points: vec![[0.0, -0.8], [0.3, -0.2], [0.6, -0.7], [0.9, 0.0]], closed: false },
+        Style::stroke([0.9, 0.2, 0.9, 1.0], 0.02),
+    );
+    shapes.add(Primitive::Line { from: [-1.0, 0.5], to: [1.0, 0.5] }, Style::stroke([0.6, 0.6, 0.6, 1.0], 0.04));
+
+    // A rotated, half-transparent group drawn below the line despite being added last
+    let group = shapes.insert(
+        Scene::ROOT,
+        Node::group()
+            .transform(Transform::rotate(0.4).then(&Transform::translate(0.5, 0.5)))
+            .z(-1),
+    );
+    shapes.insert(
+        group,
+        Node::shape(
+            Primitive::Rect { origin: [-0.2, -0.1], size: [0.4, 0.2] },
+            Style::fill([1.0, 0.3, 0.1, 1.0]).with_opacity(0.5),
+        ),
+    );
+    for (i, icon) in [Icon::Dot, Icon::Warning, Icon::Error].into_iter().enumerate() {
+        shapes.add(
+            Primitive::Icon { icon, center: [0.2 + i as f32 * 0.2, 0.7], size: 0.12 },
+            Style::fill(TEXT_COLOR),
+        );
+    }
+    assert_golden("primitives", &shapes);
+}
+
+// A gateway call fanning out to four services, AUTH failing
+fn checkout_trace() -> [TraceSpan; 5] {
+    let span = |id: &str, parent: Option<&str>, start_ms: i64, duration_ms: i64| TraceSpan {
+        span_id: id.to_string(),
+        parent_id: parent.map(str::to_string),
+        service: id.to_string(),
+        operation: None,
+        start_ns: start_ms * 1_000_000,
+        duration_ns: duration_ms * 1_000_000,
+        status: (id == "AUTH").then(|| "error".to_string()),
+    };
+    [
+        span("GATEWAY", None, 0, 100),
+        span("AUTH", Some("GATEWAY"), 10, 30),
+        span("CART", Some("GATEWAY"), 10, 60),
+        span("DB", Some("CART"), 20, 40),
+        span("PAY", Some("GATEWAY"), 75, 20),
+    ]
+}
+
+#[test]
+fn trace_waterfall_with_critical_path_and_annotation() {
+    let spans = checkout_trace();
+    let path = trace::critical_path("t1", &spans).unwrap();
+    let deploy = Annotation {
+        id: 0,
+        timestamp_ns: 50_000_000,
+        end_ns: None,
+        kind: AnnotationKind::Deploy,
+        label: "V2".to_string(),
+        service: None,
+        severity: None,
+        source: AnnotationSource::User,
+    };
+    let waterfall =
+        waterfall::waterfall_scene(&spans, Some(&path), &[deploy], None, &Labels::default(), &Theme::default());
+    assert_golden("trace_waterfall", &waterfall);
+}
+
+#[test]
+fn trace_waterfall_with_filtered_spans() {
+    let spans = checkout_trace();
+    let path = trace::critical_path("t1", &spans).unwrap();
+    let filter = |mode| SpanMatch { mode, spans: ["GATEWAY", "CART", "DB"].map(str::to_string).into() };
+    let draw = |filter: &SpanMatch| {
+        waterfall::waterfall_scene(&spans, Some(&path), &[], Some(filter), &Labels::default(), &Theme::default())
+    };
+    assert_golden("trace_waterfall_dimmed", &draw(&filter(FilterMode::Dim)));
+    assert_golden("trace_waterfall_hidden", &draw(&filter(FilterMode::Hide)));
+}
+
+#[test]
+fn replay_diff_map() {
+    let counts = |spans: usize, errors: usize, latency_ns: f64| Counts {
+        spans,
+        errors,
+        duration_sum_ns: latency_ns * spans as f64,
+        timed_spans: spans,
+    };
+    let totals = |services: &[(&str, Counts)], edges: &[(&str, &str, Counts)]| Totals {
+        services: services.iter().map(|(s, c)| (s.to_string(), *c)).collect(),
+        edges: edges.iter().map(|(f, t, c)| ((f.to_string(), t.to_string()), *c)).collect(),
+        duration_ns: Some(1_000_000_000),
+    };
+    let side = |job: &str| DiffSide { job: job.to_string(), start_ns: None, end_ns: None };
+    let before = totals(
+        &[("GATEWAY", counts(10, 0, 100.0)), ("CART", counts(10, 0, 100.0)), ("OLD", counts(5, 0, 100.0)),
+          ("AUTH", counts(10, 0, 100.0))],
+        &[("GATEWAY", "CART", counts(10, 0, 100.0)), ("GATEWAY", "OLD", counts(5, 0, 100.0)),
+          ("GATEWAY", "AUTH", counts(10, 0, 100.0))],
+    );
+    let after = totals(
+        &[("GATEWAY", counts(10, 0, 100.0)), ("CART", counts(10, 5, 100.0)), ("NEW", counts(5, 0, 100.0)),
+          ("AUTH", counts(10, 0, 50.0))],
+        &[("GATEWAY", "CART", counts(10, 5, 100.0)), ("GATEWAY", "NEW", counts(5, 0, 100.0)),
+          ("GATEWAY", "AUTH", counts(10, 0, 50.0))],
+    );
+    let replay_diff = diff::diff(side("MON"), &before, side("TUE"), &after);
+    assert_golden("replay_diff", &diff::diff_scene(&replay_diff, None, &Labels::default(), &Theme::default()));
+}
+
+#[test]
+fn service_map_with_pinned_node() {
+    let mut services: Vec<ServiceNode> = ["job-gw1", "job-cart2", "job-db3", "job-auth4"].map(service).into();
+    services[3].status = "error".to_string();
+    let pins: Pins = [("job-db3".to_string(), [0.3, -0.5])].into();
+    layout::linear(&mut services);
+    layout::apply_pins(&mut services, &pins);
+    let edges = [("job-gw1", "job-cart2"), ("job-gw1", "job-auth4"), ("job-cart2", "job-db3")];
+    assert_golden("service_map", &map::map_scene(&services, edges, &pins, None, &[], &Labels::default(), &Theme::default(), 0.0));
+    let light = map::map_scene(&services, edges, &pins, None, &[], &Labels::default(), &Theme::light(), 0.0);
+    assert_golden("service_map_light", &light);
+}
+
+#[test]
+fn service_map_with_hull_and_collapsed_group() {
+    let mut services: Vec<ServiceNode> =
+        ["shop-cart", "shop-pay", "iam-auth", "iam-users", "iam-db"].map(service).into();
+    services[4].status = "error".to_string();
+    layout::linear(&mut services);
+    let totals = Totals::default();
+    let collapsed = ["iam".to_string()].into();
+    let groups = grouping::groups(&services, |id| grouping::prefix(id).map(str::to_string), &totals, &collapsed);
+    let edges = [
+        ("shop-cart", "shop-pay"),
+        ("shop-cart", "iam-auth"),
+        ("shop-pay", "iam-users"),
+        ("iam-auth", "iam-db"),
+    ];
+    let scene = map::map_scene(&services, edges, &Pins::new(), None, &groups, &Labels::default(), &Theme::default(), 0.0);
+    assert_golden("service_map_groups", &scene);
+}
+
+#[test]
+fn service_map_with_bundled_edges() {
+    let mut services: Vec<ServiceNode> =
+        ["shop-cart", "shop-pay", "shop-web", "iam-auth", "iam-users", "iam-db"].map(service).into();
+    let pins: Pins = [
+        ("shop-cart", [-0.9, 0.5]),
+        ("shop-pay", [-0.5, 0.7]),
+        ("shop-web", [-0.8, -0.1]),
+        ("iam-auth", [0.8, 0.4]),
+        ("iam-users", [0.5, -0.6]),
+        ("iam-db", [0.9, -0.3]),
+    ]
+    .map(|(id, at)| (id.to_string(), at))
+    .into();
+    layout::linear(&mut services);
+    layout::apply_pins(&mut services, &pins);
+    let groups = grouping::groups(&services, |id| grouping::prefix(id).map(str::to_string), &Totals::default(), &[].into());
+    let edges = [
+        ("shop-cart", "iam-auth"),
+        ("shop-pay", "iam-auth"),
+        ("shop-web", "iam-users"),
+        ("shop-web", "iam-db"),
+        ("shop-cart", "shop-pay"),
+        ("iam-auth", "iam-db"),
+    ];
+    let scene = map::map_scene(&services, edges, &pins, None, &groups, &Labels::default(), &Theme::default(), DEFAULT_BUNDLING);
+    assert_golden("service_map_bundled", &scene);
+}
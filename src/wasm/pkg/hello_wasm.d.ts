@@ -1,59 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export function init_webgpu(canvas: HTMLCanvasElement): Promise<void>;
-export function hello(): void;
-export function add(a: number, b: number): number;
-export function greet(name: string): string;
-export function parse_arrow_replay(data: Uint8Array): any;
-export function start_service_animation(): void;
-export function render_replay(): void;
-export function stop_animation(): void;
-export function clear_canvas(): void;
-
-export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
-
-export interface InitOutput {
-  readonly memory: WebAssembly.Memory;
-  readonly init_webgpu: (a: any) => any;
-  readonly hello: () => [number, number];
-  readonly add: (a: number, b: number) => [number, number, number];
-  readonly greet: (a: number, b: number) => [number, number, number, number];
-  readonly parse_arrow_replay: (a: number, b: number) => [number, number, number];
-  readonly start_service_animation: () => [number, number];
-  readonly render_replay: () => [number, number];
-  readonly stop_animation: () => [number, number];
-  readonly clear_canvas: () => [number, number];
-  readonly __wbindgen_exn_store: (a: number) => void;
-  readonly __externref_table_alloc: () => number;
-  readonly __wbindgen_export_2: WebAssembly.Table;
-  readonly __wbindgen_malloc: (a: number, b: number) => number;
-  readonly __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
-  readonly __wbindgen_export_5: WebAssembly.Table;
-  readonly __externref_table_dealloc: (a: number) => void;
-  readonly __wbindgen_free: (a: number, b: number, c: number) => void;
-  readonly _dyn_core__ops__function__FnMut_____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__h74a1ca5c2b0a03a3: (a: number, b: number) => void;
-  readonly closure1161_externref_shim: (a: number, b: number, c: any) => void;
-  readonly closure1186_externref_shim: (a: number, b: number, c: any, d: any) => void;
-  readonly __wbindgen_start: () => void;
-}
-
-export type SyncInitInput = BufferSource | WebAssembly.Module;
-/**
-* Instantiates the given `module`, which can either be bytes or
-* a precompiled `WebAssembly.Module`.
-*
-* @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
-*
-* @returns {InitOutput}
-*/
-export function initSync(module: { module: SyncInitInput } | SyncInitInput): InitOutput;
-
-/**
-* If `module_or_path` is {RequestInfo} or {URL}, makes a request and
-* for everything else, calls `WebAssembly.instantiate` directly.
-*
-* @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
-*
-* @returns {Promise<InitOutput>}
-*/
-export default function __wbg_init (module_or_path?: { module_or_path: InitInput | Promise<InitInput> } | InitInput | Promise<InitInput>): Promise<InitOutput>;
@@ -1,24 +0,0 @@
-/* tslint:disable */
-/* eslint-disable */
-export const memory: WebAssembly.Memory;
-export const init_webgpu: (a: any) => any;
-export const hello: () => [number, number];
-export const add: (a: number, b: number) => [number, number, number];
-export const greet: (a: number, b: number) => [number, number, number, number];
-export const parse_arrow_replay: (a: number, b: number) => [number, number, number];
-export const start_service_animation: () => [number, number];
-export const render_replay: () => [number, number];
-export const stop_animation: () => [number, number];
-export const clear_canvas: () => [number, number];
-export const __wbindgen_exn_store: (a: number) => void;
-export const __externref_table_alloc: () => number;
-export const __wbindgen_export_2: WebAssembly.Table;
-export const __wbindgen_malloc: (a: number, b: number) => number;
-export const __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
-export const __wbindgen_export_5: WebAssembly.Table;
-export const __externref_table_dealloc: (a: number) => void;
-export const __wbindgen_free: (a: number, b: number, c: number) => void;
-export const _dyn_core__ops__function__FnMut_____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__h74a1ca5c2b0a03a3: (a: number, b: number) => void;
-export const closure1161_externref_shim: (a: number, b: number, c: any) => void;
-export const closure1186_externref_shim: (a: number, b: number, c: any, d: any) => void;
-export const __wbindgen_start: () => void;
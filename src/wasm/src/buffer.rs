@@ -0,0 +1,126 @@
+// Safe ingestion buffer for JavaScript callers.
+//
+// JavaScript used to allocate raw WASM memory, write into it and hand the
+// pointer back. `IngestBuffer` keeps ownership of that memory on the Rust
+// side: JS fills a typed view of the buffer, or copies a chunk in with a
+// bounds-checked `write`, and commits a length that is checked before any
+// bytes are read. The view is the one thing that can go stale: any WASM
+// allocation may move or detach it, so it is taken right before filling.
+
+use js_sys::Uint8Array;
+use playback_core::ingest::IngestError;
+use wasm_bindgen::prelude::*;
+
+use crate::js_error;
+
+/// Reusable staging area in WASM memory for chunk ingestion.
+///
+/// Fill `view()` or copy bytes in with `write(chunk, offset)`, then call
+/// `commit(len)`. `reserve` grows the buffer and keeps what was written so far.
+#[wasm_bindgen]
+pub struct IngestBuffer {
+    bytes: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl IngestBuffer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> Result<IngestBuffer, JsValue> {
+        Self::with_capacity(capacity).map_err(js_error)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn capacity(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Grow the buffer so it holds at least `capacity` bytes.
+    pub fn reserve(&mut self, capacity: usize) {
+        if capacity > self.bytes.len() {
+            self.bytes.resize(capacity, 0);
+        }
+    }
+
+    /// A `Uint8Array` over the whole buffer, in WASM memory.
+    ///
+    /// The view is invalidated by `reserve` and by any other call that may
+    /// allocate, since growing WASM memory detaches every view of it. Take
+    /// a fresh view right before filling it and drop it before the next
+    /// call into WASM; `commit` checks the length either way.
+    pub fn view(&mut self) -> Uint8Array {
+        // SAFETY: the view covers exactly `bytes`, which outlives this call;
+        // the caller must not use it after the next allocation (see above)
+        unsafe { Uint8Array::view_mut_raw(self.bytes.as_mut_ptr(), self.bytes.len()) }
+    }
+
+    /// Copy `chunk` into the buffer starting at byte `offset`.
+    pub fn write(&mut self, chunk: &Uint8Array, offset: usize) -> Result<(), JsValue> {
+        let slot = self.slot(offset, chunk.length() as usize).map_err(js_error)?;
+        chunk.copy_to(slot);
+        Ok(())
+    }
+
+    /// Ingest the first `len` bytes written with `write`.
+    /// `options` takes the same shape as `ingest()`; pass undefined for defaults.
+    pub fn commit(&self, len: usize, options: JsValue) -> Result<(), JsValue> {
+        let chunk = self.filled(len).map_err(js_error)?;
+        let options = crate::ingest_options(options)?;
+        crate::run_ingest(chunk, &options).map(|_| ())
+    }
+}
+
+impl IngestBuffer {
+    fn with_capacity(capacity: usize) -> Result<Self, IngestError> {
+        if capacity == 0 {
+            return Err(IngestError::ZeroCapacity);
+        }
+        Ok(IngestBuffer {
+            bytes: vec![0; capacity],
+        })
+    }
+
+    // The `len` bytes at `offset`, if the buffer holds them
+    fn slot(&mut self, offset: usize, len: usize) -> Result<&mut [u8], IngestError> {
+        let capacity = self.bytes.len();
+        let end = offset.saturating_add(len);
+        self.bytes
+            .get_mut(offset..end)
+            .ok_or(IngestError::WriteOutOfBounds { end, capacity })
+    }
+
+    fn filled(&self, len: usize) -> Result<&[u8], IngestError> {
+        if len == 0 {
+            return Err(IngestError::EmptyChunk);
+        }
+        self.bytes.get(..len).ok_or(IngestError::OutOfBounds {
+            len,
+            capacity: self.bytes.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commits_past_capacity_are_rejected() {
+        let buffer = IngestBuffer::with_capacity(4).unwrap();
+        assert_eq!(buffer.filled(4).unwrap().len(), 4);
+        assert_eq!(buffer.filled(5).unwrap_err(), IngestError::OutOfBounds { len: 5, capacity: 4 });
+        assert_eq!(buffer.filled(0).unwrap_err(), IngestError::EmptyChunk);
+        assert!(IngestBuffer::with_capacity(0).is_err());
+    }
+
+    #[test]
+    fn reserving_after_a_write_keeps_the_bytes() {
+        let mut buffer = IngestBuffer::with_capacity(2).unwrap();
+        buffer.slot(0, 2).unwrap().copy_from_slice(&[1, 2]);
+        assert_eq!(buffer.slot(1, 2).unwrap_err(), IngestError::WriteOutOfBounds { end: 3, capacity: 2 });
+        assert!(buffer.slot(usize::MAX, 2).is_err());
+
+        buffer.reserve(4);
+        buffer.slot(2, 2).unwrap().copy_from_slice(&[3, 4]);
+        assert_eq!(buffer.filled(4).unwrap(), [1, 2, 3, 4]);
+    }
+}
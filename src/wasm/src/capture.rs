@@ -0,0 +1,197 @@
+// Offscreen captures through GPU readback: PNG snapshots and recordings.
+//
+// Frames are drawn into an offscreen texture at the requested size, copied
+// into a mappable buffer and encoded in Rust, so captures work inside the
+// worker (no DOM `toDataURL`) and can exceed the canvas size.
+
+use wasm_bindgen::prelude::*;
+use wgpu::*;
+
+use playback_core::frame::Frame;
+use playback_core::playback::PlaybackClock;
+use playback_core::raster::Image;
+use playback_core::recording::{self, RecordingFormat, RecordingOptions};
+use playback_core::snapshot::{self, PixelOrder};
+use playback_core::tessellate::tessellate;
+
+use crate::{draw_frame, js_error, multisampled_view, WebGpuRenderer};
+use crate::{DEFAULT_EXPORT_SIZE, GPU_RENDERER, REPLAY_STATE, SCENE, THEME};
+
+#[wasm_bindgen]
+pub async fn capture_png(width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+    let frame = SCENE.with(|s| tessellate(&s.borrow()));
+    let image = render_offscreen(&frame, width, height).await?;
+    snapshot::encode_png(&image).map_err(js_error)
+}
+
+// Record playback of the active job: step the playback clock at
+// `options.fps` over `start_ms..end_ms`, render each distinct frame
+// offscreen and encode it. `on_progress(progress, rgba)` runs after every
+// frame; `rgba` carries the raw pixels when the format is "rgba" (for
+// WebCodecs), in which case the returned bytes are empty.
+#[wasm_bindgen]
+pub async fn record_playback(options: JsValue, on_progress: Option<js_sys::Function>) -> Result<Vec<u8>, JsValue> {
+    let options: RecordingOptions = if options.is_undefined() || options.is_null() {
+        RecordingOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid recording options: {}", e)))?
+    };
+
+    let canvas_size = GPU_RENDERER.with(|r| {
+        r.borrow().as_ref().map_or(DEFAULT_EXPORT_SIZE, |renderer| renderer.canvas_size)
+    });
+    let width = options.width.unwrap_or(canvas_size.0);
+    let height = options.height.unwrap_or(canvas_size.1);
+
+    let (services, filter, labels) = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        (state.playback_services(), state.filter_match().cloned(), state.labels().clone())
+    });
+    let theme = THEME.with(|t| *t.borrow());
+    let planned = recording::plan(&PlaybackClock::default(), &services, filter.as_ref(), &labels, &theme, &options)
+        .map_err(js_error)?;
+    let mut encoder = recording::encoder(options.format, width, height, planned.len()).map_err(js_error)?;
+
+    for (index, planned_frame) in planned.iter().enumerate() {
+        let image = render_offscreen(&planned_frame.frame, width, height).await?;
+        if let Some(encoder) = encoder.as_mut() {
+            encoder.add(&image, planned_frame.duration_ms).map_err(js_error)?;
+        }
+
+        if let Some(callback) = &on_progress {
+            let progress = serde_wasm_bindgen::to_value(&planned_frame.progress(index, planned.len()))?;
+            let rgba = match options.format {
+                RecordingFormat::Rgba => js_sys::Uint8Array::from(image.pixels.as_slice()).into(),
+                _ => JsValue::UNDEFINED,
+            };
+            callback.call2(&JsValue::NULL, &progress, &rgba)?;
+        }
+    }
+
+    match encoder {
+        Some(encoder) => encoder.finish().map_err(js_error),
+        None => Ok(Vec::new()),
+    }
+}
+
+// Draw `frame` into a fresh offscreen texture and read it back as RGBA
+async fn render_offscreen(frame: &Frame, width: u32, height: u32) -> Result<Image, JsValue> {
+    // Record and submit while the renderer is borrowed; the device handle
+    // outlives the borrow so the mapping can be awaited afterwards
+    let (device, readback, bytes_per_row, order) = GPU_RENDERER.with(|r| {
+        let mut renderer_ref = r.borrow_mut();
+        let renderer = renderer_ref.as_mut()
+            .ok_or_else(|| JsValue::from_str("WebGPU not initialized"))?;
+
+        let order = match renderer.format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => PixelOrder::Rgba,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => PixelOrder::Bgra,
+            other => return Err(JsValue::from_str(&format!("Cannot capture surface format {:?}", other))),
+        };
+
+        let limits = renderer.device.limits();
+        snapshot::check_size(width, height, limits.max_texture_dimension_2d).map_err(js_error)?;
+        let bytes_per_row = snapshot::padded_bytes_per_row(width, COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer_size = bytes_per_row as u64 * height as u64;
+        snapshot::check_memory(width, height, bytes_per_row, renderer.sample_count, limits.max_buffer_size)
+            .map_err(js_error)?;
+
+        let size = Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = renderer.device.create_texture(&TextureDescriptor {
+            label: Some("Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: renderer.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let readback = renderer.device.create_buffer(&BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: buffer_size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = renderer.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let msaa = capture_msaa(renderer, width, height);
+        draw_frame(renderer, &mut encoder, &view, msaa.as_ref(), frame, "Capture");
+        encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        renderer.queue.submit(std::iter::once(encoder.finish()));
+
+        Ok((renderer.device.clone(), readback, bytes_per_row, order))
+    })?;
+
+    let slice = readback.slice(..);
+    map_read(&device, slice).await?;
+    let image = {
+        let data = slice.get_mapped_range();
+        snapshot::from_padded_rows(width, height, bytes_per_row, order, &data)
+    };
+    readback.unmap();
+
+    image.map_err(js_error)
+}
+
+// The multisampled target for a capture, reused while size and format hold
+fn capture_msaa(renderer: &mut WebGpuRenderer, width: u32, height: u32) -> Option<TextureView> {
+    let key = (width, height, renderer.format);
+    if let Some((cached, view)) = &renderer.capture_msaa {
+        if *cached == key {
+            return Some(view.clone());
+        }
+    }
+    let view = multisampled_view(&renderer.device, renderer.format, renderer.sample_count, width, height)?;
+    renderer.capture_msaa = Some((key, view.clone()));
+    Some(view)
+}
+
+// Browsers resolve buffer mappings from the event loop, so wasm awaits a
+// promise settled by the map callback
+#[cfg(target_arch = "wasm32")]
+async fn map_read(_device: &Device, slice: BufferSlice<'_>) -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = match result {
+                Ok(()) => resolve.call0(&JsValue::NULL),
+                Err(e) => reject.call1(&JsValue::NULL, &JsValue::from_str(&format!("Failed to map capture buffer: {}", e))),
+            };
+        });
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ())
+}
+
+// Native devices only make progress when polled, so block until the map lands
+#[cfg(not(target_arch = "wasm32"))]
+async fn map_read(device: &Device, slice: BufferSlice<'_>) -> Result<(), JsValue> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(PollType::Wait)
+        .map_err(|e| JsValue::from_str(&format!("Failed to poll device: {}", e)))?;
+    receiver.recv()
+        .map_err(|e| JsValue::from_str(&format!("Capture buffer was dropped: {}", e)))?
+        .map_err(|e| JsValue::from_str(&format!("Failed to map capture buffer: {}", e)))
+}
@@ -1,15 +1,39 @@
 use wasm_bindgen::prelude::*;
-use std::alloc::{alloc as std_alloc, dealloc, Layout};
 use web_sys::HtmlCanvasElement;
 use wgpu::*;
 use wgpu::util::DeviceExt;
-use js_sys::Date;
 use wasm_bindgen::closure::Closure;
 use web_sys::window;
-use arrow::array::{Array, StringArray};
-use arrow::ipc::reader::FileReader;
-use std::io::Cursor;
-use serde::{Deserialize, Serialize};
+
+mod buffer;
+mod capture;
+
+pub use buffer::IngestBuffer;
+pub use capture::{capture_png, record_playback};
+pub use playback_core::{ReplayData, ServiceNode};
+use playback_core::annotations::{Annotation, AnnotationJump, AnnotationTimeline, NewAnnotation};
+use playback_core::anomaly::{AnomalyOptions, AnomalyTimeline};
+use playback_core::bundle::DEFAULT_BUNDLING;
+use playback_core::diff::{self, DiffSide, ReplayDiff};
+use playback_core::filter::FilterMode;
+use playback_core::frame::{Frame, VERTEX_FLOATS};
+use playback_core::grouping::GroupBy;
+use playback_core::ingest::{self, ArrowIpcDecoder, IngestOptions};
+use playback_core::jobs::ReplayState;
+use playback_core::labels::LabelRules;
+use playback_core::layout::PinnedLayout;
+use playback_core::lod::{Lod, LodOptions};
+use playback_core::map::{self, MapView};
+use playback_core::playback::{PlaybackClock, SERVICE_DWELL_MS};
+use playback_core::scene::{self, Scene};
+use playback_core::search::{SearchTarget, DEFAULT_SEARCH_LIMIT};
+use playback_core::session::{Camera, Restored, Selection, Session};
+use playback_core::store::RetentionPolicy;
+use playback_core::svg;
+use playback_core::tessellate::tessellate;
+use playback_core::theme::Theme;
+use playback_core::trace::{CriticalPath, DEFAULT_TRACE_LIMIT};
+use playback_core::waterfall;
 
 // Import the `console.log` function from the Web API
 #[wasm_bindgen]
@@ -28,58 +52,83 @@ struct WebGpuRenderer {
     device: Device,
     queue: Queue,
     surface: Surface<'static>,
-    surface_config: SurfaceConfiguration,
     render_pipeline: RenderPipeline,
+    format: TextureFormat,
+    canvas_size: (u32, u32),
+    // Samples per pixel; 1 when the adapter cannot multisample the format
+    sample_count: u32,
+    // Multisampled canvas-sized target, resolved into the surface texture
+    msaa_view: Option<TextureView>,
+    // Multisampled target of the last offscreen capture, by size and format,
+    // so a recording reuses one target for every frame
+    capture_msaa: Option<((u32, u32, TextureFormat), TextureView)>,
     current_service_index: usize,
 }
 
 // Use thread_local for WASM since we're single-threaded
 use std::cell::RefCell;
 thread_local! {
-    static GPU_RENDERER: RefCell<Option<WebGpuRenderer>> = RefCell::new(None);
-    static REPLAY_DATA: RefCell<Option<ReplayData>> = RefCell::new(None);
-    static ANIMATION_RUNNING: RefCell<bool> = RefCell::new(false);
-    static LAST_SERVICE_COUNT: RefCell<usize> = RefCell::new(0);
+    static GPU_RENDERER: RefCell<Option<WebGpuRenderer>> = const { RefCell::new(None) };
+    static REPLAY_STATE: RefCell<ReplayState> = RefCell::new(ReplayState::default());
+    static ANIMATION_RUNNING: RefCell<bool> = const { RefCell::new(false) };
+    static LAST_SERVICE_COUNT: RefCell<usize> = const { RefCell::new(0) };
+    static SCENE: RefCell<Scene> = RefCell::new(Scene::default());
+    // Service outlined after flying to a search result, with its caption
+    static HIGHLIGHT: RefCell<Option<(String, String)>> = const { RefCell::new(None) };
+    // Critical path of the trace selected with `critical_path`/`show_trace`
+    static CRITICAL_PATH: RefCell<Option<CriticalPath>> = const { RefCell::new(None) };
+    // Pan and zoom applied to every scene on screen
+    static CAMERA: RefCell<Camera> = RefCell::new(Camera::default());
+    // Camera from before flying to a search result; `clear_highlight` returns to it
+    static UNFOCUSED_CAMERA: RefCell<Option<Camera>> = const { RefCell::new(None) };
+    // Playback rate; the worker divides its rotation interval by it
+    static PLAYBACK_SPEED: RefCell<f64> = const { RefCell::new(1.0) };
+    // How strongly service map edges bundle, from 0 (straight) to 1
+    static EDGE_BUNDLING: RefCell<f32> = const { RefCell::new(DEFAULT_BUNDLING) };
+    // Colors every view is drawn in
+    static THEME: RefCell<Theme> = RefCell::new(Theme::dark());
+    // Level of detail the scene on screen is drawn at
+    static LOD: RefCell<Lod> = RefCell::new(Lod::default());
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ServiceNode {
-    pub id: String,
-    pub x: f32,
-    pub y: f32,
-    pub status: String, // "healthy", "warning", "error"
+// Convert core errors into the string errors JavaScript callers expect
+fn js_error(error: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&error.to_string())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]  
-pub struct ReplayData {
-    pub services: Vec<ServiceNode>,
-    pub all_service_ids: Vec<String>, // All unique service IDs from Arrow file
-    pub timestamp: String,
-    pub job_id: String,
+const VERTEX_SHADER_SOURCE: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
 }
 
-const VERTEX_SHADER_SOURCE: &str = r#"
 @vertex
-fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {
-    return vec4<f32>(position, 0.0, 1.0);
+fn vs_main(@location(0) position: vec2<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.color = color;
+    return out;
 }
 "#;
 
 const FRAGMENT_SHADER_SOURCE: &str = r#"
 @fragment
-fn fs_main() -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 0.8, 0.2, 1.0); // Epic golden color for FINAL BOSS MODE! 🚀
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color; // Frame colors - golden text is set in playback_core::frame 🚀
 }
 "#;
 
-const FADE_FRAGMENT_SHADER_SOURCE: &str = r#"
-@group(0) @binding(0) var<uniform> fade_alpha: f32;
+#[cfg(target_arch = "wasm32")]
+fn create_canvas_surface(instance: &Instance, canvas: HtmlCanvasElement) -> Result<Surface<'static>, JsValue> {
+    instance.create_surface(SurfaceTarget::Canvas(canvas))
+        .map_err(|e| JsValue::from_str(&format!("Failed to create surface: {:?}", e)))
+}
 
-@fragment
-fn fs_main() -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 0.8, 0.2, fade_alpha); // Alpha-blended golden color for smooth transitions
+// Canvas surfaces only exist in the browser; native builds are for headless tests
+#[cfg(not(target_arch = "wasm32"))]
+fn create_canvas_surface(_instance: &Instance, _canvas: HtmlCanvasElement) -> Result<Surface<'static>, JsValue> {
+    Err(JsValue::from_str("Canvas surfaces are only available on wasm32"))
 }
-"#;
 
 #[wasm_bindgen]
 pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
@@ -93,11 +142,10 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
     });
     
     // Get canvas size before moving canvas
-    let canvas_size = (canvas.width() as u32, canvas.height() as u32);
+    let canvas_size = (canvas.width(), canvas.height());
     
     // Create surface from canvas - WASM specific
-    let surface = instance.create_surface(SurfaceTarget::Canvas(canvas))
-        .map_err(|e| JsValue::from_str(&format!("Failed to create surface: {:?}", e)))?;
+    let surface = create_canvas_surface(&instance, canvas)?;
     console_log!("🖼️ Surface created successfully");
     
     // Request adapter
@@ -139,6 +187,10 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
     surface.configure(&device, &surface_config);
     console_log!("🎨 Surface configured with format: {:?}, size: {}x{}", surface_config.format, surface_config.width, surface_config.height);
     
+    // Antialias with 4x MSAA wherever the adapter can multisample the format
+    let sample_count = sample_count(adapter.get_texture_format_features(surface_config.format).flags);
+    console_log!("🔬 Drawing with {}x multisampling", sample_count);
+    
     // Create shaders
     let vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
         label: Some("Vertex Shader"),
@@ -166,13 +218,9 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
             entry_point: Some("vs_main"),
             compilation_options: Default::default(),
             buffers: &[VertexBufferLayout {
-                array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                array_stride: (VERTEX_FLOATS * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
                 step_mode: VertexStepMode::Vertex,
-                attributes: &[VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: VertexFormat::Float32x2,
-                }],
+                attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x4],
             }],
         },
         fragment: Some(FragmentState {
@@ -196,7 +244,7 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
         },
         depth_stencil: None,
         multisample: MultisampleState {
-            count: 1,
+            count: sample_count,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
@@ -205,13 +253,19 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
     
     // Simple pipeline - no fade effects needed for 10-second transitions
     
+    let msaa_view = multisampled_view(&device, surface_config.format, sample_count, canvas_size.0, canvas_size.1);
+    
     // Store the renderer globally
     let renderer = WebGpuRenderer {
         device,
         queue,
         surface,
-        surface_config,
         render_pipeline,
+        format: surface_config.format,
+        canvas_size,
+        sample_count,
+        msaa_view,
+        capture_msaa: None,
         current_service_index: 0,
     };
     
@@ -221,314 +275,74 @@ pub async fn init_webgpu(canvas: HtmlCanvasElement) -> Result<(), JsValue> {
     Ok(())
 }
 
-fn create_char_geometry(c: char, x: f32, y: f32, char_width: f32, char_height: f32) -> Vec<f32> {
-    let mut vertices = Vec::new();
-    let segment_width = char_width / 5.0;
-    let segment_height = char_height / 7.0;
-    
-    // Simple 5x7 bitmap font patterns
-    let pattern = match c.to_ascii_uppercase() {
-        'H' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (2,3),                                            // Middle horizontal
-            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
-        ],
-        'E' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
-            (1,3), (2,3), (3,3),                              // Middle horizontal  
-            (1,6), (2,6), (3,6), (4,6),                       // Top horizontal
-        ],
-        'L' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
-        ],
-        'O' => vec![
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
-            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
-            (1,6), (2,6), (3,6),                              // Top horizontal
-        ],
-        'A' => vec![
-            (1,0), (2,0), (3,0),                              // Bottom of A
-            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
-            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
-            (1,3), (2,3), (3,3),                              // Middle horizontal
-            (2,6),                                            // Top
-        ],
-        'W' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,0), (1,1),                                      // Left bottom
-            (2,0), (2,1), (2,2),                              // Middle
-            (3,0), (3,1),                                      // Right bottom
-            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
-        ],
-        'S' => vec![
-            (1,0), (2,0), (3,0), (4,0),                       // Bottom horizontal
-            (0,1), (0,2),                                      // Left bottom
-            (1,3), (2,3), (3,3),                              // Middle horizontal
-            (4,4), (4,5),                                      // Right top
-            (0,6), (1,6), (2,6), (3,6),                       // Top horizontal
-        ],
-        'M' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,5), (1,6),                                      // Left diagonal
-            (2,4), (2,5), (2,6),                              // Middle
-            (3,5), (3,6),                                      // Right diagonal
-            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
-        ],
-        'V' => vec![
-            (0,3), (0,4), (0,5), (0,6),                       // Left top
-            (1,1), (1,2),                                      // Left middle
-            (2,0),                                             // Bottom center
-            (3,1), (3,2),                                      // Right middle  
-            (4,3), (4,4), (4,5), (4,6),                       // Right top
-        ],
-        'T' => vec![
-            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
-            (2,0), (2,1), (2,2), (2,3), (2,4), (2,5),         // Center vertical
-        ],
-        'K' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,3),                                             // Middle connection
-            (2,2), (2,4),                                      // Diagonals
-            (3,1), (3,5),                                      // Diagonals  
-            (4,0), (4,6),                                      // Right ends
-        ],
-        'I' => vec![
-            (0,0), (1,0), (2,0), (3,0), (4,0),                // Bottom horizontal
-            (2,1), (2,2), (2,3), (2,4), (2,5),                // Center vertical
-            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
-        ],
-        'R' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,3), (1,6), (2,3), (2,6), (3,3), (3,6),         // Horizontals
-            (4,4), (4,5), (4,6),                               // Right top
-            (3,1), (4,0),                                      // Right diagonal
-        ],
-        'C' => vec![
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
-            (1,6), (2,6), (3,6),                              // Top horizontal
-            (4,1), (4,5),                                      // Right ends only
-        ],
-        'N' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,1), (2,2), (3,3),                              // Diagonal
-            (4,0), (4,1), (4,2), (4,3), (4,4), (4,5), (4,6), // Right vertical
-        ],
-        'U' => vec![
-            (0,1), (0,2), (0,3), (0,4), (0,5), (0,6),         // Left vertical
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (4,1), (4,2), (4,3), (4,4), (4,5), (4,6),         // Right vertical
-        ],
-        'D' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (1,6), (2,6), (3,6),                              // Top horizontal
-            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
-        ],
-        'P' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,3), (1,6), (2,3), (2,6), (3,3), (3,6),         // Horizontals
-            (4,4), (4,5), (4,6),                               // Right top only
-        ],
-        'F' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,3), (2,3), (3,3),                              // Middle horizontal
-            (1,6), (2,6), (3,6), (4,6),                       // Top horizontal
-        ],
-        'G' => vec![
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
-            (1,6), (2,6), (3,6),                              // Top horizontal
-            (4,1), (4,2), (2,3), (3,3), (4,3),                // Right with middle bar
-        ],
-        'B' => vec![
-            (0,0), (0,1), (0,2), (0,3), (0,4), (0,5), (0,6), // Left vertical
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (1,3), (2,3), (3,3),                              // Middle horizontal
-            (1,6), (2,6), (3,6),                              // Top horizontal
-            (4,1), (4,2), (4,4), (4,5),                       // Right segments
-        ],
-        'Y' => vec![
-            (0,5), (0,6),                                      // Left top
-            (1,4), (2,3),                                      // Left diagonal
-            (2,0), (2,1), (2,2),                              // Center bottom
-            (3,4), (4,5), (4,6),                              // Right diagonal and top
-        ],
-        'J' => vec![
-            (0,1),                                             // Left bottom hook
-            (1,0), (2,0), (3,0),                              // Bottom horizontal
-            (4,1), (4,2), (4,3), (4,4), (4,5), (4,6),         // Right vertical
-        ],
-        'Q' => vec![
-            (1,0), (2,0), (3,0),                              // Bottom horizontal  
-            (0,1), (0,2), (0,3), (0,4), (0,5),                // Left vertical
-            (4,1), (4,2), (4,3), (4,4), (4,5),                // Right vertical
-            (1,6), (2,6), (3,6),                              // Top horizontal
-            (3,1), (4,0),                                      // Tail
-        ],
-        'X' => vec![
-            (0,0), (0,6),                                      // Left corners
-            (1,1), (1,5),                                      // Inner diagonals
-            (2,2), (2,3), (2,4),                              // Center
-            (3,1), (3,5),                                      // Inner diagonals
-            (4,0), (4,6),                                      // Right corners
-        ],
-        'Z' => vec![
-            (0,0), (1,0), (2,0), (3,0), (4,0),                // Bottom horizontal
-            (4,1), (3,2), (2,3), (1,4), (0,5),                // Diagonal
-            (0,6), (1,6), (2,6), (3,6), (4,6),                // Top horizontal
-        ],
-        '+' => vec![
-            (2,1), (2,2), (2,4), (2,5),                       // Vertical line
-            (1,3), (3,3),                                      // Horizontal line
-        ],
-        '=' => vec![
-            (1,2), (2,2), (3,2),                              // Top line
-            (1,4), (2,4), (3,4),                              // Bottom line
-        ],
-        '!' => vec![
-            (2,2), (2,3), (2,4), (2,5), (2,6),                // Vertical line
-            (2,0),                                             // Bottom dot
-        ],
-        ',' => vec![
-            (2,0), (1,1),                                      // Comma shape
-        ],
-        '.' => vec![
-            (2,0),                                             // Dot
-        ],
-        ':' => vec![
-            (2,1), (2,5),                                      // Two dots
-        ],
-        ';' => vec![
-            (2,5), (2,1), (1,0),                              // Semicolon
-        ],
-        '?' => vec![
-            (1,6), (2,6), (3,6),                              // Top
-            (4,5), (4,4),                                      // Right top
-            (2,3), (3,3),                                      // Middle
-            (2,0),                                             // Bottom dot
-        ],
-        '0'..='9' => {
-            let digit = c.to_digit(10).unwrap_or(0);
-            match digit {
-                0 => vec![
-                    (1,0), (2,0), (3,0),                       // Bottom
-                    (0,1), (0,2), (0,3), (0,4), (0,5),         // Left
-                    (4,1), (4,2), (4,3), (4,4), (4,5),         // Right
-                    (1,6), (2,6), (3,6),                       // Top
-                ],
-                1 => vec![
-                    (2,0), (2,1), (2,2), (2,3), (2,4), (2,5), (2,6), // Vertical line
-                    (1,5),                                      // Top left
-                ],
-                2 => vec![
-                    (0,0), (1,0), (2,0), (3,0), (4,0),         // Bottom
-                    (0,1), (0,2),                               // Left bottom
-                    (1,3), (2,3), (3,3),                       // Middle
-                    (4,4), (4,5),                               // Right top
-                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
-                ],
-                3 => vec![
-                    (0,0), (1,0), (2,0), (3,0),                // Bottom
-                    (4,1), (4,2),                               // Right bottom
-                    (1,3), (2,3), (3,3),                       // Middle
-                    (4,4), (4,5),                               // Right top
-                    (0,6), (1,6), (2,6), (3,6),                // Top
-                ],
-                4 => vec![
-                    (3,0), (3,1), (3,2), (3,3), (3,4), (3,5), (3,6), // Right vertical
-                    (0,3), (0,4), (0,5),                       // Left vertical
-                    (1,3), (2,3),                               // Middle horizontal
-                ],
-                5 => vec![
-                    (1,0), (2,0), (3,0), (4,0),                // Bottom
-                    (4,1), (4,2),                               // Right bottom
-                    (1,3), (2,3), (3,3),                       // Middle
-                    (0,4), (0,5),                               // Left top
-                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
-                ],
-                6 => vec![
-                    (1,0), (2,0), (3,0),                       // Bottom
-                    (0,1), (0,2), (0,3), (0,4), (0,5),         // Left
-                    (1,3), (2,3), (3,3),                       // Middle
-                    (4,1), (4,2),                               // Right bottom
-                    (1,6), (2,6), (3,6),                       // Top
-                ],
-                7 => vec![
-                    (3,0), (3,1), (3,2), (3,3),                // Right diagonal
-                    (2,4), (2,5),                               // Middle
-                    (0,6), (1,6), (2,6), (3,6), (4,6),         // Top
-                ],
-                8 => vec![
-                    (1,0), (2,0), (3,0),                       // Bottom
-                    (0,1), (0,2), (0,4), (0,5),                // Left
-                    (4,1), (4,2), (4,4), (4,5),                // Right
-                    (1,3), (2,3), (3,3),                       // Middle
-                    (1,6), (2,6), (3,6),                       // Top
-                ],
-                _ => vec![(2,3)], // Default dot
-            }
-        },
-        ' ' => vec![], // Space
-        _ => vec![(1,1), (2,1), (3,1), (1,2), (2,2), (3,2), (1,3), (2,3), (3,3)], // Default block
-    };
-    
-    // Convert pattern to triangles
-    for (px, py) in pattern {
-        let seg_x = x + px as f32 * segment_width;
-        let seg_y = y + py as f32 * segment_height;
-        
-        // Two triangles per segment
-        vertices.extend_from_slice(&[
-            seg_x, seg_y,                                    // Bottom left
-            seg_x + segment_width, seg_y,                    // Bottom right
-            seg_x, seg_y + segment_height,                   // Top left
-        ]);
-        vertices.extend_from_slice(&[
-            seg_x, seg_y + segment_height,                   // Top left
-            seg_x + segment_width, seg_y,                    // Bottom right
-            seg_x + segment_width, seg_y + segment_height,   // Top right
-        ]);
-    }
+fn render_text_webgpu(text: &str) -> Result<(), JsValue> {
+    // Build the scene for text using proper bitmap font
+    let theme = THEME.with(|t| *t.borrow());
+    present_scene(scene::text_scene(text, &theme), "Render")?;
     
-    vertices
+    console_log!("🎨 Rendered '{}' with WebGPU! FINAL BOSS POWER! ⚡", text);
+    Ok(())
+}
+
+// Size exports use before a canvas is attached
+const DEFAULT_EXPORT_SIZE: (u32, u32) = (1600, 900);
+const MSAA_SAMPLES: u32 = 4;
+
+// `MSAA_SAMPLES` when a format with these features can be multisampled that
+// far, otherwise 1
+fn sample_count(flags: TextureFormatFeatureFlags) -> u32 {
+    if flags.sample_count_supported(MSAA_SAMPLES) { MSAA_SAMPLES } else { 1 }
+}
+
+// Serialize the scene currently on screen to a standalone SVG document
+#[wasm_bindgen]
+pub fn export_svg() -> String {
+    let (width, height) = GPU_RENDERER.with(|r| {
+        r.borrow().as_ref().map_or(DEFAULT_EXPORT_SIZE, |renderer| renderer.canvas_size)
+    });
+    let document = SCENE.with(|s| svg::export_svg(&s.borrow(), width, height));
+    console_log!("🖼️ Exported {}x{} SVG ({} bytes)", width, height, document.len());
+    document
 }
 
-fn create_text_geometry(text: &str, x: f32, y: f32) -> Vec<f32> {
-    let mut vertices = Vec::new();
-    let char_width = 0.08;
-    let char_height = 0.14;
-    let char_spacing = 0.01;
+// Make `scene` the current scene and draw it to the canvas at the level of
+// detail the camera zoom calls for. The CPU rasterizer in
+// playback_core::raster draws the same tessellated frame for the
+// golden-image tests.
+fn present_scene(mut scene: Scene, label: &str) -> Result<(), JsValue> {
+    // Clip space spans two units across the shorter side of the canvas
+    let camera = CAMERA.with(|c| *c.borrow());
+    let (width, height) = GPU_RENDERER.with(|r| {
+        r.borrow().as_ref().map_or(DEFAULT_EXPORT_SIZE, |renderer| renderer.canvas_size)
+    });
+    let pixels_per_unit = camera.zoom * width.min(height) as f32 / 2.0;
+    let (mut drawn, stats) = LOD.with(|l| {
+        let mut lod = l.borrow_mut();
+        if lod.update(pixels_per_unit) {
+            console_log!("🔭 LOD scale now {:.1} px per unit", pixels_per_unit);
+        }
+        lod.simplify(&scene)
+    });
     
-    for (i, c) in text.chars().enumerate() {
-        let char_x = x + i as f32 * (char_width + char_spacing);
-        let char_vertices = create_char_geometry(c, char_x, y, char_width, char_height);
-        vertices.extend(char_vertices);
+    // Scenes are built with an identity root, which carries the camera. The
+    // full scene is kept for exports and redraws.
+    for scene in [&mut scene, &mut drawn] {
+        if let Some(root) = scene.node_mut(Scene::ROOT) {
+            root.transform = camera.transform();
+        }
     }
+    let frame = tessellate(&drawn);
+    console_log!(
+        "🔍 Tessellated {} scene nodes into {} vertices ({} labels hidden, {} points, {} edges merged)",
+        scene.len(), frame.vertex_count(), stats.hidden_labels, stats.points, stats.merged_edges
+    );
+    SCENE.with(|s| *s.borrow_mut() = scene);
     
-    vertices
-}
-
-fn render_text_webgpu(text: &str) -> Result<(), JsValue> {
     GPU_RENDERER.with(|r| {
         let renderer_ref = r.borrow();
         let renderer = renderer_ref.as_ref()
             .ok_or_else(|| JsValue::from_str("WebGPU not initialized"))?;
         
-        // Create vertex data for text using proper bitmap font
-        let vertices = create_text_geometry(text, -0.5, 0.0);
-        console_log!("🔍 Generated {} vertices for text '{}': {:?}", vertices.len(), text, &vertices[0..std::cmp::min(12, vertices.len())]);
-        
-        // Create vertex buffer
-        let vertex_buffer = renderer.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: BufferUsages::VERTEX,
-        });
-        
         // Get current surface texture
         let output = renderer.surface.get_current_texture()
             .map_err(|e| JsValue::from_str(&format!("Failed to get surface texture: {:?}", e)))?;
@@ -536,51 +350,81 @@ fn render_text_webgpu(text: &str) -> Result<(), JsValue> {
         
         // Create command encoder
         let mut encoder = renderer.device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
+            label: Some(&format!("{} Encoder", label)),
         });
-        
-        {
-            // Begin render pass
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.0,
-                            g: 0.0, 
-                            b: 0.0,
-                            a: 1.0,
-                        }),
-                        store: StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            
-            render_pass.set_pipeline(&renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            let vertex_count = (vertices.len() / 2) as u32; // Each vertex is 2 f32s (x,y)
-            console_log!("🎯 Drawing {} vertices from {} f32 values", vertex_count, vertices.len());
-            render_pass.draw(0..vertex_count, 0..1);
-        }
+        draw_frame(renderer, &mut encoder, &view, renderer.msaa_view.as_ref(), &frame, label);
         
         // Submit commands and present
         renderer.queue.submit(std::iter::once(encoder.finish()));
         output.present();
         
-        console_log!("🎨 Rendered '{}' with WebGPU! FINAL BOSS POWER! ⚡", text);
-        
-        // Check for any errors
-        console_log!("🔍 Checking for WebGPU errors...");
         Ok(())
     })
 }
 
+// The multisampled target a `width` x `height` pass draws into before it is
+// resolved, or None without multisampling
+fn multisampled_view(device: &Device, format: TextureFormat, sample_count: u32, width: u32, height: u32) -> Option<TextureView> {
+    (sample_count > 1).then(|| {
+        device.create_texture(&TextureDescriptor {
+            label: Some("MSAA Texture"),
+            size: Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        }).create_view(&TextureViewDescriptor::default())
+    })
+}
+
+// Record a render pass that clears `view` and draws `frame` into it. With
+// `msaa` the pass draws into that multisampled target and resolves into
+// `view`. Shared by the on-screen path and offscreen captures so both draw
+// identically.
+fn draw_frame(
+    renderer: &WebGpuRenderer,
+    encoder: &mut CommandEncoder,
+    view: &TextureView,
+    msaa: Option<&TextureView>,
+    frame: &Frame,
+    label: &str,
+) {
+    let [r, g, b, a] = frame.clear.map(f64::from);
+    let vertex_buffer = (!frame.vertices.is_empty()).then(|| {
+        renderer.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", label)),
+            contents: bytemuck::cast_slice(&frame.vertex_data()),
+            usage: BufferUsages::VERTEX,
+        })
+    });
+    
+    // Begin render pass with CLEAR - remove whatever was drawn before
+    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some(&format!("{} Pass", label)),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: msaa.unwrap_or(view),
+            resolve_target: msaa.map(|_| view),
+            ops: Operations {
+                load: LoadOp::Clear(Color { r, g, b, a }),
+                // Only the resolved samples are needed afterwards
+                store: if msaa.is_some() { StoreOp::Discard } else { StoreOp::Store },
+            },
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    
+    if let Some(vertex_buffer) = &vertex_buffer {
+        render_pass.set_pipeline(&renderer.render_pipeline);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(0..frame.vertex_count(), 0..1);
+    }
+}
+
 // Export a `hello` function that renders via WebGPU
 #[wasm_bindgen]
 pub fn hello() -> Result<(), JsValue> {
@@ -606,219 +450,797 @@ pub fn greet(name: &str) -> Result<String, JsValue> {
     Ok(greeting)
 }
 
-// Memory management exports for direct WASM memory writes
+// Append a chunk copied in from JavaScript. To reuse one staging buffer across appends use `IngestBuffer`.
+// With a source key, an unchanged source is skipped and a changed one replaces its old rows.
 #[wasm_bindgen]
-pub fn alloc(size: usize) -> *mut u8 {
-    let layout = Layout::from_size_align(size, 1).unwrap();
-    unsafe { std_alloc(layout) }
+pub fn append_chunk(data: &[u8], source_key: Option<String>, last_modified: Option<String>) -> Result<(), JsValue> {
+    let options = IngestOptions {
+        source: source_key,
+        last_modified,
+        ..Default::default()
+    };
+    run_ingest(data, &options).map(|_| ())
 }
 
+// Legacy parse function - kept for compatibility but avoid using in polling loops
 #[wasm_bindgen]
-pub fn free(ptr: *mut u8, size: usize) {
-    let layout = Layout::from_size_align(size, 1).unwrap();
-    unsafe { dealloc(ptr, layout) }
+pub fn parse_arrow_replay(data: &[u8]) -> Result<JsValue, JsValue> {
+    let options = IngestOptions {
+        snapshot: true,
+        ..Default::default()
+    };
+    ingest_with_options(data, &options)
 }
 
-// Direct append from memory pointer - NO REBUILDING STATE
+// General ingestion entry point: `options` is `{ snapshot, mode, job }`, all optional.
+// Returns the replay snapshot when `snapshot` is set, otherwise undefined.
 #[wasm_bindgen]
-pub fn append_chunk(ptr: *const u8, len: usize) -> Result<(), JsValue> {
-    let data = unsafe { std::slice::from_raw_parts(ptr, len) };
-    console_log!("📦 Appending {} bytes directly to WASM state", len);
+pub fn ingest(data: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+    ingest_with_options(data, &ingest_options(options)?)
+}
+
+// Parse ingest options passed from JavaScript; undefined or null means defaults
+fn ingest_options(value: JsValue) -> Result<IngestOptions, JsValue> {
+    if value.is_undefined() || value.is_null() {
+        return Ok(IngestOptions::default());
+    }
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|e| JsValue::from_str(&format!("Invalid ingest options: {:?}", e)))
+}
+
+fn ingest_with_options(data: &[u8], options: &IngestOptions) -> Result<JsValue, JsValue> {
+    match run_ingest(data, options)? {
+        // Return data to JavaScript
+        Some(replay_data) => serde_wasm_bindgen::to_value(&replay_data)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize replay data: {:?}", e))),
+        None => Ok(JsValue::UNDEFINED),
+    }
+}
+
+// Single ingestion path behind every entry point - NO REBUILDING STATE
+fn run_ingest(data: &[u8], options: &IngestOptions) -> Result<Option<ReplayData>, JsValue> {
+    console_log!("📦 Ingesting {} bytes ({:?})", data.len(), options.mode);
     
-    // Parse the chunk
-    let cursor = Cursor::new(data);
-    let reader = FileReader::try_new(cursor, None)
-        .map_err(|e| JsValue::from_str(&format!("Failed to create Arrow reader: {:?}", e)))?;
+    let outcome = REPLAY_STATE.with(|r| {
+        ingest::ingest(&mut r.borrow_mut(), &ArrowIpcDecoder, data, options)
+    }).map_err(js_error)?;
     
-    let mut new_service_ids = Vec::new();
-    let mut unique_ids = std::collections::HashSet::new();
+    if outcome.skipped {
+        console_log!("⏭️ Source {:?} unchanged ({:?}), skipping", options.source, options.last_modified);
+        return Ok(outcome.snapshot);
+    }
     
-    // Read all record batches and collect NEW service IDs
-    for maybe_batch in reader {
-        let batch = maybe_batch
-            .map_err(|e| JsValue::from_str(&format!("Failed to read batch: {:?}", e)))?;
-        
-        // Extract job IDs from the current basic schema
-        if let Some(id_column) = batch.column_by_name("id") {
-            if let Some(id_array) = id_column.as_any().downcast_ref::<StringArray>() {
-                for i in 0..id_array.len() {
-                    let id_value = id_array.value(i);
-                    if unique_ids.insert(id_value.to_string()) {
-                        new_service_ids.push(id_value.to_string());
-                    }
-                }
-            }
-        }
+    let stats = outcome.stats;
+    console_log!("✅ Ingested {} batches: {} new services added, {} spans added, {} duplicates, {} evicted",
+                outcome.batches, stats.new_services, stats.added_spans, stats.duplicate_spans, stats.evicted_spans);
+    
+    Ok(outcome.snapshot)
+}
+
+// Configure how much replay data is kept in memory; pass undefined to lift a limit.
+// Span, age and service limits apply to each job, and max_jobs bounds the number of jobs.
+// A NaN, infinite or negative max_age_ms is rejected and the policy left unchanged.
+#[wasm_bindgen]
+pub fn set_retention_policy(
+    max_spans: Option<usize>,
+    max_age_ms: Option<f64>,
+    max_services: Option<usize>,
+    max_jobs: Option<usize>,
+) -> Result<(), JsValue> {
+    let max_age_ns = max_age_ms
+        .map(|ms| RetentionPolicy::max_age_from_ms(ms).ok_or_else(|| format!("Invalid max_age_ms: {}", ms)))
+        .transpose()
+        .map_err(|e| JsValue::from_str(&e))?;
+    let policy = RetentionPolicy { max_spans, max_age_ns, max_services, max_jobs };
+    
+    let stats = REPLAY_STATE.with(|r| r.borrow_mut().set_policy(policy));
+    console_log!("🧹 Retention policy updated: {:?} ({} spans, {} services, {} jobs evicted)",
+                policy, stats.evicted_spans, stats.evicted_services, stats.evicted_jobs);
+    Ok(())
+}
+
+// Get retained span count for memory monitoring
+#[wasm_bindgen]
+pub fn get_span_count() -> usize {
+    REPLAY_STATE.with(|r| r.borrow().span_count())
+}
+
+// Remove every span contributed by a source file, e.g. when the backend deletes it.
+// Returns the number of spans removed.
+#[wasm_bindgen]
+pub fn remove_source(source_key: &str) -> Result<usize, JsValue> {
+    let stats = REPLAY_STATE.with(|r| r.borrow_mut().remove_source(source_key))
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown source: {}", source_key)))?;
+    console_log!("🗑️ Removed source '{}': {} spans, {} services dropped",
+                source_key, stats.removed_spans, stats.evicted_services);
+    Ok(stats.removed_spans)
+}
+
+// List ingested source files with per-batch provenance
+#[wasm_bindgen]
+pub fn list_sources() -> Result<JsValue, JsValue> {
+    let sources = REPLAY_STATE.with(|r| r.borrow().source_summaries());
+    serde_wasm_bindgen::to_value(&sources)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize sources: {:?}", e)))
+}
+
+// List every ingested job with its service and span counts
+#[wasm_bindgen]
+pub fn list_jobs() -> Result<JsValue, JsValue> {
+    let jobs = REPLAY_STATE.with(|r| r.borrow().jobs());
+    serde_wasm_bindgen::to_value(&jobs)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize jobs: {:?}", e)))
+}
+
+#[wasm_bindgen]
+pub fn get_active_job() -> Option<String> {
+    REPLAY_STATE.with(|r| r.borrow().active_job().map(str::to_string))
+}
+
+// Switch which job drives rendering; the service rotation restarts from the first service
+#[wasm_bindgen]
+pub fn set_active_job(job_id: &str) -> Result<(), JsValue> {
+    let found = REPLAY_STATE.with(|r| r.borrow_mut().set_active_job(job_id));
+    if !found {
+        return Err(JsValue::from_str(&format!("Unknown job: {}", job_id)));
     }
     
-    // APPEND to existing state - never replace
-    let added_count = REPLAY_DATA.with(|r| {
-        let mut borrowed = r.borrow_mut();
-        
-        match borrowed.as_mut() {
-            Some(existing_data) => {
-                let mut all_existing_ids: std::collections::HashSet<String> = 
-                    existing_data.all_service_ids.iter().cloned().collect();
-                
-                let mut added_new = 0;
-                for new_id in new_service_ids {
-                    if all_existing_ids.insert(new_id.clone()) {
-                        existing_data.all_service_ids.push(new_id);
-                        added_new += 1;
-                    }
-                }
-                
-                if added_new > 0 {
-                    // Regenerate services list only if new services were added
-                    existing_data.services = existing_data.all_service_ids.iter().enumerate().map(|(i, id)| {
-                        ServiceNode {
-                            id: id.clone(),
-                            x: (i as f32 * 0.3) - (existing_data.all_service_ids.len() as f32 * 0.15),
-                            y: 0.0,
-                            status: "healthy".to_string(),
-                        }
-                    }).collect();
-                }
-                
-                existing_data.timestamp = chrono::Utc::now().to_rfc3339();
-                added_new
-            },
-            None => {
-                // First time - create new data
-                let services: Vec<ServiceNode> = new_service_ids.iter().enumerate().map(|(i, id)| {
-                    ServiceNode {
-                        id: id.clone(),
-                        x: (i as f32 * 0.3) - (new_service_ids.len() as f32 * 0.15),
-                        y: 0.0,
-                        status: "healthy".to_string(),
-                    }
-                }).collect();
-                
-                let data = ReplayData {
-                    services,
-                    all_service_ids: new_service_ids.clone(),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                    job_id: new_service_ids.first().unwrap_or(&String::new()).clone(),
-                };
-                
-                *borrowed = Some(data);
-                new_service_ids.len()
-            }
+    GPU_RENDERER.with(|r| {
+        if let Some(renderer) = r.borrow_mut().as_mut() {
+            renderer.current_service_index = 0;
         }
     });
+    // The selected trace belonged to the previous job
+    CRITICAL_PATH.with(|c| *c.borrow_mut() = None);
     
-    console_log!("✅ Appended chunk: {} new services added", added_count);
+    console_log!("🗂️ Active job set to '{}'", job_id);
     Ok(())
 }
 
-// Legacy parse function - kept for compatibility but avoid using in polling loops
+// Compare the services seen in two jobs
 #[wasm_bindgen]
-pub fn parse_arrow_replay(data: &[u8]) -> Result<JsValue, JsValue> {
-    console_log!("🏹 Parsing Arrow IPC file, {} bytes", data.len());
-    
-    let cursor = Cursor::new(data);
-    let reader = FileReader::try_new(cursor, None)
-        .map_err(|e| JsValue::from_str(&format!("Failed to create Arrow reader: {:?}", e)))?;
-    
-    let mut new_service_ids = Vec::new();
-    let mut unique_ids = std::collections::HashSet::new();
-    let mut job_id = String::new();
-    
-    // Read all record batches and collect ALL service IDs
-    for maybe_batch in reader {
-        let batch = maybe_batch
-            .map_err(|e| JsValue::from_str(&format!("Failed to read batch: {:?}", e)))?;
-        
-        console_log!("📊 Processing batch with {} rows, {} columns", batch.num_rows(), batch.num_columns());
-        
-        // Extract job IDs from the current basic schema
-        if let Some(id_column) = batch.column_by_name("id") {
-            if let Some(id_array) = id_column.as_any().downcast_ref::<StringArray>() {
-                for i in 0..id_array.len() {
-                    let id_value = id_array.value(i);
-                    
-                    // Add to unique set to avoid duplicates (no logging per ID)
-                    if unique_ids.insert(id_value.to_string()) {
-                        new_service_ids.push(id_value.to_string());
-                    }
-                    
-                    // Keep the last job_id for backwards compatibility
-                    job_id = id_value.to_string();
-                }
+pub fn compare_jobs(job_a: &str, job_b: &str) -> Result<JsValue, JsValue> {
+    let comparison = REPLAY_STATE.with(|r| r.borrow().compare_jobs(job_a, job_b))
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown job: {} or {}", job_a, job_b)))?;
+    serde_wasm_bindgen::to_value(&comparison)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize job comparison: {:?}", e)))
+}
+
+// Filter every view by a span expression, e.g.
+// `service = "checkout" AND duration > 200ms AND status = error`.
+// `mode` is "dim" (default) to fade services without a matching span or
+// "hide" to drop them; an empty expression clears the filter. Returns
+// `{ expr, mode, matching_services, matching_spans, total_services, total_spans }`,
+// or throws `{ message, start, end }` with the position of a parse error.
+#[wasm_bindgen]
+pub fn set_filter(expr: &str, mode: Option<String>) -> Result<JsValue, JsValue> {
+    if expr.trim().is_empty() {
+        clear_filter();
+        return Ok(JsValue::NULL);
+    }
+    let mode: FilterMode = mode.as_deref().unwrap_or("dim").parse().map_err(|e: String| JsValue::from_str(&e))?;
+    
+    let summary = REPLAY_STATE.with(|r| r.borrow_mut().set_filter(expr, mode)).map_err(|e| {
+        console_log!("❌ Filter rejected: {}", e);
+        serde_wasm_bindgen::to_value(&e).unwrap_or_else(|_| js_error(&e))
+    })?;
+    console_log!("🔎 Filter '{}' ({:?}): {}/{} services, {}/{} spans",
+                summary.expr, summary.mode, summary.matching_services, summary.total_services,
+                summary.matching_spans, summary.total_spans);
+    
+    refresh_filtered_view();
+    serde_wasm_bindgen::to_value(&summary)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize filter summary: {:?}", e)))
+}
+
+// Remove the active filter so every service shows again
+#[wasm_bindgen]
+pub fn clear_filter() {
+    if REPLAY_STATE.with(|r| r.borrow_mut().clear_filter()) {
+        console_log!("🔎 Filter cleared");
+        refresh_filtered_view();
+    }
+}
+
+// The active filter's summary, or null when none is set
+#[wasm_bindgen]
+pub fn get_filter() -> Result<JsValue, JsValue> {
+    match REPLAY_STATE.with(|r| r.borrow().filter_summary()) {
+        Some(summary) => serde_wasm_bindgen::to_value(&summary)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize filter summary: {:?}", e))),
+        None => Ok(JsValue::NULL),
+    }
+}
+
+// Redraw after the filter changed: the rotation may have lost services, so
+// keep the index in range and show the current one with its new styling
+fn refresh_filtered_view() {
+    let count = get_service_count();
+    GPU_RENDERER.with(|r| {
+        if let Some(renderer) = r.borrow_mut().as_mut() {
+            if renderer.current_service_index >= count {
+                renderer.current_service_index = 0;
             }
         }
-    }
-    
-    console_log!("🎯 Parsed {} unique service IDs from this file: {:?}", new_service_ids.len(), new_service_ids);
+    });
+    LAST_SERVICE_COUNT.with(|r| *r.borrow_mut() = count);
     
-    // APPEND to existing state instead of replacing it
-    let replay_data = REPLAY_DATA.with(|r| {
-        let mut borrowed = r.borrow_mut();
-        
-        match borrowed.as_mut() {
-            Some(existing_data) => {
-                // Merge new services with existing ones
-                let mut all_existing_ids: std::collections::HashSet<String> = 
-                    existing_data.all_service_ids.iter().cloned().collect();
-                
-                let mut added_new = false;
-                for new_id in new_service_ids {
-                    if all_existing_ids.insert(new_id.clone()) {
-                        existing_data.all_service_ids.push(new_id.clone());
-                        added_new = true;
-                    }
-                }
-                
-                if added_new {
-                    // Regenerate services list with new positions
-                    existing_data.services = existing_data.all_service_ids.iter().enumerate().map(|(i, id)| {
-                        ServiceNode {
-                            id: id.clone(),
-                            x: (i as f32 * 0.3) - (existing_data.all_service_ids.len() as f32 * 0.15),
-                            y: 0.0,
-                            status: "healthy".to_string(),
-                        }
-                    }).collect();
-                    
-                    console_log!("📈 Merged services: {} total services", existing_data.all_service_ids.len());
-                } else {
-                    console_log!("✅ No new services - existing data intact");
-                }
-                
-                existing_data.timestamp = chrono::Utc::now().to_rfc3339();
-                existing_data.job_id = job_id;
-                existing_data.clone()
-            },
-            None => {
-                // First time - create new data
-                console_log!("🆕 Creating initial replay data");
-                let services: Vec<ServiceNode> = new_service_ids.iter().enumerate().map(|(i, id)| {
-                    ServiceNode {
-                        id: id.clone(),
-                        x: (i as f32 * 0.3) - (new_service_ids.len() as f32 * 0.15),
-                        y: 0.0,
-                        status: "healthy".to_string(),
-                    }
-                }).collect();
-                
-                let data = ReplayData {
-                    services,
-                    all_service_ids: new_service_ids,
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                    job_id,
-                };
-                
-                *borrowed = Some(data.clone());
-                data
-            }
+    if !ANIMATION_RUNNING.with(|r| *r.borrow()) {
+        return;
+    }
+    if count == 0 {
+        if let Err(e) = clear_canvas() {
+            console_log!("❌ Failed to clear canvas: {:?}", e);
         }
+    } else {
+        render_current_service();
+    }
+}
+
+// Search the active job's services, operations, span IDs and attribute values.
+// Returns up to `limit` (default 20) results, best first:
+// `{ kind, service, label, score, spans, ... }`
+#[wasm_bindgen]
+pub fn search(query: &str, limit: Option<usize>) -> Result<JsValue, JsValue> {
+    let results = REPLAY_STATE.with(|r| r.borrow().search(query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)));
+    console_log!("🔍 Search '{}': {} results", query, results.len());
+    serde_wasm_bindgen::to_value(&results)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize search results: {:?}", e)))
+}
+
+// Fly to a service: draw the service map with the camera zoomed in on the
+// service's node and the node outlined, captioned with `caption`. Like
+// `show_service_map`, the caller holds the rotation while the map is up.
+// Returns the service's index in the rotation so a worker-driven loop can
+// continue from there.
+#[wasm_bindgen]
+pub fn focus_service(service_id: &str, caption: Option<String>) -> Result<usize, JsValue> {
+    let (services, groups) = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        (state.playback_services(), state.groups())
     });
+    let index = services.iter().position(|s| s.id == service_id).ok_or_else(|| {
+        JsValue::from_str(&format!("Service '{}' is not shown; it may be hidden by the filter", service_id))
+    })?;
+    let camera = map::focus(&services, &groups, service_id).expect("shown services are on the map");
     
-    console_log!("✅ Updated replay data with {} total services", replay_data.services.len());
+    GPU_RENDERER.with(|r| {
+        if let Some(renderer) = r.borrow_mut().as_mut() {
+            renderer.current_service_index = index;
+        }
+    });
+    HIGHLIGHT.with(|h| *h.borrow_mut() = Some((service_id.to_string(), caption.unwrap_or_default())));
+    let previous = CAMERA.with(|c| std::mem::replace(&mut *c.borrow_mut(), camera));
+    UNFOCUSED_CAMERA.with(|u| {
+        u.borrow_mut().get_or_insert(previous);
+    });
     
-    // Return data to JavaScript
-    serde_wasm_bindgen::to_value(&replay_data)
-        .map_err(|e| JsValue::from_str(&format!("Failed to serialize replay data: {:?}", e)))
+    console_log!("🎯 Flying to service '{}' (index {}) at ({:.2}, {:.2})",
+                service_id, index, camera.center[0], camera.center[1]);
+    show_service_map()?;
+    Ok(index)
+}
+
+// Fly to a search result of any kind: a service, operation, attribute value
+// or span is shown at its service's node, captioned with the result's
+// label. Takes a result as returned by `search`; see `focus_service`.
+#[wasm_bindgen]
+pub fn focus_result(result: JsValue) -> Result<usize, JsValue> {
+    let target: SearchTarget = serde_wasm_bindgen::from_value(result)
+        .map_err(|e| JsValue::from_str(&format!("Invalid search result: {}", e)))?;
+    focus_service(target.service(), Some(target.label()))
+}
+
+// Drop the search highlight and zoom back out to the camera from before
+// the flight. The caller redraws.
+#[wasm_bindgen]
+pub fn clear_highlight() {
+    HIGHLIGHT.with(|h| *h.borrow_mut() = None);
+    if let Some(camera) = UNFOCUSED_CAMERA.with(|u| u.borrow_mut().take()) {
+        CAMERA.with(|c| *c.borrow_mut() = camera);
+    }
+}
+
+// The active job's traces, slowest first: up to `limit` (default 50) of
+// `{ trace_id, span_count, root_service, start_ns, duration_ns }`
+#[wasm_bindgen]
+pub fn list_traces(limit: Option<usize>) -> Result<JsValue, JsValue> {
+    let traces = REPLAY_STATE.with(|r| r.borrow().traces(limit.unwrap_or(DEFAULT_TRACE_LIMIT)));
+    serde_wasm_bindgen::to_value(&traces)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize traces: {:?}", e)))
+}
+
+// Compute a trace's critical path and select it, so the service view
+// outlines services on the path. Returns
+// `{ trace_id, root_span_id, start_ns, duration_ns, segments, span_ids, services }`
+#[wasm_bindgen]
+pub fn critical_path(trace_id: &str) -> Result<JsValue, JsValue> {
+    let path = REPLAY_STATE.with(|r| r.borrow().critical_path(trace_id)).map_err(js_error)?;
+    console_log!("🧭 Critical path of trace '{}': {} segments over {} spans",
+                trace_id, path.segments.len(), path.span_ids.len());
+    let value = serde_wasm_bindgen::to_value(&path)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize critical path: {:?}", e)))?;
+    CRITICAL_PATH.with(|c| *c.borrow_mut() = Some(path));
+    Ok(value)
+}
+
+// Draw a trace's waterfall with its critical path highlighted and the
+// active filter applied to its spans. The caller pauses the service
+// rotation while the waterfall is up; the path stays selected afterwards.
+// Returns the same value as `critical_path`.
+#[wasm_bindgen]
+pub fn show_trace(trace_id: &str) -> Result<JsValue, JsValue> {
+    let value = critical_path(trace_id)?;
+    let scene = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        let spans = state.trace(trace_id);
+        let path = CRITICAL_PATH.with(|c| c.borrow().clone());
+        let theme = THEME.with(|t| *t.borrow());
+        let filter = state.trace_match(trace_id);
+        let annotations = state.annotations();
+        waterfall::waterfall_scene(&spans, path.as_ref(), annotations, filter.as_ref(), state.labels(), &theme)
+    });
+    present_scene(scene, &format!("Trace {}", trace_id))?;
+    Ok(value)
+}
+
+// Deselect the critical path; services are no longer outlined for it
+#[wasm_bindgen]
+pub fn clear_trace() {
+    CRITICAL_PATH.with(|c| *c.borrow_mut() = None);
+}
+
+// Parse both sides of a diff, each `{ job, start_ns?, end_ns? }`, and
+// compare them
+fn replay_diff(before: JsValue, after: JsValue) -> Result<ReplayDiff, JsValue> {
+    let side = |value: JsValue| -> Result<DiffSide, JsValue> {
+        serde_wasm_bindgen::from_value(value)
+            .map_err(|e| JsValue::from_str(&format!("Invalid diff side: {}", e)))
+    };
+    let (before, after) = (side(before)?, side(after)?);
+    let unknown = format!("Unknown job: {} or {}", before.job, after.job);
+    REPLAY_STATE
+        .with(|r| r.borrow().diff(before, after))
+        .ok_or_else(|| JsValue::from_str(&unknown))
+}
+
+// Compare two jobs, or two time windows of one job. Returns
+// `{ before, after, services: [{ service, change, before, after, rate_delta, ... }], edges }`
+#[wasm_bindgen]
+pub fn diff_replays(before: JsValue, after: JsValue) -> Result<JsValue, JsValue> {
+    let replay_diff = replay_diff(before, after)?;
+    console_log!("🔀 Diff {} vs {}: {} services, {} edges",
+                replay_diff.before.job, replay_diff.after.job,
+                replay_diff.services.len(), replay_diff.edges.len());
+    serde_wasm_bindgen::to_value(&replay_diff)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize diff: {:?}", e)))
+}
+
+// Draw the diff as a service map colored by delta, faded or trimmed by the
+// active filter. Like `show_trace`, the caller pauses the service rotation
+// while the map is up. Returns the same value as `diff_replays`.
+#[wasm_bindgen]
+pub fn show_diff(before: JsValue, after: JsValue) -> Result<JsValue, JsValue> {
+    let replay_diff = replay_diff(before, after)?;
+    let label = format!("Diff {} vs {}", replay_diff.before.job, replay_diff.after.job);
+    let theme = THEME.with(|t| *t.borrow());
+    let scene = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        let filter = state.diff_match(&replay_diff);
+        diff::diff_scene(&replay_diff, filter.as_ref(), state.labels(), &theme)
+    });
+    present_scene(scene, &label)?;
+    serde_wasm_bindgen::to_value(&replay_diff)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize diff: {:?}", e)))
+}
+
+// Anomalies detected in the active job, as timeline markers:
+// `{ start_ns, end_ns, markers: [{ label, service, metric, kind, severity, start_ns, end_ns, ... }] }`
+#[wasm_bindgen]
+pub fn anomalies() -> Result<JsValue, JsValue> {
+    let timeline = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        AnomalyTimeline::new(state.time_range(), state.anomalies())
+    });
+    serde_wasm_bindgen::to_value(&timeline)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize anomalies: {:?}", e)))
+}
+
+// Tune anomaly detection, e.g. `{ window_ns, z_threshold }`; omitted fields
+// keep their defaults. Detection reruns on every job and service statuses
+// follow. Returns the number of anomalies in the active job.
+#[wasm_bindgen]
+pub fn set_anomaly_options(options: JsValue) -> Result<usize, JsValue> {
+    let options: AnomalyOptions = if options.is_undefined() || options.is_null() {
+        AnomalyOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid anomaly options: {}", e)))?
+    };
+    let found = REPLAY_STATE.with(|r| {
+        let mut state = r.borrow_mut();
+        state.set_anomaly_options(options);
+        state.anomalies().len()
+    });
+    console_log!("📈 Anomaly detection rerun: {} anomalies in the active job", found);
+    refresh_filtered_view();
+    Ok(found)
+}
+
+// The active job's annotations, user-added and detected, in time order:
+// `{ start_ns, end_ns, annotations: [{ id, timestamp_ns, end_ns, kind, label, service, severity, source }] }`
+#[wasm_bindgen]
+pub fn annotations() -> Result<JsValue, JsValue> {
+    let timeline = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        AnnotationTimeline::new(state.time_range(), state.annotations())
+    });
+    serde_wasm_bindgen::to_value(&timeline)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize annotations: {:?}", e)))
+}
+
+// Annotate the active job, e.g. `{ timestamp_ns, kind: "deploy", label: "v2.3" }`.
+// `kind` is deploy, incident, anomaly, bookmark (default) or note; `end_ns`,
+// `service` and `severity` are optional. Returns the new annotation's id.
+#[wasm_bindgen]
+pub fn add_annotation(annotation: JsValue) -> Result<u32, JsValue> {
+    let annotation: NewAnnotation = serde_wasm_bindgen::from_value(annotation)
+        .map_err(|e| JsValue::from_str(&format!("Invalid annotation: {}", e)))?;
+    let id = REPLAY_STATE.with(|r| r.borrow_mut().add_annotation(annotation))
+        .ok_or_else(|| JsValue::from_str("No active job to annotate"))?;
+    console_log!("📌 Added annotation {}", id);
+    Ok(id)
+}
+
+// Remove a user annotation; detected ones follow their anomalies
+#[wasm_bindgen]
+pub fn remove_annotation(id: u32) -> bool {
+    REPLAY_STATE.with(|r| r.borrow_mut().remove_annotation(id))
+}
+
+// The active job's user annotations, for saving alongside a session
+#[wasm_bindgen]
+pub fn export_annotations() -> Result<JsValue, JsValue> {
+    let saved = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        state.active().map(|job| job.annotations.user()).unwrap_or_default()
+    });
+    serde_wasm_bindgen::to_value(&saved)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize annotations: {:?}", e)))
+}
+
+// Replace the active job's user annotations with ones from `export_annotations`
+#[wasm_bindgen]
+pub fn import_annotations(saved: JsValue) -> Result<usize, JsValue> {
+    let saved: Vec<Annotation> = serde_wasm_bindgen::from_value(saved)
+        .map_err(|e| JsValue::from_str(&format!("Invalid annotations: {}", e)))?;
+    let count = saved.len();
+    if !REPLAY_STATE.with(|r| r.borrow_mut().load_annotations(saved)) {
+        return Err(JsValue::from_str("No active job to annotate"));
+    }
+    console_log!("📌 Imported {} annotations", count);
+    Ok(count)
+}
+
+// Jump to the annotation after `from` (the first one when omitted), or the
+// one before it when `forward` is false. An annotation tied to a shown
+// service flies there, captioned with its label. Returns
+// `{ annotation, service_index }`, or null past either end.
+#[wasm_bindgen]
+pub fn jump_to_annotation(from: Option<u32>, forward: bool) -> Result<JsValue, JsValue> {
+    let Some(annotation) = REPLAY_STATE.with(|r| r.borrow().step_annotation(from, forward).cloned()) else {
+        return Ok(JsValue::NULL);
+    };
+    let shown = annotation.service.as_ref().filter(|service| {
+        REPLAY_STATE.with(|r| r.borrow().playback_services().iter().any(|s| &s.id == *service))
+    });
+    let service_index = match shown {
+        Some(service) => Some(focus_service(service, Some(annotation.label.clone()))?),
+        None => None,
+    };
+    console_log!("⏭️ Jumped to annotation {} '{}'", annotation.id, annotation.label);
+    serde_wasm_bindgen::to_value(&AnnotationJump { annotation, service_index })
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize annotation: {:?}", e)))
+}
+
+// Pan and zoom the view: `(x, y)` is the clip-space point shown at the
+// centre of the canvas. The scene on screen is redrawn immediately.
+#[wasm_bindgen]
+pub fn set_camera(x: f32, y: f32, zoom: f32) -> Result<(), JsValue> {
+    if !(zoom.is_finite() && zoom > 0.0 && x.is_finite() && y.is_finite()) {
+        return Err(JsValue::from_str(&format!("Invalid camera: ({}, {}) zoom {}", x, y, zoom)));
+    }
+    CAMERA.with(|c| *c.borrow_mut() = Camera { center: [x, y], zoom });
+    // An explicit camera replaces the view a search flight would return to
+    UNFOCUSED_CAMERA.with(|u| *u.borrow_mut() = None);
+    let scene = SCENE.with(|s| s.borrow().clone());
+    present_scene(scene, "Camera")
+}
+
+// Switch every view to a built-in theme by name ("dark", "light",
+// "deuteranopia", "protanopia") or to custom colors, where missing colors
+// come from the dark theme; null restores dark. The caller redraws the view.
+#[wasm_bindgen]
+pub fn set_theme(theme: JsValue) -> Result<(), JsValue> {
+    let theme = if theme.is_undefined() || theme.is_null() {
+        Theme::dark()
+    } else if let Some(name) = theme.as_string() {
+        Theme::named(&name).map_err(js_error)?
+    } else {
+        serde_wasm_bindgen::from_value(theme)
+            .map_err(|e| JsValue::from_str(&format!("Invalid theme: {}", e)))?
+    };
+    THEME.with(|t| *t.borrow_mut() = theme);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn get_theme() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&THEME.with(|t| *t.borrow()))
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize theme: {:?}", e)))
+}
+
+// Tune level of detail: `{ enabled, min_label_px, point_radius_px,
+// bundle_cell_px, hysteresis }`, each optional; null restores the defaults.
+// The scene on screen is redrawn.
+#[wasm_bindgen]
+pub fn set_lod_options(options: JsValue) -> Result<(), JsValue> {
+    let options: LodOptions = if options.is_undefined() || options.is_null() {
+        LodOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid LOD options: {}", e)))?
+    };
+    LOD.with(|l| *l.borrow_mut() = Lod::new(options));
+    let scene = SCENE.with(|s| s.borrow().clone());
+    present_scene(scene, "LOD")
+}
+
+#[wasm_bindgen]
+pub fn get_lod_options() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&LOD.with(|l| *l.borrow().options()))
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize LOD options: {:?}", e)))
+}
+
+// `{ center: [x, y], zoom }`
+#[wasm_bindgen]
+pub fn get_camera() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&CAMERA.with(|c| *c.borrow()))
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize camera: {:?}", e)))
+}
+
+// Draw every shown service at its layout position, with the calls between
+// them, pinned services ringed, groups outlined or collapsed and the search
+// highlight, if any, ringed. Like `show_trace`, the caller pauses the
+// service rotation while the map is up, and redraws it after pinning or
+// grouping.
+#[wasm_bindgen]
+pub fn show_service_map() -> Result<(), JsValue> {
+    let scene = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        let edges = state.service_edges();
+        let edges = edges.iter().map(|(from, to)| (from.as_str(), to.as_str()));
+        let services = state.playback_services();
+        let (theme, bundling) = (THEME.with(|t| *t.borrow()), EDGE_BUNDLING.with(|b| *b.borrow()));
+        let (pins, groups, filter) = (state.pins(), state.groups(), state.filter_match());
+        let mut scene = map::map_scene(&services, edges, &pins, filter, &groups, state.labels(), &theme, bundling);
+        if let Some((service, caption)) = HIGHLIGHT.with(|h| h.borrow().clone()) {
+            map::highlight(&mut scene, &services, &groups, &service, &caption, &theme);
+        }
+        scene
+    });
+    present_scene(scene, "Service map")
+}
+
+// Bundle service map edges through the grouping hierarchy: 0 draws straight
+// edges, 1 follows the hierarchy fully. The caller redraws the map.
+#[wasm_bindgen]
+pub fn set_edge_bundling(strength: f32) -> Result<(), JsValue> {
+    if !(0.0..=1.0).contains(&strength) {
+        return Err(JsValue::from_str(&format!("Invalid edge bundling: {}", strength)));
+    }
+    EDGE_BUNDLING.with(|b| *b.borrow_mut() = strength);
+    Ok(())
+}
+
+// What is under clip-space `(x, y)` on the service map:
+// `{ kind: "service" | "group", id }`, or null
+#[wasm_bindgen]
+pub fn pick_node(x: f32, y: f32) -> Result<JsValue, JsValue> {
+    let point = CAMERA.with(|c| c.borrow().unproject([x, y]));
+    let picked = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        map::pick(&state.playback_services(), &state.groups(), point)
+    });
+    serde_wasm_bindgen::to_value(&picked)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize pick: {:?}", e)))
+}
+
+// Group services on the map by name prefix (`"prefix"`) or by a span
+// attribute (`{ attribute: "k8s.namespace" }`); null stops grouping. Every
+// group starts expanded. Returns the number of groups.
+#[wasm_bindgen]
+pub fn set_grouping(by: JsValue) -> Result<usize, JsValue> {
+    let by: Option<GroupBy> = serde_wasm_bindgen::from_value(by)
+        .map_err(|e| JsValue::from_str(&format!("Invalid grouping: {}", e)))?;
+    let count = REPLAY_STATE.with(|r| {
+        let mut state = r.borrow_mut();
+        state.set_grouping(by);
+        state.groups().len()
+    });
+    console_log!("🗂️ Grouped services into {} groups", count);
+    Ok(count)
+}
+
+// The shown services' groups in name order:
+// `[{ name, members, collapsed, status, metrics: { spans, errors, rate_per_s, error_rate, mean_latency_ns } }]`
+#[wasm_bindgen]
+pub fn groups() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&REPLAY_STATE.with(|r| r.borrow().groups()))
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize groups: {:?}", e)))
+}
+
+// Collapse a group into one node on the map, or expand it again
+#[wasm_bindgen]
+pub fn collapse_group(name: &str, collapsed: bool) -> Result<(), JsValue> {
+    if !REPLAY_STATE.with(|r| r.borrow_mut().collapse_group(name, collapsed)) {
+        return Err(JsValue::from_str(&format!("Unknown group: {}", name)));
+    }
+    Ok(())
+}
+
+// How service IDs become labels in every view and export, e.g.
+// `{ pattern: "^(\\w+)-[0-9a-f]+$", aliases: { "legacy-gw": "gateway" } }`.
+// Fields are `aliases`, `column` (a span attribute holding the display
+// name), `pattern` with an optional `template` such as `"$1"`,
+// `strip_prefixes` and `strip_suffixes`; all are optional. Takes effect
+// from the next render.
+#[wasm_bindgen]
+pub fn set_label_rules(rules: JsValue) -> Result<(), JsValue> {
+    let rules: LabelRules = if rules.is_undefined() || rules.is_null() {
+        LabelRules::default()
+    } else {
+        serde_wasm_bindgen::from_value(rules)
+            .map_err(|e| JsValue::from_str(&format!("Invalid label rules: {}", e)))?
+    };
+    REPLAY_STATE.with(|r| r.borrow_mut().set_label_rules(rules))
+        .map_err(|e| JsValue::from_str(&format!("Invalid label pattern: {}", e)))?;
+    console_log!("🏷️ Label rules updated");
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn get_label_rules() -> Result<JsValue, JsValue> {
+    let rules = REPLAY_STATE.with(|r| r.borrow().labels().rules().clone());
+    serde_wasm_bindgen::to_value(&rules)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize label rules: {:?}", e)))
+}
+
+// The label views draw for a service
+#[wasm_bindgen]
+pub fn display_name(service_id: &str) -> String {
+    REPLAY_STATE.with(|r| r.borrow().labels().label(service_id))
+}
+
+// Pin a service at layout position `(x, y)`. It stays there through later
+// appends and re-layouts until unpinned.
+#[wasm_bindgen]
+pub fn pin_node(id: &str, x: f32, y: f32) -> Result<(), JsValue> {
+    if !(x.is_finite() && y.is_finite()) {
+        return Err(JsValue::from_str(&format!("Invalid position: ({}, {})", x, y)));
+    }
+    if !REPLAY_STATE.with(|r| r.borrow_mut().pin_node(id, [x, y])) {
+        return Err(JsValue::from_str("No active job to pin services in"));
+    }
+    Ok(())
+}
+
+// Return a service to the automatic layout. False if it was not pinned.
+#[wasm_bindgen]
+pub fn unpin(id: &str) -> bool {
+    REPLAY_STATE.with(|r| r.borrow_mut().unpin(id))
+}
+
+// Move a service to follow the pointer at clip-space `(x, y)` on the service
+// map and pin it there. Returns its layout position `[x, y]`.
+#[wasm_bindgen]
+pub fn drag_node(id: &str, x: f32, y: f32) -> Result<Vec<f32>, JsValue> {
+    let point = CAMERA.with(|c| c.borrow().unproject([x, y]));
+    let count = get_service_count();
+    let [x, y] = MapView::new(count).to_layout(point);
+    pin_node(id, x, y)?;
+    Ok(vec![x, y])
+}
+
+// The active job's pinned positions as versioned JSON, for `import_layout`
+#[wasm_bindgen]
+pub fn export_layout() -> String {
+    let pins = REPLAY_STATE.with(|r| r.borrow().pins());
+    console_log!("🧷 Exported layout with {} pinned services", pins.len());
+    PinnedLayout { pins, ..Default::default() }.to_json()
+}
+
+// Replace the active job's pins with a layout from `export_layout`. Pins for
+// services not seen yet apply when they arrive. Returns the number of pins.
+#[wasm_bindgen]
+pub fn import_layout(json: &str) -> Result<usize, JsValue> {
+    let layout = PinnedLayout::from_json(json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid layout: {}", e)))?;
+    let count = layout.pins.len();
+    if !REPLAY_STATE.with(|r| r.borrow_mut().set_pins(layout.pins)) {
+        return Err(JsValue::from_str("No active job to pin services in"));
+    }
+    console_log!("🧷 Imported layout with {} pinned services", count);
+    Ok(count)
+}
+
+// Playback rate: 2 rotates through services twice as fast
+#[wasm_bindgen]
+pub fn set_playback_speed(speed: f64) -> Result<(), JsValue> {
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(JsValue::from_str(&format!("Invalid playback speed: {}", speed)));
+    }
+    PLAYBACK_SPEED.with(|s| *s.borrow_mut() = speed);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn get_playback_speed() -> f64 {
+    PLAYBACK_SPEED.with(|s| *s.borrow())
+}
+
+// Serialize the view to versioned JSON bytes: camera, active job, filter,
+// playhead, speed, selection, user annotations, pinned positions, grouping
+// and label rules
+#[wasm_bindgen]
+pub fn save_session() -> Vec<u8> {
+    let mut session = REPLAY_STATE.with(|r| r.borrow().session());
+    session.camera = CAMERA.with(|c| *c.borrow());
+    session.speed = get_playback_speed();
+    let index = GPU_RENDERER.with(|r| r.borrow().as_ref().map_or(0, |renderer| renderer.current_service_index));
+    session.playhead_ms = index as f64 * SERVICE_DWELL_MS;
+    let (service, caption) = HIGHLIGHT.with(|h| h.borrow().clone()).unzip();
+    let trace = CRITICAL_PATH.with(|c| c.borrow().as_ref().map(|path| path.trace_id.clone()));
+    session.selection = Selection { service, caption, trace };
+    
+    let bytes = session.to_bytes();
+    console_log!("💾 Saved session v{} ({} bytes)", session.version, bytes.len());
+    bytes
+}
+
+// Restore a session from `save_session`, including ones written by older or
+// newer builds, and redraw. The session is checked before anything changes,
+// so a value no view can take, such as a zero speed, rejects it whole.
+// Returns `{ service_index, warnings: [{ kind, ... }] }`: the service at the
+// saved playhead so the rotation resumes there, and the parts that were
+// dropped, e.g. a job that is not loaded yet or a filter that no longer parses.
+#[wasm_bindgen]
+pub fn load_session(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let session = Session::from_bytes(bytes).map_err(js_error)?;
+    let warnings = REPLAY_STATE.with(|r| r.borrow_mut().restore_session(&session));
+    for warning in &warnings {
+        console_log!("⚠️ {}", warning);
+    }
+    CAMERA.with(|c| *c.borrow_mut() = session.camera);
+    UNFOCUSED_CAMERA.with(|u| *u.borrow_mut() = None);
+    PLAYBACK_SPEED.with(|s| *s.borrow_mut() = session.speed);
+    
+    let selection = session.selection;
+    let highlight = selection.service.map(|service| (service, selection.caption.unwrap_or_default()));
+    HIGHLIGHT.with(|h| *h.borrow_mut() = highlight);
+    let path = selection.trace.and_then(|trace_id| REPLAY_STATE.with(|r| r.borrow().critical_path(&trace_id).ok()));
+    CRITICAL_PATH.with(|c| *c.borrow_mut() = path);
+    
+    let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
+    let index = PlaybackClock::default().service_index(session.playhead_ms, services.len()).unwrap_or(0);
+    console_log!("📂 Loaded session: job {:?}, service {}/{}", session.job, index, services.len());
+    if services.is_empty() {
+        clear_canvas()?;
+    } else {
+        render_service_by_index(index)?;
+    }
+    serde_wasm_bindgen::to_value(&Restored { service_index: index, warnings })
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize session load: {:?}", e)))
 }
 
 // Start animation ONCE - called only when first services are available
@@ -831,9 +1253,7 @@ pub fn start_service_animation() -> Result<(), JsValue> {
         return Ok(());
     }
     
-    let services = REPLAY_DATA.with(|r| {
-        r.borrow().as_ref().map(|data| data.services.clone()).unwrap_or_default()
-    });
+    let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
     
     if services.is_empty() {
         return Err(JsValue::from_str("No replay data loaded"));
@@ -870,9 +1290,7 @@ pub fn start_service_animation() -> Result<(), JsValue> {
 pub fn render_replay() -> Result<(), JsValue> {
     console_log!("📊 Checking if animation should start or continue");
     
-    let services = REPLAY_DATA.with(|r| {
-        r.borrow().as_ref().map(|data| data.services.clone()).unwrap_or_default()
-    });
+    let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
     
     if services.is_empty() {
         return Err(JsValue::from_str("No replay data loaded"));
@@ -899,9 +1317,7 @@ pub fn render_replay() -> Result<(), JsValue> {
 // Get service count for worker animation control
 #[wasm_bindgen]
 pub fn get_service_count() -> usize {
-    REPLAY_DATA.with(|r| {
-        r.borrow().as_ref().map(|data| data.services.len()).unwrap_or(0)
-    })
+    REPLAY_STATE.with(|r| r.borrow().playback_services().len())
 }
 
 // Render specific service by index (bypassing internal animation)
@@ -909,9 +1325,7 @@ pub fn get_service_count() -> usize {
 pub fn render_service_by_index(service_index: usize) -> Result<(), JsValue> {
     console_log!("🎯 Worker-controlled render: service index {}", service_index);
     
-    let services = REPLAY_DATA.with(|r| {
-        r.borrow().as_ref().map(|data| data.services.clone()).unwrap_or_default()
-    });
+    let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
     
     if services.is_empty() {
         console_log!("❌ No services available to render");
@@ -920,6 +1334,11 @@ pub fn render_service_by_index(service_index: usize) -> Result<(), JsValue> {
     
     let actual_index = service_index % services.len();
     let service = &services[actual_index];
+    GPU_RENDERER.with(|r| {
+        if let Some(renderer) = r.borrow_mut().as_mut() {
+            renderer.current_service_index = actual_index;
+        }
+    });
     
     console_log!("🎯 Rendering service '{}' (index {}/{})", service.id, actual_index, services.len() - 1);
     
@@ -944,9 +1363,7 @@ fn start_self_managing_loop() {
 fn render_current_service() {
     console_log!("📍 ========== render_current_service() CALLED ==========");
     
-    let services = REPLAY_DATA.with(|r| {
-        r.borrow().as_ref().map(|data| data.services.clone()).unwrap_or_default()
-    });
+    let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
     
     if services.is_empty() { 
         console_log!("❌ No services to render");
@@ -990,9 +1407,7 @@ fn schedule_next_service_transition() {
         }
         
         // Get fresh service data - this picks up new services automatically
-        let services = REPLAY_DATA.with(|r| {
-            r.borrow().as_ref().map(|data| data.services.clone()).unwrap_or_default()
-        });
+        let services = REPLAY_STATE.with(|r| r.borrow().playback_services());
         
         if services.is_empty() {
             console_log!("❌ No services available, stopping animation");
@@ -1022,18 +1437,18 @@ fn schedule_next_service_transition() {
         render_current_service();
         
         // Continue the animation loop if we have services
-        if services.len() > 0 {
+        if !services.is_empty() {
             schedule_next_service_transition();
         } else {
             console_log!("❌ No more services, stopping animation");
         }
     }) as Box<dyn FnMut()>);
     
-    // Schedule transition after 10 seconds - DON'T forget the closure
+    // Schedule transition after one dwell period - DON'T forget the closure
     let window = window().unwrap();
     let timeout_id = window.set_timeout_with_callback_and_timeout_and_arguments_0(
         closure.as_ref().unchecked_ref(), 
-        10000 // 10 seconds
+        SERVICE_DWELL_MS as i32 // 10 seconds
     ).unwrap();
     
     console_log!("🕐 Timeout {} scheduled for 10 seconds", timeout_id);
@@ -1058,144 +1473,49 @@ pub fn stop_animation() -> Result<(), JsValue> {
 // Remove the old alpha rendering function - no longer needed
 
 fn render_service_node(service: &ServiceNode) -> Result<(), JsValue> {
-    // Create geometry for this specific service node
-    let vertices = create_service_geometry(&service.id, service.x, service.y, &service.status);
-    
-    GPU_RENDERER.with(|r| {
-        let renderer_ref = r.borrow();
-        let renderer = renderer_ref.as_ref()
-            .ok_or_else(|| JsValue::from_str("WebGPU not initialized"))?;
-        
-        // Create vertex buffer for this service
-        let vertex_buffer = renderer.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Service {} Vertex Buffer", service.id)),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: BufferUsages::VERTEX,
-        });
-        
-        // Get current surface texture
-        let output = renderer.surface.get_current_texture()
-            .map_err(|e| JsValue::from_str(&format!("Failed to get surface texture: {:?}", e)))?;
-        let view = output.texture.create_view(&TextureViewDescriptor::default());
-        
-        // Create command encoder
-        let mut encoder = renderer.device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("Service Render Encoder"),
-        });
-        
-        {
-            // Begin render pass with CLEAR - remove previous service text
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Service Render Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        }), // CLEAR the canvas for each service
-                        store: StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-            
-            render_pass.set_pipeline(&renderer.render_pipeline);
-            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            let vertex_count = (vertices.len() / 2) as u32;
-            render_pass.draw(0..vertex_count, 0..1);
-        }
-        
-        // Submit commands and present
-        renderer.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        
-        console_log!("🎨 Rendered service '{}' with CLEAR", service.id);
-        
-        Ok(())
-    })
-}
-
-fn create_service_geometry(service_id: &str, _x: f32, _y: f32, _status: &str) -> Vec<f32> {
-    // Render a clean service identifier instead of full service ID
-    let clean_id = extract_service_number(service_id);
-    
-    // Center the service display
-    let centered_x = 0.0; // Always center horizontally
-    let centered_y = 0.0; // Always center vertically
-    
-    console_log!("🎯 Creating geometry for service '{}' -> clean ID: '{}' at ({}, {})", 
-                service_id, clean_id, centered_x, centered_y);
-    
-    create_text_geometry(&clean_id, centered_x, centered_y)
-}
-
-fn extract_service_number(service_id: &str) -> String {
-    // Extract just the number/identifier part for cleaner display
-    if service_id.starts_with("job-") {
-        // For "job-abc123" -> "abc123" 
-        service_id.strip_prefix("job-").unwrap_or(service_id).to_string()
-    } else if service_id.len() > 8 {
-        // For very long IDs, take last 8 characters
-        service_id.chars().rev().take(8).collect::<String>().chars().rev().collect()
-    } else {
-        // Use the ID as-is if it's short
-        service_id.to_string()
+    // Build the scene for this specific service node
+    let (filter, labels) = REPLAY_STATE.with(|r| {
+        let state = r.borrow();
+        (state.filter_match().cloned(), state.labels().clone())
+    });
+    console_log!("🎯 Creating scene for service '{}' -> label: '{}'", service.id, labels.label(&service.id));
+    
+    let theme = THEME.with(|t| *t.borrow());
+    let mut service_scene = scene::filtered_service_scene(service, filter.as_ref(), &labels, &theme);
+    // A search highlight wins over the critical path's
+    let caption = HIGHLIGHT.with(|h| match h.borrow().as_ref() {
+        Some((highlighted, caption)) if *highlighted == service.id => Some(caption.clone()),
+        _ => None,
+    });
+    let caption = caption.or_else(|| CRITICAL_PATH.with(|c| {
+        let path = c.borrow();
+        let share = path.as_ref()?.services.iter().find(|share| share.service == service.id)?;
+        Some(format!("CRITICAL {:.1}MS", share.time_ns as f64 / 1_000_000.0))
+    }));
+    if let Some(caption) = caption {
+        scene::highlight_service(&mut service_scene, service, &caption, &labels, &theme);
     }
+    present_scene(service_scene, &format!("Service {}", service.id))?;
+    
+    console_log!("🎨 Rendered service '{}' with CLEAR", service.id);
+    Ok(())
 }
 
 // Clear the canvas for new replay rendering
 #[wasm_bindgen]
 pub fn clear_canvas() -> Result<(), JsValue> {
-    GPU_RENDERER.with(|r| {
-        let renderer_ref = r.borrow();
-        let renderer = renderer_ref.as_ref()
-            .ok_or_else(|| JsValue::from_str("WebGPU not initialized"))?;
-        
-        // Get current surface texture
-        let output = renderer.surface.get_current_texture()
-            .map_err(|e| JsValue::from_str(&format!("Failed to get surface texture: {:?}", e)))?;
-        let view = output.texture.create_view(&TextureViewDescriptor::default());
-        
-        // Create command encoder
-        let mut encoder = renderer.device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("Clear Encoder"),
-        });
-        
-        {
-            // Clear the canvas
-            let _render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Clear Pass"),
-                color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        }),
-                        store: StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-        }
-        
-        // Submit commands and present
-        renderer.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        
-        Ok(())
-    })
+    present_scene(Scene::new(THEME.with(|t| t.borrow().background)), "Clear")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_that_cannot_multisample_draw_single_sampled() {
+        let x4 = TextureFormatFeatureFlags::MULTISAMPLE_X2 | TextureFormatFeatureFlags::MULTISAMPLE_X4;
+        assert_eq!(sample_count(x4), MSAA_SAMPLES);
+        assert_eq!(sample_count(TextureFormatFeatureFlags::MULTISAMPLE_X2), 1);
+        assert_eq!(sample_count(TextureFormatFeatureFlags::empty()), 1);
+    }
 }